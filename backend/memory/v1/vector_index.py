@@ -629,8 +629,12 @@ class VectorIndex:
         logging.info(f"[VectorIndex] 🔄 Recovering {table_type} for \"{diary_name}\" via Rust...")
         try:
             db_path = self.config.store_path / "emotional_companionship.db"
-            count = idx.recover_from_sqlite(str(db_path), table_type, diary_name)
-            logging.info(f"[VectorIndex] ✅ Recovered {count} vectors via Rust")
+            report = idx.recover_from_sqlite(str(db_path), table_type, diary_name)
+            count = report.inserted
+            logging.info(
+                f"[VectorIndex] ✅ Recovered {count} vectors via Rust "
+                f"(excluded {report.excluded}, unreadable rows {report.skipped_row_errors})"
+            )
 
             # 如果 Rust 恢复返回 0 条，尝试手动恢复
             if count == 0:
@@ -1,11 +1,80 @@
+//! Vexus-Lite 向量索引 (PyO3 扩展)
+//!
+//! 字节序: 所有以 `bytes` 传入的 f32 向量默认按小端 (与 x86 / ARM 上的 numpy、SQLite BLOB 一致) 直接重解释。
+//! 写入类方法 (add / add_batch / recover_from_sqlite / recover_from_sqlite_multi) 提供 byte_order="be"，
+//! 对来自大端机器的数据逐个交换 f32 的字节后再使用；其余方法只接受本机 (小端) 字节。
+
 #![deny(clippy::all)]
 #![allow(unsafe_op_in_unsafe_fn)]
 
+use hashbrown::HashSet;
+use numpy::ndarray::Array2;
+use numpy::{IntoPyArray, PyArray1, PyArray2, PyReadwriteArray1};
 use pyo3::prelude::*;
-use pyo3::types::PyType;
-use std::sync::{Arc, RwLock};
+use pyo3::types::{PyBytes, PyDict, PyType};
+use rayon::prelude::*;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
 use usearch::Index;
-use rusqlite::Connection;
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+#[cfg(any(test, feature = "testing"))]
+mod testing;
+
+// 异常层级: 运行时错误继承 VexusError (其本身继承 RuntimeError, 兼容旧的 except RuntimeError)；
+// 参数/查找类错误继承原先抛出的内置异常 (ValueError / KeyError / ...)，旧的 except 写法仍然有效
+pyo3::create_exception!(vector_db, VexusError, pyo3::exceptions::PyRuntimeError, "Vexus 索引错误基类");
+pyo3::create_exception!(vector_db, RemoveError, VexusError, "删除失败 (重试后仍失败)");
+pyo3::create_exception!(vector_db, IndexClosedError, VexusError, "索引已 close, 不能再操作");
+pyo3::create_exception!(vector_db, SqliteError, VexusError, "SQLite 打开/查询失败");
+pyo3::create_exception!(vector_db, CorruptFileError, VexusError, "索引或侧车文件无法解析");
+pyo3::create_exception!(vector_db, StorageError, VexusError, "文件读写失败");
+pyo3::create_exception!(vector_db, DimensionMismatchError, pyo3::exceptions::PyValueError, "向量/缓冲区维度不符");
+pyo3::create_exception!(vector_db, InvalidArgumentError, pyo3::exceptions::PyValueError, "参数不合法");
+pyo3::create_exception!(vector_db, NotFoundError, pyo3::exceptions::PyKeyError, "ID 或版本不存在");
+pyo3::create_exception!(vector_db, CapacityError, pyo3::exceptions::PyMemoryError, "容量不足或超出内存上限");
+pyo3::create_exception!(vector_db, VexusTimeoutError, pyo3::exceptions::PyTimeoutError, "等待索引锁/搜索超时");
+pyo3::create_exception!(vector_db, MissingFileError, pyo3::exceptions::PyFileNotFoundError, "文件不存在");
+pyo3::create_exception!(vector_db, CursorExpiredError, InvalidArgumentError, "分页游标过期或与查询不符，需从第一页重新开始");
+pyo3::create_exception!(vector_db, ReadOnlyIndexError, VexusError, "只读共享视图不能修改，或 save 的目标正被共享视图使用");
+
+/// 稳定的错误码 → 异常类型，作为类属性 `code` 挂在异常上 (e.code)，并以 ERROR_CODES 暴露给 Python
+/// 只允许追加: 已发布的码不能改名、删除或改挂到别的异常上
+fn error_codes(py: Python<'_>) -> Vec<(&'static str, Bound<'_, PyType>)> {
+    vec![
+        ("E_INTERNAL", py.get_type_bound::<VexusError>()),
+        ("E_REMOVE", py.get_type_bound::<RemoveError>()),
+        ("E_INDEX_CLOSED", py.get_type_bound::<IndexClosedError>()),
+        ("E_DIM_MISMATCH", py.get_type_bound::<DimensionMismatchError>()),
+        ("E_CAPACITY", py.get_type_bound::<CapacityError>()),
+        ("E_LOCK_TIMEOUT", py.get_type_bound::<VexusTimeoutError>()),
+        ("E_SQLITE", py.get_type_bound::<SqliteError>()),
+        ("E_CORRUPT_FILE", py.get_type_bound::<CorruptFileError>()),
+        ("E_IO", py.get_type_bound::<StorageError>()),
+        ("E_FILE_NOT_FOUND", py.get_type_bound::<MissingFileError>()),
+        ("E_INVALID_ARGUMENT", py.get_type_bound::<InvalidArgumentError>()),
+        ("E_NOT_FOUND", py.get_type_bound::<NotFoundError>()),
+        ("E_CURSOR_EXPIRED", py.get_type_bound::<CursorExpiredError>()),
+        ("E_READ_ONLY", py.get_type_bound::<ReadOnlyIndexError>()),
+    ]
+}
+
+/// 删除时遇到瞬时错误的最大重试次数
+const REMOVE_MAX_RETRIES: u32 = 3;
+
+/// 构造时未指定 max_results 时的单次搜索结果数上限
+const DEFAULT_MAX_RESULTS: u32 = 100_000;
+
+/// open_shared 打开的文件 (规范化路径 → 本进程内打开的句柄数)，save 拒绝覆盖其中的文件
+static SHARED_VIEWS: Mutex<BTreeMap<PathBuf, usize>> = Mutex::new(BTreeMap::new());
+
+/// 命名空间占 u64 key 的高 8 位；打包时表内 ID 限制在 u32 范围 (见 pack_id)
+const NAMESPACE_SHIFT: u32 = 56;
+const NAMESPACE_ID_MASK: u64 = (1 << NAMESPACE_SHIFT) - 1;
 
 /// 搜索结果 (返回 ID 而非 Tag 文本)
 /// 上层 Python 会拿着 ID 去 SQLite 里查具体的文本内容
@@ -13,8 +82,28 @@ use rusqlite::Connection;
 pub struct SearchResult {
     #[pyo3(get, set)]
     pub id: u32,
+    /// 命名空间 (key 高 8 位)，未使用 id_namespace 写入的向量为 0
+    #[pyo3(get, set)]
+    pub namespace: u8,
     #[pyo3(get, set)]
     pub score: f64,
+    /// 多跳搜索中距起点的跳数 (普通搜索恒为 0)
+    #[pyo3(get, set)]
+    pub hop: u32,
+    /// 调试信息: 带 require_labels 的 search 实际采用的策略 ("exact" / "filtered_hnsw")，其余情况为 None
+    #[pyo3(get)]
+    pub strategy: Option<String>,
+    /// search(raw=True) 时为 usearch 返回的原始距离 (未经分数变换)，其余情况为 None
+    #[pyo3(get)]
+    pub distance: Option<f64>,
+}
+
+impl SearchResult {
+    /// 从索引 key 构造结果，拆出命名空间与表内 ID
+    fn from_key(key: u64, score: f64, hop: u32) -> Self {
+        let (namespace, id) = unpack_id(key);
+        Self { id: id as u32, namespace, score, hop, strategy: None, distance: None }
+    }
 }
 
 #[pyclass]
@@ -27,6 +116,9 @@ pub struct SvdResult {
     pub k: u32,
     #[pyo3(get, set)]
     pub dim: u32,
+    /// 近似误差上界 (compute_svd_streaming 的累计收缩量)，精确分解为 None
+    #[pyo3(get, set)]
+    pub error_bound: Option<f64>,
 }
 
 #[pyclass]
@@ -37,6 +129,9 @@ pub struct OrthogonalProjectionResult {
     pub residual: Vec<f64>,
     #[pyo3(get, set)]
     pub basis_coefficients: Vec<f64>,
+    /// skip_zero_vectors=true 时被去掉的全零 tag 下标 (原始编号)，basis_coefficients 不含这些 tag
+    #[pyo3(get, set)]
+    pub skipped_rows: Vec<u32>,
 }
 
 #[pyclass]
@@ -45,6 +140,17 @@ pub struct HandshakeResult {
     pub magnitudes: Vec<f64>,
     #[pyo3(get, set)]
     pub directions: Vec<f64>,
+    /// 以下仅在 extended=true 时填充，否则为 None
+    /// magnitude / tag 模长；零模长 tag 为 +inf
+    #[pyo3(get, set)]
+    pub relative_magnitudes: Option<Vec<f64>>,
+    #[pyo3(get, set)]
+    pub query_norm: Option<f64>,
+    #[pyo3(get, set)]
+    pub tag_norms: Option<Vec<f64>>,
+    /// skip_zero_vectors=true 时被去掉的全零 tag 下标 (原始编号)，其余字段不含这些 tag
+    #[pyo3(get, set)]
+    pub skipped_rows: Vec<u32>,
 }
 
 #[pyclass]
@@ -59,6 +165,25 @@ pub struct ProjectResult {
     pub total_energy: f64,
 }
 
+/// 语义轨迹: 按时间排序的一串向量在嵌入空间中的运动特征
+#[pyclass]
+pub struct TrajectoryResult {
+    /// 相邻两点的距离 (长度 n-1)
+    #[pyo3(get)]
+    pub velocities: Vec<f64>,
+    /// 速度的一阶差分 (长度 n-2)
+    #[pyo3(get)]
+    pub accelerations: Vec<f64>,
+    #[pyo3(get)]
+    pub total_path_length: f64,
+    /// 首尾两点的距离
+    #[pyo3(get)]
+    pub net_displacement: f64,
+    /// 1 - mean(|加速度|) / mean(速度)，匀速为 1，静止不动时也记为 1
+    #[pyo3(get)]
+    pub smoothness: f64,
+}
+
 /// 统计信息
 #[pyclass]
 pub struct VexusStats {
@@ -72,522 +197,6487 @@ pub struct VexusStats {
     pub memory_usage: u32,
 }
 
-/// 核心索引结构 (无状态，只存向量)
+/// 单个命名空间的统计 (见 DetailedStats)
 #[pyclass]
-pub struct VexusIndex {
-    index: Arc<RwLock<Index>>,
-    dimensions: u32,
+#[derive(Clone)]
+pub struct NamespaceStats {
+    #[pyo3(get)]
+    pub vectors: u32,
+    /// 按向量数比例分摊的索引内存 (近似)
+    #[pyo3(get)]
+    pub memory_bytes: u64,
+    /// 带时间戳 (set_side_data) 的向量数
+    #[pyo3(get)]
+    pub timestamped: u32,
 }
 
-#[pymethods]
-impl VexusIndex {
-    /// 创建新的空索引
-    #[new]
-    pub fn new(dim: u32, capacity: u32) -> PyResult<Self> {
-        let index = Index::new(&usearch::IndexOptions {
-            dimensions: dim as usize,
-            metric: usearch::MetricKind::L2sq,
-            quantization: usearch::ScalarKind::F32,
-            connectivity: 16,
-            expansion_add: 128,
-            expansion_search: 64,
-            multi: false,
-        })
-        .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("Failed to create index: {:?}", e)))?;
-
-        index
-            .reserve(capacity as usize)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("Failed to reserve capacity: {:?}", e)))?;
+/// 按命名空间细分的统计，只读登记的 key 与附加数据，不扫描索引
+#[pyclass]
+pub struct DetailedStats {
+    #[pyo3(get)]
+    pub total_vectors: u32,
+    #[pyo3(get)]
+    pub memory_usage: u64,
+    #[pyo3(get)]
+    pub timestamped: u32,
+    /// 命名空间 → 统计；未使用 id_namespace 写入的向量计在 0 下
+    #[pyo3(get)]
+    pub namespaces: HashMap<u8, NamespaceStats>,
+}
 
-        Ok(Self {
-            index: Arc::new(RwLock::new(index)),
-            dimensions: dim,
-        })
+#[pymethods]
+impl DetailedStats {
+    /// 转为嵌套 dict，便于日志输出
+    pub fn as_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let namespaces = PyDict::new_bound(py);
+        for (ns, st) in &self.namespaces {
+            let d = PyDict::new_bound(py);
+            d.set_item("vectors", st.vectors)?;
+            d.set_item("memory_bytes", st.memory_bytes)?;
+            d.set_item("timestamped", st.timestamped)?;
+            namespaces.set_item(ns, d)?;
+        }
+        let out = PyDict::new_bound(py);
+        out.set_item("total_vectors", self.total_vectors)?;
+        out.set_item("memory_usage", self.memory_usage)?;
+        out.set_item("timestamped", self.timestamped)?;
+        out.set_item("namespaces", namespaces)?;
+        Ok(out)
     }
+}
 
-    /// 从磁盘加载索引
-    #[classmethod]
-    #[pyo3(signature = (dim, capacity, index_path, _unused_map_path=None))]
-    pub fn load(_cls: &Bound<'_, PyType>, dim: u32, capacity: u32, index_path: String, _unused_map_path: Option<String>) -> PyResult<Self> {
-        let index = Index::new(&usearch::IndexOptions {
-            dimensions: dim as usize,
-            metric: usearch::MetricKind::L2sq,
-            quantization: usearch::ScalarKind::F32,
-            connectivity: 16,
-            expansion_add: 128,
-            expansion_search: 64,
-            multi: false,
-        })
-        .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("Failed to create index wrapper: {:?}", e)))?;
+/// recommended_options 给出的 HNSW 参数
+#[pyclass]
+pub struct RecommendedOptions {
+    #[pyo3(get)]
+    pub connectivity: u32,
+    #[pyo3(get)]
+    pub expansion_add: u32,
+    #[pyo3(get)]
+    pub expansion_search: u32,
+}
 
-        index.load(&index_path)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("Failed to load index from disk: {:?}", e)))?;
+/// 索引配置 (info() 返回)
+#[pyclass]
+pub struct IndexInfo {
+    #[pyo3(get)]
+    pub dimensions: u32,
+    #[pyo3(get)]
+    pub metric: String,
+    #[pyo3(get)]
+    pub quantization: String,
+    #[pyo3(get)]
+    pub connectivity: u32,
+    #[pyo3(get)]
+    pub expansion_add: u32,
+    #[pyo3(get)]
+    pub expansion_search: u32,
+    #[pyo3(get)]
+    pub value_bounds: Option<(f32, f32)>,
+}
 
-        let current_capacity = index.capacity();
-        if capacity as usize > current_capacity {
-            index
-                .reserve(capacity as usize)
-                .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("Failed to expand capacity: {:?}", e)))?;
-        }
+/// 查询日志中的一条搜索记录 (enable_query_log 开启后记录)
+#[pyclass]
+#[derive(Clone, Serialize)]
+pub struct QueryLogEntry {
+    /// Unix 时间 (秒)
+    #[pyo3(get)]
+    pub timestamp: u64,
+    /// 搜索方法名，如 "search" / "search_robust"
+    #[pyo3(get)]
+    pub method: String,
+    #[pyo3(get)]
+    pub k: u32,
+    /// 其余参数的摘要
+    #[pyo3(get)]
+    pub options: String,
+    #[pyo3(get)]
+    pub ids: Vec<u32>,
+    #[pyo3(get)]
+    pub scores: Vec<f64>,
+    /// 查询向量，仅 store_vectors=true 时记录
+    #[pyo3(get)]
+    pub query: Option<Vec<f32>>,
+}
 
-        Ok(Self {
-            index: Arc::new(RwLock::new(index)),
-            dimensions: dim,
-        })
-    }
+/// 最近 N 次搜索的环形缓冲区
+struct QueryLog {
+    capacity: usize,
+    store_vectors: bool,
+    entries: VecDeque<QueryLogEntry>,
+}
 
-    /// 保存索引到磁盘
-    pub fn save(&self, index_path: String) -> PyResult<()> {
-        let index = self.index.read()
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("Lock failed: {}", e)))?;
+/// search_paged 的一个分页会话: 首页调用时的候选快照，按 (距离, key) 升序
+struct PageSession {
+    query_hash: u64,
+    candidates: Vec<(u64, f32)>,
+    created: std::time::Instant,
+}
 
-        let temp_path = format!("{}.tmp", index_path);
+#[derive(Default)]
+struct PageCache {
+    next_id: u64,
+    sessions: HashMap<u64, PageSession>,
+}
 
-        index
-            .save(&temp_path)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("Failed to save index: {:?}", e)))?;
+/// search_paged 首页最多取这么多候选，能翻到的结果总数以此为上限
+const PAGE_CANDIDATE_CAP: usize = 1000;
+/// 分页会话的存活时间
+const PAGE_SESSION_TTL: std::time::Duration = std::time::Duration::from_secs(300);
+/// 同时保留的分页会话数，超出时淘汰最早的
+const PAGE_MAX_SESSIONS: usize = 64;
+/// 游标: 会话 ID u64 + 查询哈希 u64 + 上一条的距离 f32 + 上一条的 key u64 (小端)
+const PAGE_CURSOR_LEN: usize = 28;
+
+/// self_check 的结果: 各项检查是否通过及细节
+/// failures 为失败项的可读描述，可直接写日志；passed=false 时应改走 SQLite 恢复
+#[pyclass]
+pub struct IntegrityReport {
+    /// 全部检查通过
+    #[pyo3(get)]
+    pub passed: bool,
+    /// usearch size() 与侧车登记的 ID 数一致
+    #[pyo3(get)]
+    pub size_ok: bool,
+    #[pyo3(get)]
+    pub index_size: u64,
+    #[pyo3(get)]
+    pub key_count: u64,
+    /// 索引文件的维度与配置一致
+    #[pyo3(get)]
+    pub dimensions_ok: bool,
+    /// 抽样的 ID 都能取回向量且分量均为有限值
+    #[pyo3(get)]
+    pub vectors_ok: bool,
+    #[pyo3(get)]
+    pub sampled: u32,
+    /// 登记了但取不回向量的 key (含命名空间)，升序
+    #[pyo3(get)]
+    pub missing_keys: Vec<u64>,
+    /// 存储的向量含 NaN / inf 的 key，升序
+    #[pyo3(get)]
+    pub non_finite_keys: Vec<u64>,
+    /// 抽样查询都完成且返回了结果 (没有可用的查询向量时视为通过)
+    #[pyo3(get)]
+    pub queries_ok: bool,
+    #[pyo3(get)]
+    pub queries_run: u32,
+    #[pyo3(get)]
+    pub query_errors: Vec<String>,
+    #[pyo3(get)]
+    pub failures: Vec<String>,
+}
 
-        std::fs::rename(&temp_path, &index_path)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("Failed to rename index file: {}", e)))?;
+/// 向量库健康指标 (抽样估计)
+#[pyclass]
+pub struct CorpusHealth {
+    #[pyo3(get)]
+    pub sample_size: u32,
+    #[pyo3(get)]
+    pub mean_norm: f64,
+    #[pyo3(get)]
+    pub norm_variance: f64,
+    /// 模长低于 1e-6 的向量比例
+    #[pyo3(get)]
+    pub near_zero_fraction: f64,
+    /// 样本两两余弦相似度的均值 (越接近 1 说明嵌入越坍缩)
+    #[pyo3(get)]
+    pub mean_pairwise_cosine: f64,
+    /// 有效维度: 样本协方差谱的参与率 (Σλ)² / Σλ²
+    #[pyo3(get)]
+    pub effective_dim: f64,
+}
 
-        Ok(())
+#[pymethods]
+impl CorpusHealth {
+    /// 转为 dict，便于日志输出
+    pub fn as_dict(&self) -> HashMap<String, f64> {
+        HashMap::from([
+            ("sample_size".to_string(), self.sample_size as f64),
+            ("mean_norm".to_string(), self.mean_norm),
+            ("norm_variance".to_string(), self.norm_variance),
+            ("near_zero_fraction".to_string(), self.near_zero_fraction),
+            ("mean_pairwise_cosine".to_string(), self.mean_pairwise_cosine),
+            ("effective_dim".to_string(), self.effective_dim),
+        ])
     }
+}
 
-    /// 单个添加
-    pub fn add(&self, id: u32, vector: Vec<u8>) -> PyResult<()> {
-        let index = self.index.write()
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("Lock failed: {}", e)))?;
-
-        let vec_slice: &[f32] = unsafe {
-            std::slice::from_raw_parts(
-                vector.as_ptr() as *const f32,
-                vector.len() / std::mem::size_of::<f32>(),
-            )
-        };
+/// 多探针鲁棒搜索结果
+#[pyclass]
+pub struct RobustSearchResult {
+    #[pyo3(get)]
+    pub id: u32,
+    /// 命名空间 (key 高 8 位)，同 SearchResult.namespace
+    #[pyo3(get)]
+    pub namespace: u8,
+    /// 稳定性加权分数 = 平均分数 * stability
+    #[pyo3(get)]
+    pub score: f64,
+    /// 在所有探针 (含原始查询) 中出现的比例
+    #[pyo3(get)]
+    pub stability: f64,
+    /// 出现时的平均名次 (从 0 开始)
+    #[pyo3(get)]
+    pub mean_rank: f64,
+}
 
-        if vec_slice.len() != self.dimensions as usize {
-            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
-                "Dimension mismatch: expected {}, got {}",
-                self.dimensions,
-                vec_slice.len()
-            )));
-        }
+/// 预热报告 (各次合成查询的耗时)
+#[pyclass]
+pub struct WarmupReport {
+    #[pyo3(get)]
+    pub n_queries: u32,
+    #[pyo3(get)]
+    pub latencies_ms: Vec<f64>,
+    #[pyo3(get)]
+    pub first_ms: f64,
+    /// 后一半查询的平均耗时
+    #[pyo3(get)]
+    pub steady_ms: f64,
+    /// first_ms / steady_ms，大于 1 说明预热有效
+    #[pyo3(get)]
+    pub improvement: f64,
+}
 
-        if index.size() + 1 >= index.capacity() {
-             let new_cap = (index.capacity() as f64 * 1.5) as usize;
-             let _ = index.reserve(new_cap);
-        }
+/// 单条检索结果的逐维度解释
+#[pyclass]
+pub struct ExplainResult {
+    #[pyo3(get)]
+    pub id: u64,
+    /// 贡献最大的维度下标 (按贡献绝对值降序)
+    #[pyo3(get)]
+    pub dims: Vec<u32>,
+    /// 对应维度的贡献值 -(q_d - v_d)^2
+    #[pyo3(get)]
+    pub contributions: Vec<f64>,
+    /// 全部维度贡献之和 (即 -L2sq 距离)
+    #[pyo3(get)]
+    pub total: f64,
+    #[pyo3(get)]
+    pub score: f64,
+}
 
-        index
-            .add(id as u64, vec_slice)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("Add failed: {:?}", e)))?;
+/// 恢复报告 (recover_from_sqlite / recover_from_sqlite_multi)
+#[pyclass]
+#[derive(Default)]
+pub struct RecoverReport {
+    #[pyo3(get)]
+    pub inserted: u32,
+    #[pyo3(get)]
+    pub skipped_dim_mismatch: u32,
+    /// 读取失败的行数 (如 vector 列不是 BLOB、id 不是整数)
+    #[pyo3(get)]
+    pub skipped_row_errors: u32,
+    /// 被 include_ids / exclude_ids 过滤掉的行数
+    #[pyo3(get)]
+    pub excluded: u32,
+    #[pyo3(get)]
+    pub files_processed: u32,
+    /// (文件路径, 错误信息)
+    #[pyo3(get)]
+    pub failed_files: Vec<(String, String)>,
+    #[pyo3(get)]
+    pub growth_events: Vec<GrowthEvent>,
+}
 
-        Ok(())
+impl RecoverReport {
+    /// 累加一个文件的恢复计数
+    fn absorb(&mut self, counts: RecoverCounts) {
+        self.inserted += counts.inserted;
+        self.skipped_dim_mismatch += counts.skipped_dim_mismatch;
+        self.skipped_row_errors += counts.skipped_row_errors;
+        self.excluded += counts.excluded;
+        self.files_processed += 1;
+        self.growth_events.extend(counts.growth_events);
     }
 
-    /// 批量添加
-    pub fn add_batch(&self, ids: Vec<u32>, vectors: Vec<u8>) -> PyResult<()> {
-        let index = self.index.write()
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("Lock failed: {}", e)))?;
+    /// 扩容事件记为 debug，维度不符被跳过的行记为 warning
+    fn log(&self, py: Python<'_>, method: &str, dimensions: u32) {
+        log_growth_events(py, method, &self.growth_events);
+        if self.skipped_dim_mismatch > 0 {
+            log_to_python(py, LOG_WARNING, || {
+                format!(
+                    "{}: skipped {} vectors due to dimension mismatch (expected {} bytes)",
+                    method,
+                    self.skipped_dim_mismatch,
+                    dimensions as usize * std::mem::size_of::<f32>()
+                )
+            });
+        }
+    }
+}
 
-        let count = ids.len();
-        let dim = self.dimensions as usize;
+/// verify_against_sqlite 的比对结果
+#[pyclass]
+pub struct VerifyReport {
+    /// 实际比对哈希的 ID 数 (SQLite 与索引共有、且登记过哈希)
+    #[pyo3(get)]
+    pub checked: u32,
+    /// 索引中记录的哈希与 SQLite BLOB 不一致的 ID，升序
+    #[pyo3(get)]
+    pub mismatched: Vec<u32>,
+    /// 共有但没有登记哈希的 ID 数 (开启哈希登记之前写入、且未回填)
+    #[pyo3(get)]
+    pub unhashed: u32,
+}
 
-        let vec_slice: &[f32] = unsafe {
-            std::slice::from_raw_parts(
-                vectors.as_ptr() as *const f32,
-                vectors.len() / std::mem::size_of::<f32>(),
-            )
-        };
+/// search_adaptive 的截断依据
+#[pyclass]
+pub struct AdaptiveCutoff {
+    /// 建议保留的结果数 (即返回的结果数)
+    #[pyo3(get)]
+    pub suggested_k: u32,
+    #[pyo3(get)]
+    pub method: String,
+    /// 相邻距离差 distances[i + 1] - distances[i]
+    #[pyo3(get)]
+    pub gaps: Vec<f64>,
+    /// 每个截断位置 i (保留前 i + 1 个) 的判据值，suggested_k - 1 处取最大；
+    /// elbow 为相对跳变 gaps[i] / (最大距离 - 最小距离)，threshold_knee 为归一化曲线的 x - y
+    #[pyo3(get)]
+    pub criterion: Vec<f64>,
+}
 
-        if vec_slice.len() != count * dim {
-             return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("Batch size mismatch".to_string()));
-        }
+/// 一次容量扩容 (reserve) 事件
+#[pyclass]
+#[derive(Clone)]
+pub struct GrowthEvent {
+    #[pyo3(get)]
+    pub old_capacity: u64,
+    #[pyo3(get)]
+    pub new_capacity: u64,
+    #[pyo3(get)]
+    pub elapsed_ms: f64,
+}
 
-        if index.size() + count >= index.capacity() {
-            let new_cap = ((index.size() + count) as f64 * 1.5) as usize;
-            let _ = index.reserve(new_cap);
-        }
+/// HNSW 图连通性抽样
+/// 对抽样节点读取其实际层级与第 0 层邻接表 (level_of_key / neighbors),
+/// 并用自身向量搜索检查能否找回自己
+#[pyclass]
+pub struct GraphStats {
+    #[pyo3(get)]
+    pub n_nodes: u64,
+    /// 构建时的 connectivity (M)
+    #[pyo3(get)]
+    pub connectivity: u32,
+    /// 抽样节点第 0 层的平均邻居数 (实测)
+    #[pyo3(get)]
+    pub avg_degree: f64,
+    /// 层数 (max_level + 1)，空索引为 0
+    #[pyo3(get)]
+    pub n_levels: u32,
+    /// 抽样节点中的最高层编号；只出现在少数节点上的高层可能抽不到
+    #[pyo3(get)]
+    pub max_level: u32,
+    /// 抽样节点中以自身为 top-1 的比例, 明显低于 1 说明图连通性不足
+    #[pyo3(get)]
+    pub self_recall: f64,
+    #[pyo3(get)]
+    pub sample_size: u32,
+}
 
-        for (i, id) in ids.iter().enumerate() {
-            let start = i * dim;
-            let v = &vec_slice[start..start+dim];
-            index.add(*id as u64, v)
-                .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("Batch add failed idx {}: {:?}", i, e)))?;
+/// 分数校准参数
+#[pyclass]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CalibrationInfo {
+    /// 随机向量对距离的经验分位点 (0%..100%，共 101 个)
+    #[pyo3(get)]
+    pub quantiles: Vec<f64>,
+    #[pyo3(get)]
+    pub sample_size: u32,
+    #[pyo3(get)]
+    pub seed: u64,
+    #[pyo3(get)]
+    pub n_pairs: u64,
+}
+
+impl CalibrationInfo {
+    /// 距离 -> [0,1] 置信度: 1 - 经验 CDF(距离)，分位点之间线性插值，单调不增
+    fn apply(&self, dist: f64) -> f64 {
+        let q = &self.quantiles;
+        let n = q.len();
+        if n < 2 || dist <= q[0] {
+            return 1.0;
+        }
+        if dist >= q[n - 1] {
+            return 0.0;
         }
 
-        Ok(())
+        let pos = q.partition_point(|&x| x <= dist);
+        let (lo, hi) = (q[pos - 1], q[pos]);
+        let frac = if hi > lo { (dist - lo) / (hi - lo) } else { 0.0 };
+        let cdf = ((pos - 1) as f64 + frac) / (n - 1) as f64;
+
+        (1.0 - cdf).clamp(0.0, 1.0)
     }
+}
 
-    /// 搜索
-    pub fn search(&self, query: Vec<u8>, k: u32) -> PyResult<Vec<SearchResult>> {
-        let index = self.index.read()
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("Lock failed: {}", e)))?;
-
-        let query_slice: &[f32] = unsafe {
-            std::slice::from_raw_parts(
-                query.as_ptr() as *const f32,
-                query.len() / std::mem::size_of::<f32>(),
-            )
-        };
+/// 索引生命周期统计 (随 save/load 持久化)
+#[pyclass]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LifetimeStats {
+    /// 创建时间 (Unix 秒)
+    #[pyo3(get)]
+    pub created_at: u64,
+    #[pyo3(get)]
+    pub total_adds: u64,
+    #[pyo3(get)]
+    pub total_removes: u64,
+    /// SQLite 恢复次数 (按文件计)
+    #[pyo3(get)]
+    pub total_recoveries: u64,
+    /// 最近一次压缩/重建 (compact、recenter、in_place 的 apply_transform) 时间 (Unix 秒)，从未发生时为 None
+    #[pyo3(get)]
+    pub last_compaction: Option<u64>,
+    #[pyo3(get)]
+    pub save_count: u64,
+    /// 加载的是没有统计块的旧文件，统计从加载时刻重新开始
+    #[pyo3(get)]
+    pub initialized_fresh: bool,
+}
 
-        if query_slice.len() != self.dimensions as usize {
-            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
-                "Search dimension mismatch: expected {}, got {}. (Check your bytes input!)",
-                self.dimensions,
-                query_slice.len()
-            )));
+impl LifetimeStats {
+    fn new() -> Self {
+        Self {
+            created_at: now_secs(),
+            total_adds: 0,
+            total_removes: 0,
+            total_recoveries: 0,
+            last_compaction: None,
+            save_count: 0,
+            initialized_fresh: false,
         }
+    }
 
-        let matches = index
-            .search(query_slice, k as usize)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("Search failed: {:?}", e)))?;
-
-        let mut results = Vec::with_capacity(matches.keys.len());
-
-        for (key, &dist) in matches.keys.iter().zip(matches.distances.iter()) {
-            results.push(SearchResult {
-                id: *key as u32,
-                score: 1.0 - dist as f64,
-            });
+    /// 旧文件没有统计块时使用
+    fn legacy() -> Self {
+        Self {
+            initialized_fresh: true,
+            ..Self::new()
         }
-
-        Ok(results)
     }
+}
 
-    /// 删除 (按 ID)
-    pub fn remove(&self, id: u32) -> PyResult<()> {
-        let index = self.index.write()
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("Lock failed: {}", e)))?;
+/// 索引侧车元数据，随 save/load 写入 `<index_path>.meta`
+/// usearch 无法枚举 key，所以存活 ID 集合由这里维护
+struct IndexMeta {
+    keys: HashSet<u64>,
+    calibration: Option<CalibrationInfo>,
+    lifetime: LifetimeStats,
+    /// contains 的快速否定层 (仅内存，不落盘)
+    bloom: Option<BloomFilter>,
+    /// 每个 ID 的附加数据 (随 save/load 持久化)
+    side: BTreeMap<u64, SideRecord>,
+    /// 上次 compact 以来 usearch 删除留在图中的墓碑节点数 (随 save/load 持久化)
+    tombstones: u64,
+    /// 标签倒排索引: 标签 -> 有序 ID 集合 (随 save/load 持久化)
+    label_index: BTreeMap<String, BTreeSet<u64>>,
+    /// ID -> 标签集合，由 label_index 派生 (不单独落盘)，两者同步维护
+    id_labels: HashMap<u64, BTreeSet<String>>,
+    /// ID -> 写入时向量小端字节的 xxh3 哈希 (enable_vector_hashes 之后才记录，随 save/load 持久化)
+    hashes: Option<HashMap<u64, u64>>,
+    /// enable_normalization 之后为 Some: 写入时把向量归一化 (随 save/load 持久化)
+    normalize: Option<NormalizeMode>,
+}
 
-        index.remove(id as u64)
-             .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("Remove failed: {:?}", e)))?;
+/// 写入归一化的状态
+#[derive(Default)]
+struct NormalizeMode {
+    /// ID -> 归一化前的 L2 模长 (record_norms=true 之后才记录)
+    norms: Option<HashMap<u64, f64>>,
+}
 
-        Ok(())
-    }
+/// 单个 ID 的附加数据
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct SideRecord {
+    meta: Option<String>,
+    timestamp: Option<u64>,
+    /// 向量已删除但附加数据仍保留
+    tombstone: bool,
+}
 
-    /// 获取当前索引状态
-    pub fn stats(&self) -> PyResult<VexusStats> {
-        let index = self.index.read()
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("Lock failed: {}", e)))?;
+/// get_side_data 返回给 Python 的 (meta, timestamp, tombstone)
+type SideDataTuple = (Option<String>, Option<u64>, bool);
 
-        Ok(VexusStats {
-            total_vectors: index.size() as u32,
-            dimensions: self.dimensions,
-            capacity: index.capacity() as u32,
-            memory_usage: index.memory_usage() as u32,
-        })
-    }
+/// get_vectors_array 返回给 Python 的 (ID 数组, 向量矩阵)
+type VectorArrays = (Py<PyArray1<u32>>, Py<PyArray2<f32>>);
 
-    /// 从 SQLite 数据库恢复索引 (同步版本)
-    #[pyo3(signature = (db_path, table_type, filter_diary_name=None))]
-    pub fn recover_from_sqlite(&self, db_path: String, table_type: String, filter_diary_name: Option<String>) -> PyResult<u32> {
-        let conn = Connection::open(&db_path)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("Failed to open DB: {}", e)))?;
+/// recover_file 从 SQLite 读出的一行: (id, vector BLOB, 是否在 include 中, 是否在 exclude 中)
+type RecoverRow = (i64, Vec<u8>, Option<bool>, Option<bool>);
 
-        let sql: String;
+/// parse_usearch_partial 读出的 (key, 向量) 条目
+type PartialEntries = Vec<(u64, Vec<f32>)>;
 
-        if table_type == "tags" {
-            sql = "SELECT id, vector FROM tags WHERE vector IS NOT NULL".to_string();
-        } else if table_type == "chunks" && filter_diary_name.is_some() {
-            sql = "SELECT c.id, c.vector FROM chunks c JOIN diary_files f ON c.file_id = f.id WHERE f.diary_name = ?1 AND c.vector IS NOT NULL".to_string();
-        } else {
-            return Ok(0);
-        }
+/// 侧车中 "side_data" 分段的格式版本
+const SIDE_DATA_VERSION: u32 = 1;
 
-        let mut stmt = conn
-            .prepare(&sql)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("Failed to prepare statement: {}", e)))?;
-
-        let mut count = 0;
-        let mut skipped_dim_mismatch = 0;
-        let expected_byte_len = self.dimensions as usize * std::mem::size_of::<f32>();
-
-        let index = self.index.write()
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("Lock failed: {}", e)))?;
-
-        let mut process_row = |id: i64, vector_bytes: Vec<u8>| {
-             if vector_bytes.len() == expected_byte_len {
-                let vec_slice: &[f32] = unsafe {
-                    std::slice::from_raw_parts(
-                        vector_bytes.as_ptr() as *const f32,
-                        self.dimensions as usize,
-                    )
-                };
+/// self_check 最多执行的抽样查询数
+const SELF_CHECK_QUERIES: usize = 8;
 
-                if index.size() + 1 >= index.capacity() {
-                    let new_cap = (index.capacity() as f64 * 1.5) as usize;
-                    let _ = index.reserve(new_cap);
-                }
+/// 标签过滤搜索: 候选不超过这个数时逐个精确计算距离，超过时改用带过滤器的 HNSW 搜索
+const LABEL_EXACT_MAX: usize = 4096;
 
-                if index.add(id as u64, vec_slice).is_ok() {
-                    count += 1;
-                }
-            } else {
-                skipped_dim_mismatch += 1;
-            }
-        };
+#[derive(Serialize, Deserialize)]
+struct SideDataSection {
+    version: u32,
+    records: Vec<(u64, SideRecord)>,
+}
 
-        if let Some(name) = &filter_diary_name {
-            let rows = stmt.query_map([name], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, Vec<u8>>(1)?)))
-                .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("Query failed: {}", e)))?;
+impl Default for IndexMeta {
+    fn default() -> Self {
+        Self {
+            keys: HashSet::new(),
+            calibration: None,
+            lifetime: LifetimeStats::new(),
+            bloom: None,
+            side: BTreeMap::new(),
+            tombstones: 0,
+            label_index: BTreeMap::new(),
+            id_labels: HashMap::new(),
+            hashes: None,
+            normalize: None,
+        }
+    }
+}
 
-            for row_result in rows {
-                if let Ok((id, vector_bytes)) = row_result {
-                    process_row(id, vector_bytes);
-                }
-            }
-        } else {
-            let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, Vec<u8>>(1)?)))
-                .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("Query failed: {}", e)))?;
+/// Bloom 过滤器每个 key 的哈希次数
+const BLOOM_HASHES: u64 = 4;
 
-            for row_result in rows {
-                if let Ok((id, vector_bytes)) = row_result {
-                    process_row(id, vector_bytes);
-                }
-            }
+/// 只增不删的 Bloom 过滤器: 删除后旧位保留，只会多出假阳性，不会出现假阴性
+struct BloomFilter {
+    bits: Vec<u64>,
+    n_bits: u64,
+    /// 被过滤器直接判否的查询数
+    definite_negatives: AtomicU64,
+    /// 过滤器判"可能存在"、落到真实检查的查询数
+    fallthroughs: AtomicU64,
+}
+
+impl BloomFilter {
+    fn new(n_bits: u64) -> Self {
+        let n_bits = n_bits.max(64);
+        Self {
+            bits: vec![0; n_bits.div_ceil(64) as usize],
+            n_bits,
+            definite_negatives: AtomicU64::new(0),
+            fallthroughs: AtomicU64::new(0),
         }
+    }
+
+    /// 双重哈希生成 BLOOM_HASHES 个位置
+    fn positions(&self, id: u64) -> impl Iterator<Item = u64> + '_ {
+        let mut rng = SplitMix64::new(id);
+        let h1 = rng.next_u64();
+        let h2 = rng.next_u64() | 1;
+        (0..BLOOM_HASHES).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % self.n_bits)
+    }
 
-        if skipped_dim_mismatch > 0 {
-            println!("[Vexus-Lite] ⚠️ Skipped {} vectors due to dimension mismatch (Expected {} bytes, got various)", skipped_dim_mismatch, expected_byte_len);
+    fn insert(&mut self, id: u64) {
+        let positions: Vec<u64> = self.positions(id).collect();
+        for pos in positions {
+            self.bits[(pos / 64) as usize] |= 1 << (pos % 64);
         }
+    }
 
-        Ok(count)
+    fn may_contain(&self, id: u64) -> bool {
+        self.positions(id).all(|pos| self.bits[(pos / 64) as usize] & (1 << (pos % 64)) != 0)
     }
+}
 
-    /// 高性能 SVD 分解
-    pub fn compute_svd(&self, flattened_vectors: Vec<u8>, n: u32, max_k: u32) -> PyResult<SvdResult> {
-        let dim = self.dimensions as usize;
-        let n = n as usize;
-        let max_k = max_k as usize;
+/// 侧车文件: 版本号 + 若干具名分段，每段单独 bincode 编码
+/// 读取时忽略未知分段、缺失分段取默认值，便于后续扩展
+#[derive(Serialize, Deserialize)]
+struct MetaFile {
+    version: u32,
+    sections: Vec<(String, Vec<u8>)>,
+}
 
-        let vec_slice: &[f32] = unsafe {
-            std::slice::from_raw_parts(
-                flattened_vectors.as_ptr() as *const f32,
-                flattened_vectors.len() / std::mem::size_of::<f32>(),
-            )
-        };
+const META_VERSION: u32 = 1;
 
-        if vec_slice.len() != n * dim {
-            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
-                "Flattened vectors length mismatch: expected {}, got {}",
-                n * dim,
-                vec_slice.len()
-            )));
+impl IndexMeta {
+    /// 记录新加入的 ID (同时更新 Bloom 过滤器、内容哈希与归一化前的模长)
+    /// vector 为调用方传入的原始向量 (归一化之前)
+    fn insert_key(&mut self, id: u64, vector: &[f32]) {
+        self.keys.insert(id);
+        self.record_hash(id, vector);
+        if let Some(NormalizeMode { norms: Some(norms) }) = &mut self.normalize {
+            norms.insert(id, dot(vector, vector).sqrt());
+        }
+        if let Some(bloom) = &mut self.bloom {
+            bloom.insert(id);
         }
+        if let Some(record) = self.side.get_mut(&id) {
+            record.tombstone = false;
+        }
+    }
 
-        use nalgebra::DMatrix;
-        let matrix = DMatrix::from_row_slice(n, dim, vec_slice);
-        let svd = matrix.svd(false, true);
+    /// 开启写入归一化时返回缩放为单位 L2 长度的副本 (零向量原样返回)，否则原样借用
+    fn normalized<'a>(&self, vector: &'a [f32]) -> Cow<'a, [f32]> {
+        let norm = dot(vector, vector).sqrt();
+        if self.normalize.is_none() || norm == 0.0 {
+            return Cow::Borrowed(vector);
+        }
+        Cow::Owned(vector.iter().map(|&x| (x as f64 / norm) as f32).collect())
+    }
 
-        let s = svd.singular_values.as_slice().iter().map(|&x| x as f64).collect::<Vec<_>>();
-        let v_t = svd.v_t.ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Failed to compute V^T matrix".to_string()))?;
+    /// 开启哈希登记时记录 (或更新) ID 的向量哈希
+    fn record_hash(&mut self, id: u64, vector: &[f32]) {
+        if let Some(hashes) = &mut self.hashes {
+            hashes.insert(id, vector_hash(vector));
+        }
+    }
 
-        let k = std::cmp::min(s.len(), max_k);
-        let mut u_flattened = Vec::with_capacity(k * dim);
+    /// 记录被删除的 ID (附加数据保留并标记 tombstone，标签随之移除)
+    fn remove_key(&mut self, id: u64) {
+        self.keys.remove(&id);
+        if let Some(hashes) = &mut self.hashes {
+            hashes.remove(&id);
+        }
+        if let Some(NormalizeMode { norms: Some(norms) }) = &mut self.normalize {
+            norms.remove(&id);
+        }
+        if let Some(record) = self.side.get_mut(&id) {
+            record.tombstone = true;
+        }
+        self.set_labels(id, BTreeSet::new());
+    }
 
-        for i in 0..k {
-            let row = v_t.row(i);
-            for &val in row.iter() {
-                u_flattened.push(val as f64);
+    /// 替换 ID 的标签集合 (空集合即清除)，同步更新倒排索引
+    fn set_labels(&mut self, id: u64, labels: BTreeSet<String>) {
+        if let Some(old) = self.id_labels.remove(&id) {
+            for label in old {
+                if let Some(ids) = self.label_index.get_mut(&label) {
+                    ids.remove(&id);
+                    if ids.is_empty() {
+                        self.label_index.remove(&label);
+                    }
+                }
             }
         }
-
-        Ok(SvdResult {
-            u: u_flattened,
-            s: s[..k].to_vec(),
-            k: k as u32,
-            dim: dim as u32,
-        })
+        for label in &labels {
+            self.label_index.entry(label.clone()).or_default().insert(id);
+        }
+        if !labels.is_empty() {
+            self.id_labels.insert(id, labels);
+        }
     }
 
-    /// 高性能 Gram-Schmidt 正交投影
-    pub fn compute_orthogonal_projection(
-        &self,
-        vector: Vec<u8>,
-        flattened_tags: Vec<u8>,
-        n_tags: u32,
-    ) -> PyResult<OrthogonalProjectionResult> {
-        let dim = self.dimensions as usize;
-        let n = n_tags as usize;
+    /// 同时带有全部 labels 的 ID，升序 (从最小的集合开始求交)
+    fn ids_with_all_labels(&self, labels: &[String]) -> Vec<u64> {
+        let mut sets = Vec::with_capacity(labels.len());
+        for label in labels {
+            match self.label_index.get(label) {
+                Some(ids) => sets.push(ids),
+                None => return Vec::new(),
+            }
+        }
+        sets.sort_by_key(|ids| ids.len());
+        match sets.split_first() {
+            Some((smallest, rest)) => smallest
+                .iter()
+                .copied()
+                .filter(|id| rest.iter().all(|ids| ids.contains(id)))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
 
-        let query: &[f32] = unsafe {
-            std::slice::from_raw_parts(vector.as_ptr() as *const f32, vector.len() / 4)
-        };
-        let tags_slice: &[f32] = unsafe {
-            std::slice::from_raw_parts(flattened_tags.as_ptr() as *const f32, flattened_tags.len() / 4)
-        };
+    fn to_bytes(&self, config: &IndexConfig) -> bincode::Result<Vec<u8>> {
+        let mut keys: Vec<u64> = self.keys.iter().copied().collect();
+        keys.sort_unstable();
 
-        if query.len() != dim || tags_slice.len() != n * dim {
-            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("Dimension mismatch".to_string()));
+        let mut sections = vec![
+            ("config".to_string(), bincode::serialize(config)?),
+            ("keys".to_string(), bincode::serialize(&keys)?),
+            ("lifetime".to_string(), bincode::serialize(&self.lifetime)?),
+            ("tombstones".to_string(), bincode::serialize(&self.tombstones)?),
+        ];
+        if let Some(calibration) = &self.calibration {
+            sections.push(("calibration".to_string(), bincode::serialize(calibration)?));
+        }
+        if !self.side.is_empty() {
+            let side = SideDataSection {
+                version: SIDE_DATA_VERSION,
+                records: self.side.iter().map(|(&id, r)| (id, r.clone())).collect(),
+            };
+            sections.push(("side_data".to_string(), bincode::serialize(&side)?));
+        }
+        if !self.label_index.is_empty() {
+            let labels: Vec<(&String, Vec<u64>)> = self
+                .label_index
+                .iter()
+                .map(|(label, ids)| (label, ids.iter().copied().collect()))
+                .collect();
+            sections.push(("label_index".to_string(), bincode::serialize(&labels)?));
+        }
+        if let Some(hashes) = &self.hashes {
+            let mut hashes: Vec<(u64, u64)> = hashes.iter().map(|(&id, &h)| (id, h)).collect();
+            hashes.sort_unstable();
+            sections.push(("vector_hashes".to_string(), bincode::serialize(&hashes)?));
+        }
+        if let Some(mode) = &self.normalize {
+            let norms: Option<Vec<(u64, f64)>> = mode.norms.as_ref().map(|norms| {
+                let mut norms: Vec<(u64, f64)> = norms.iter().map(|(&id, &n)| (id, n)).collect();
+                norms.sort_unstable_by_key(|&(id, _)| id);
+                norms
+            });
+            sections.push(("normalize".to_string(), bincode::serialize(&norms)?));
         }
 
-        let mut basis: Vec<Vec<f64>> = Vec::with_capacity(n);
-        let mut basis_coefficients = vec![0.0; n];
-        let mut projection = vec![0.0; dim];
+        bincode::serialize(&MetaFile {
+            version: META_VERSION,
+            sections,
+        })
+    }
 
-        for i in 0..n {
-            let start = i * dim;
-            let tag_vec = &tags_slice[start..start + dim];
-            let mut v: Vec<f64> = tag_vec.iter().map(|&x| x as f64).collect();
+    /// 解析侧车文件，返回 (元数据, 保存时的索引配置)；旧文件没有配置分段
+    fn from_bytes(bytes: &[u8]) -> bincode::Result<(Self, Option<IndexConfig>)> {
+        let file: MetaFile = bincode::deserialize(bytes)?;
+        let mut meta = IndexMeta {
+            lifetime: LifetimeStats::legacy(),
+            ..IndexMeta::default()
+        };
+        let mut config = None;
 
-            for u in &basis {
-                let mut dot = 0.0;
-                for d in 0..dim {
-                    dot += v[d] * u[d];
+        for (name, payload) in &file.sections {
+            match name.as_str() {
+                "config" => config = Some(bincode::deserialize(payload)?),
+                "keys" => {
+                    let keys: Vec<u64> = bincode::deserialize(payload)?;
+                    meta.keys = keys.into_iter().collect();
                 }
-                for d in 0..dim {
-                    v[d] -= dot * u[d];
+                "calibration" => meta.calibration = Some(bincode::deserialize(payload)?),
+                "lifetime" => meta.lifetime = bincode::deserialize(payload)?,
+                "tombstones" => meta.tombstones = bincode::deserialize(payload)?,
+                "side_data" => {
+                    let side: SideDataSection = bincode::deserialize(payload)?;
+                    if side.version > SIDE_DATA_VERSION {
+                        return Err(Box::new(bincode::ErrorKind::Custom(format!(
+                            "Unsupported side_data version {} (max {})",
+                            side.version, SIDE_DATA_VERSION
+                        ))));
+                    }
+                    meta.side = side.records.into_iter().collect();
                 }
-            }
+                "label_index" => {
+                    let labels: Vec<(String, Vec<u64>)> = bincode::deserialize(payload)?;
+                    for (label, ids) in labels {
+                        for &id in &ids {
+                            meta.id_labels.entry(id).or_default().insert(label.clone());
+                        }
+                        meta.label_index.insert(label, ids.into_iter().collect());
+                    }
+                }
+                "vector_hashes" => {
+                    let hashes: Vec<(u64, u64)> = bincode::deserialize(payload)?;
+                    meta.hashes = Some(hashes.into_iter().collect());
+                }
+                "normalize" => {
+                    let norms: Option<Vec<(u64, f64)>> = bincode::deserialize(payload)?;
+                    meta.normalize = Some(NormalizeMode { norms: norms.map(|norms| norms.into_iter().collect()) });
+                }
+                _ => {}
+            }
+        }
+
+        Ok((meta, config))
+    }
+}
+
+/// 索引配置，随侧车的 "config" 分段持久化，load 时据此重建 usearch 索引
+/// metric / quantization 用字符串保存，便于以后扩展取值
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct IndexConfig {
+    dimensions: u32,
+    /// "l2sq" | "cos" | "ip"
+    metric: String,
+    /// "f32" | "f16"
+    quantization: String,
+    connectivity: u32,
+    expansion_add: u32,
+    expansion_search: u32,
+    /// 可选的分量取值范围 [lo, hi]，add/add_batch 时校验
+    value_bounds: Option<(f32, f32)>,
+}
+
+impl IndexConfig {
+    /// 默认配置 (与历史上写死的参数一致)
+    fn new(dimensions: u32, value_bounds: Option<(f32, f32)>) -> Self {
+        Self {
+            dimensions,
+            metric: "l2sq".to_string(),
+            quantization: "f32".to_string(),
+            connectivity: 16,
+            expansion_add: 128,
+            expansion_search: 64,
+            value_bounds,
+        }
+    }
+
+    /// 按 usearch 的存储布局粗估 n 个向量占用的字节数 (误差目标 ~20%)
+    /// 每个节点: 向量 + key(8) + level(2) + 第 0 层 2M 个 u32 邻居 + 高层平均 1/(M-1) 层、每层 M 个邻居，
+    /// 另加节点指针和 IndexMeta 中的 key 记录
+    fn estimate_memory(&self, n: u64) -> u64 {
+        let scalar_bytes = if self.quantization == "f16" { 2 } else { 4 };
+        let m = self.connectivity.max(2) as f64;
+        let vector = (self.dimensions as u64 * scalar_bytes) as f64;
+        let base_layer = 4.0 + 2.0 * m * 4.0;
+        let upper_layers = (4.0 + m * 4.0) / (m - 1.0);
+        let bookkeeping = 8.0 + 2.0 + 8.0 + 16.0;
+        ((vector + base_layer + upper_layers + bookkeeping) * n as f64).ceil() as u64
+    }
+
+    fn create_index(&self) -> PyResult<Index> {
+        let metric = match DistanceMetric::parse(&self.metric)? {
+            DistanceMetric::L2sq => usearch::MetricKind::L2sq,
+            DistanceMetric::Cos => usearch::MetricKind::Cos,
+            DistanceMetric::Ip => usearch::MetricKind::IP,
+        };
+        let quantization = match self.quantization.as_str() {
+            "f32" => usearch::ScalarKind::F32,
+            "f16" => usearch::ScalarKind::F16,
+            other => {
+                return Err(PyErr::new::<InvalidArgumentError, _>(format!(
+                    "Unsupported quantization '{}'",
+                    other
+                )))
+            }
+        };
+
+        Index::new(&usearch::IndexOptions {
+            dimensions: self.dimensions as usize,
+            metric,
+            quantization,
+            connectivity: self.connectivity as usize,
+            expansion_add: self.expansion_add as usize,
+            expansion_search: self.expansion_search as usize,
+            multi: false,
+        })
+        .map_err(|e| PyErr::new::<VexusError, _>(format!("Failed to create index: {:?}", e)))
+    }
+}
+
+/// 核心索引结构 (无状态，只存向量)
+/// 加锁顺序: 先 index 后 meta
+#[pyclass]
+pub struct VexusIndex {
+    index: Arc<RwLock<Index>>,
+    meta: Arc<RwLock<IndexMeta>>,
+    /// 索引配置 (随 save/load 持久化)
+    config: IndexConfig,
+    /// close() 之后置位，所有访问索引的操作报 IndexClosedError
+    closed: AtomicBool,
+    /// 恢复前的内存预估上限 (字节，0 表示不限制)，仅运行时有效，不持久化
+    memory_limit: AtomicU64,
+    /// 查询日志 (None 表示未开启)，独立的锁，在释放 index 锁之后记录，不持久化
+    query_log: Mutex<Option<QueryLog>>,
+    /// search_paged 的分页会话，独立的锁，不持有 index 锁时访问，不持久化
+    page_cache: Mutex<PageCache>,
+    /// 单次搜索允许请求的最大结果数 (构造时指定)，不持久化
+    max_results: u32,
+    /// open_shared 打开时为所映射文件的规范化路径，此时索引只读
+    shared_path: Option<PathBuf>,
+}
+
+#[pymethods]
+impl VexusIndex {
+    /// 创建新的空索引
+    /// value_bounds=(lo, hi) 时，add/add_batch 拒绝任何分量超出范围 (或为 NaN) 的向量，默认不校验
+    /// bloom_bits: 为 contains/contains_batch 启用该位数的 Bloom 过滤器，默认不启用
+    /// connectivity / expansion_add / expansion_search 不传时用默认值 (16 / 128 / 64)，
+    /// 可参考 recommended_options 按规模选择
+    /// max_results: 单次搜索 k (及候选数、页大小) 的上限，超过时报 ValueError，防止超大 k 撑爆内存
+    #[new]
+    #[pyo3(signature = (dim, capacity, value_bounds=None, bloom_bits=None, connectivity=None, expansion_add=None, expansion_search=None, max_results=DEFAULT_MAX_RESULTS))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        dim: u32,
+        capacity: u32,
+        value_bounds: Option<(f32, f32)>,
+        bloom_bits: Option<u64>,
+        connectivity: Option<u32>,
+        expansion_add: Option<u32>,
+        expansion_search: Option<u32>,
+        max_results: u32,
+    ) -> PyResult<Self> {
+        let mut config = IndexConfig::new(dim, value_bounds);
+        if let Some(m) = connectivity {
+            config.connectivity = m;
+        }
+        if let Some(ef) = expansion_add {
+            config.expansion_add = ef;
+        }
+        if let Some(ef) = expansion_search {
+            config.expansion_search = ef;
+        }
+        let index = config.create_index()?;
+
+        index
+            .reserve(capacity as usize)
+            .map_err(|e| PyErr::new::<CapacityError, _>(format!("Failed to reserve capacity: {:?}", e)))?;
+
+        let meta = IndexMeta {
+            bloom: bloom_bits.map(BloomFilter::new),
+            ..IndexMeta::default()
+        };
+
+        Ok(Self {
+            index: Arc::new(RwLock::new(index)),
+            meta: Arc::new(RwLock::new(meta)),
+            config,
+            closed: AtomicBool::new(false),
+            memory_limit: AtomicU64::new(0),
+            query_log: Mutex::new(None),
+            page_cache: Mutex::new(PageCache::default()),
+            max_results,
+            shared_path: None,
+        })
+    }
+
+    /// 从磁盘加载索引
+    /// 若存在 `<index_path>.meta` 则一并恢复 ID 集合、校准参数与索引配置 (旧文件没有则从空开始)
+    /// 配置以保存时为准；显式传入的 dim / value_bounds 只用于校验，与保存的不一致时报 ValueError
+    /// bloom_bits 同 new；没有 .meta 的旧索引无法枚举 ID，此时不启用 Bloom 过滤器
+    #[classmethod]
+    #[pyo3(signature = (dim, capacity, index_path, _unused_map_path=None, value_bounds=None, bloom_bits=None, max_results=DEFAULT_MAX_RESULTS))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn load(
+        _cls: &Bound<'_, PyType>,
+        dim: u32,
+        capacity: u32,
+        index_path: String,
+        _unused_map_path: Option<String>,
+        value_bounds: Option<(f32, f32)>,
+        bloom_bits: Option<u64>,
+        max_results: u32,
+    ) -> PyResult<Self> {
+        let meta_path = format!("{}.meta", index_path);
+        let (mut meta, stored_config) = if std::path::Path::new(&meta_path).exists() {
+            let bytes = std::fs::read(&meta_path)
+                .map_err(|e| PyErr::new::<StorageError, _>(format!("Failed to read meta file: {}", e)))?;
+            IndexMeta::from_bytes(&bytes)
+                .map_err(|e| PyErr::new::<CorruptFileError, _>(format!("Failed to parse meta file: {}", e)))?
+        } else {
+            let legacy = IndexMeta {
+                lifetime: LifetimeStats::legacy(),
+                ..IndexMeta::default()
+            };
+            (legacy, None)
+        };
+
+        let config = match stored_config {
+            Some(stored) => {
+                if stored.dimensions != dim {
+                    return Err(PyErr::new::<DimensionMismatchError, _>(format!(
+                        "Dimension mismatch: index was saved with dim {}, got {}",
+                        stored.dimensions, dim
+                    )));
+                }
+                if value_bounds.is_some() && value_bounds != stored.value_bounds {
+                    return Err(PyErr::new::<InvalidArgumentError, _>(format!(
+                        "value_bounds mismatch: index was saved with {:?}, got {:?}",
+                        stored.value_bounds, value_bounds
+                    )));
+                }
+                stored
+            }
+            None => IndexConfig::new(dim, value_bounds),
+        };
+
+        let index = config.create_index()?;
+
+        index.load(&index_path)
+            .map_err(|e| PyErr::new::<CorruptFileError, _>(format!("Failed to load index from disk: {:?}", e)))?;
+
+        let current_capacity = index.capacity();
+        if capacity as usize > current_capacity {
+            index
+                .reserve(capacity as usize)
+                .map_err(|e| PyErr::new::<CapacityError, _>(format!("Failed to expand capacity: {:?}", e)))?;
+        }
+
+        if let Some(bits) = bloom_bits
+            && meta.keys.len() == index.size()
+        {
+            let mut bloom = BloomFilter::new(bits);
+            for &id in &meta.keys {
+                bloom.insert(id);
+            }
+            meta.bloom = Some(bloom);
+        }
+
+        Ok(Self {
+            index: Arc::new(RwLock::new(index)),
+            meta: Arc::new(RwLock::new(meta)),
+            config,
+            closed: AtomicBool::new(false),
+            memory_limit: AtomicU64::new(0),
+            query_log: Mutex::new(None),
+            page_cache: Mutex::new(PageCache::default()),
+            max_results,
+            shared_path: None,
+        })
+    }
+
+    /// 以只读共享方式打开 save 保存的索引: 用 mmap 映射文件 (usearch view)，向量和图不读入堆内存
+    /// 多个进程 (如 gunicorn pre-fork worker) 打开同一文件时只读页面共享系统页缓存，常驻内存接近一份。
+    /// 维度等配置取自 .meta 侧车 (必须存在)。返回的句柄只能查询: 所有修改操作 (add / remove / recover /
+    /// compact / set_labels 等) 抛 ReadOnlyIndexError；可以 save 到其他路径。
+    /// 句柄没有后台线程，没有调用进行中时可以安全 fork (例如 master 进程 preload 后再 fork)
+    /// max_results 同 new (不随 .meta 保存)
+    #[classmethod]
+    #[pyo3(signature = (path, max_results=DEFAULT_MAX_RESULTS))]
+    pub fn open_shared(_cls: &Bound<'_, PyType>, path: String, max_results: u32) -> PyResult<Self> {
+        let meta_path = format!("{}.meta", path);
+        if !Path::new(&path).exists() || !Path::new(&meta_path).exists() {
+            return Err(PyErr::new::<MissingFileError, _>(format!(
+                "open_shared needs both {} and {}",
+                path, meta_path
+            )));
+        }
+        let bytes = std::fs::read(&meta_path)
+            .map_err(|e| PyErr::new::<StorageError, _>(format!("Failed to read meta file: {}", e)))?;
+        let (meta, stored_config) = IndexMeta::from_bytes(&bytes)
+            .map_err(|e| PyErr::new::<CorruptFileError, _>(format!("Failed to parse meta file: {}", e)))?;
+        let config = stored_config.ok_or_else(|| {
+            PyErr::new::<CorruptFileError, _>("Meta file has no config section; open it with load instead".to_string())
+        })?;
+        let canonical = std::fs::canonicalize(&path)
+            .map_err(|e| PyErr::new::<StorageError, _>(format!("Failed to resolve {}: {}", path, e)))?;
+
+        let index = config.create_index()?;
+        index.view(&path)
+            .map_err(|e| PyErr::new::<CorruptFileError, _>(format!("Failed to map index file: {:?}", e)))?;
+
+        *SHARED_VIEWS
+            .lock()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?
+            .entry(canonical.clone())
+            .or_insert(0) += 1;
+
+        Ok(Self {
+            index: Arc::new(RwLock::new(index)),
+            meta: Arc::new(RwLock::new(meta)),
+            config,
+            closed: AtomicBool::new(false),
+            memory_limit: AtomicU64::new(0),
+            query_log: Mutex::new(None),
+            page_cache: Mutex::new(PageCache::default()),
+            max_results,
+            shared_path: Some(canonical),
+        })
+    }
+
+    /// 是否为 open_shared 打开的只读视图
+    #[getter]
+    pub fn is_shared(&self) -> PyResult<bool> {
+        self.check_open()?;
+        Ok(self.shared_path.is_some())
+    }
+
+    /// 按预期规模与维度给出 HNSW 参数建议 (纯计算，不需要索引)
+    /// connectivity 约为 log2(n)，高维再加一些；expansion_add ≈ 8M，expansion_search ≈ 4M
+    /// 结果可直接传给 new(connectivity=..., expansion_add=..., expansion_search=...)
+    #[classmethod]
+    pub fn recommended_options(_cls: &Bound<'_, PyType>, n_expected: u32, dim: u32) -> RecommendedOptions {
+        let log_n = (n_expected.max(2) as f64).log2().ceil() as u32;
+        let dim_bonus = match dim {
+            512.. => 8,
+            128.. => 4,
+            _ => 0,
+        };
+        let connectivity = (log_n + dim_bonus).clamp(8, 48);
+
+        RecommendedOptions {
+            connectivity,
+            expansion_add: (connectivity * 8).clamp(64, 512),
+            expansion_search: (connectivity * 4).clamp(32, 256),
+        }
+    }
+
+    /// 索引中至少有 min_vectors 个向量时返回 True (例如在 SVD/EPA 之前检查样本是否足够)
+    pub fn is_ready(&self, min_vectors: u32) -> PyResult<bool> {
+        Ok(self.read_index()?.size() >= min_vectors as usize)
+    }
+
+    /// 预估再添加 n_vectors 个向量需要的内存 (字节)，按当前维度/量化/连接度估算，不精确
+    pub fn estimate_memory(&self, n_vectors: u32) -> PyResult<u64> {
+        self.check_open()?;
+        Ok(self.config.estimate_memory(n_vectors as u64))
+    }
+
+    /// 设置恢复前的内存上限 (字节)；None 取消限制
+    /// recover_from_sqlite 会先 COUNT 待恢复行数，预估超过上限时拒绝执行 (force=True 跳过检查)
+    #[pyo3(signature = (limit=None))]
+    pub fn set_memory_limit(&self, limit: Option<u64>) -> PyResult<()> {
+        self.check_open()?;
+        self.memory_limit.store(limit.unwrap_or(0), Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// 开启查询日志: 保留最近 capacity 次搜索 (search / search_into / search_with_forced / search_robust)
+    /// store_vectors=true 时同时记录查询向量；重复调用会清空已有记录。日志不随 save/load 持久化
+    #[pyo3(signature = (capacity, store_vectors=false))]
+    pub fn enable_query_log(&self, capacity: u32, store_vectors: bool) -> PyResult<()> {
+        self.check_open()?;
+        if capacity == 0 {
+            return Err(PyErr::new::<InvalidArgumentError, _>("capacity must be at least 1".to_string()));
+        }
+        let mut log = self.query_log.lock()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+        *log = Some(QueryLog {
+            capacity: capacity as usize,
+            store_vectors,
+            entries: VecDeque::with_capacity(capacity as usize),
+        });
+        Ok(())
+    }
+
+    /// 查询日志中的记录 (旧 → 新)，未开启时为空列表
+    pub fn query_log(&self) -> PyResult<Vec<QueryLogEntry>> {
+        self.check_open()?;
+        let log = self.query_log.lock()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+        Ok(log.as_ref().map(|l| l.entries.iter().cloned().collect()).unwrap_or_default())
+    }
+
+    /// 清空查询日志 (保持开启状态)
+    pub fn clear_query_log(&self) -> PyResult<()> {
+        self.check_open()?;
+        let mut log = self.query_log.lock()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+        if let Some(l) = log.as_mut() {
+            l.entries.clear();
+        }
+        Ok(())
+    }
+
+    /// 把查询日志写成 JSONL (每行一条记录)，返回写入的条数
+    pub fn dump_query_log(&self, path: String) -> PyResult<u32> {
+        self.check_open()?;
+        let entries = self.query_log()?;
+        let mut out = String::new();
+        for entry in &entries {
+            let line = serde_json::to_string(entry)
+                .map_err(|e| PyErr::new::<VexusError, _>(format!("Failed to encode query log: {}", e)))?;
+            out.push_str(&line);
+            out.push('\n');
+        }
+        std::fs::write(&path, out)
+            .map_err(|e| PyErr::new::<StorageError, _>(format!("Failed to write query log: {}", e)))?;
+        Ok(entries.len() as u32)
+    }
+
+    /// 从可能损坏的索引文件中尽量恢复，返回 (索引, 恢复的向量数, 文件声明的向量总数)
+    /// 先按 load 正常加载；失败时按 usearch 的序列化布局逐条读取 (key, 向量)，
+    /// 读到第一个不完整/不一致的条目为止，用读出的部分重建索引。
+    /// 文件头不是本版本能解析的 usearch v2 格式时报 CorruptFileError；max_results 同 new
+    #[classmethod]
+    #[pyo3(signature = (dim, capacity, index_path, value_bounds=None, max_results=DEFAULT_MAX_RESULTS))]
+    pub fn load_partial(
+        cls: &Bound<'_, PyType>,
+        dim: u32,
+        capacity: u32,
+        index_path: String,
+        value_bounds: Option<(f32, f32)>,
+        max_results: u32,
+    ) -> PyResult<(Self, u32, u32)> {
+        if let Ok(loaded) = Self::load(cls, dim, capacity, index_path.clone(), None, value_bounds, None, max_results) {
+            let size = loaded.read_index()?.size() as u32;
+            return Ok((loaded, size, size));
+        }
+
+        let bytes = std::fs::read(&index_path)
+            .map_err(|e| PyErr::new::<StorageError, _>(format!("Failed to read index file: {}", e)))?;
+        let (entries, total) = parse_usearch_partial(&bytes, dim as usize)
+            .map_err(|msg| PyErr::new::<CorruptFileError, _>(format!("Cannot partially load {}: {}", index_path, msg)))?;
+
+        let vexus = Self::new(dim, capacity.max(entries.len() as u32), value_bounds, None, None, None, None, max_results)?;
+        {
+            let index = vexus.write_index()?;
+            let mut meta = vexus.meta.write()
+                .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+            for (id, v) in &entries {
+                if index.add(*id, v).is_ok() {
+                    meta.insert_key(*id, v);
+                }
+            }
+            meta.lifetime = LifetimeStats::legacy();
+            meta.lifetime.total_recoveries += 1;
+        }
+
+        let recovered = vexus.read_index()?.size() as u32;
+        Ok((vexus, recovered, total as u32))
+    }
+
+    /// 近似相等 (测试用): 配置与向量数一致，且所有登记 ID 的向量逐分量误差不超过 tol
+    pub fn approx_equals(&self, other: PyRef<'_, VexusIndex>, tol: f64) -> PyResult<bool> {
+        if std::ptr::eq(self, &*other) {
+            return Ok(true);
+        }
+        if self.config.dimensions != other.config.dimensions || self.config.metric != other.config.metric {
+            return Ok(false);
+        }
+
+        let index = self.read_index()?;
+        let meta = self.meta.read()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+        let other_index = other.read_index()?;
+        let other_meta = other.meta.read()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+
+        if index.size() != other_index.size() || meta.keys != other_meta.keys {
+            return Ok(false);
+        }
+
+        let dim = self.config.dimensions as usize;
+        Ok(meta.keys.iter().all(|&id| {
+            match (get_vector(&index, id, dim), get_vector(&other_index, id, dim)) {
+                (Some(a), Some(b)) => a.iter().zip(b.iter()).all(|(&x, &y)| ((x - y) as f64).abs() <= tol),
+                _ => false,
+            }
+        }))
+    }
+
+    /// 检查每个登记的 ID 都能取回维度正确、分量有限的向量 (不会因坏数据崩溃)
+    /// 没有 .meta 的旧索引无法枚举 ID、无从校验，此时返回 None (而不是 True)
+    pub fn verify_integrity(&self) -> PyResult<Option<bool>> {
+        let index = self.read_index()?;
+        let meta = self.meta.read()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+
+        if meta.keys.is_empty() && index.size() > 0 {
+            return Ok(None);
+        }
+        if meta.keys.len() != index.size() {
+            return Ok(Some(false));
+        }
+
+        let dim = self.config.dimensions as usize;
+        Ok(Some(meta.keys.iter().all(|&id| {
+            get_vector(&index, id, dim).is_some_and(|v| v.iter().all(|x| x.is_finite()))
+        })))
+    }
+
+    /// 保存索引到磁盘
+    /// 在写锁下把索引和 meta 一次性序列化成内存快照，再释放锁写文件，
+    /// 保证保存过程中不会与任何修改操作交错 (避免生成半增长的损坏文件)
+    /// 目标文件正被本进程内某个 open_shared 视图映射时拒绝保存 (ReadOnlyIndexError)
+    pub fn save(&self, py: Python<'_>, index_path: String) -> PyResult<()> {
+        if let Ok(target) = std::fs::canonicalize(&index_path) {
+            let views = SHARED_VIEWS.lock()
+                .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+            if views.contains_key(&target) {
+                return Err(ReadOnlyIndexError::new_err(format!(
+                    "Refusing to overwrite {}: it backs an open shared view",
+                    index_path
+                )));
+            }
+        }
+        let (index_bytes, meta_bytes) = {
+            let index = self.lock_index_exclusive()?;
+            let mut meta = self.meta.write()
+                .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+
+            let mut index_bytes = vec![0u8; index.serialized_length()];
+            index
+                .save_to_buffer(&mut index_bytes)
+                .map_err(|e| PyErr::new::<VexusError, _>(format!("Failed to save index: {:?}", e)))?;
+
+            meta.lifetime.save_count += 1;
+            let meta_bytes = meta.to_bytes(&self.config)
+                .map_err(|e| PyErr::new::<VexusError, _>(format!("Failed to encode meta: {}", e)))?;
+
+            (index_bytes, meta_bytes)
+        };
+
+        py.allow_threads(|| {
+            let temp_path = format!("{}.tmp", index_path);
+            std::fs::write(&temp_path, &index_bytes)
+                .map_err(|e| PyErr::new::<StorageError, _>(format!("Failed to write index file: {}", e)))?;
+            std::fs::rename(&temp_path, &index_path)
+                .map_err(|e| PyErr::new::<StorageError, _>(format!("Failed to rename index file: {}", e)))?;
+
+            let meta_path = format!("{}.meta", index_path);
+            let meta_temp_path = format!("{}.tmp", meta_path);
+            std::fs::write(&meta_temp_path, &meta_bytes)
+                .map_err(|e| PyErr::new::<StorageError, _>(format!("Failed to write meta file: {}", e)))?;
+            std::fs::rename(&meta_temp_path, &meta_path)
+                .map_err(|e| PyErr::new::<StorageError, _>(format!("Failed to rename meta file: {}", e)))?;
+
+            Ok(())
+        })
+    }
+
+    /// 单个添加
+    /// id_namespace: 把命名空间打包进 key 高位 (见 pack_id)，避免不同表的 ID 互相覆盖
+    /// byte_order: "le" (默认) 或 "be"，见模块文档
+    #[pyo3(signature = (id, vector, id_namespace=None, byte_order="le"))]
+    pub fn add(&self, py: Python<'_>, id: u32, vector: Vec<u8>, id_namespace: Option<u8>, byte_order: &str) -> PyResult<()> {
+        let key = namespaced_key(id_namespace, id as i64)?;
+        let byte_order = ByteOrder::parse(byte_order)?;
+        let index = self.write_index()?;
+
+        let vec_slice = byte_order.decode(&vector, "vector", Some(self.config.dimensions as usize))?;
+        let vec_slice = &*vec_slice;
+
+        self.check_bounds(key, vec_slice)?;
+
+        let growth = ensure_capacity(&index, index.size() + 1);
+
+        let mut meta = self.meta.write()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+        index
+            .add(key, &meta.normalized(vec_slice))
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Add failed: {:?}", e)))?;
+        meta.insert_key(key, vec_slice);
+        meta.lifetime.total_adds += 1;
+
+        drop(meta);
+        drop(index);
+        log_growth_events(py, "add", growth.as_slice());
+        Ok(())
+    }
+
+    /// 批量添加
+    /// 预先一次性扩容到所需容量，返回本次发生的扩容事件 (最多一次)，同时以 debug 级别写入 vector_db 日志器
+    /// byte_order 同 add
+    #[pyo3(signature = (ids, vectors, id_namespace=None, byte_order="le"))]
+    pub fn add_batch(&self, py: Python<'_>, ids: Vec<u32>, vectors: Vec<u8>, id_namespace: Option<u8>, byte_order: &str) -> PyResult<Vec<GrowthEvent>> {
+        let keys = ids
+            .iter()
+            .map(|&id| namespaced_key(id_namespace, id as i64))
+            .collect::<PyResult<Vec<u64>>>()?;
+        let byte_order = ByteOrder::parse(byte_order)?;
+        let index = self.write_index()?;
+
+        let count = ids.len();
+        let dim = self.config.dimensions as usize;
+
+        let vec_slice = byte_order.decode(&vectors, "vectors", Some(count * dim))?;
+
+        for (i, &key) in keys.iter().enumerate() {
+            self.check_bounds(key, &vec_slice[i * dim..(i + 1) * dim])?;
+        }
+
+        let growth_events: Vec<GrowthEvent> = ensure_capacity(&index, index.size() + count).into_iter().collect();
+
+        let mut meta = self.meta.write()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+
+        for (i, &key) in keys.iter().enumerate() {
+            let start = i * dim;
+            let v = &vec_slice[start..start+dim];
+            index.add(key, &meta.normalized(v))
+                .map_err(|e| PyErr::new::<VexusError, _>(format!("Batch add failed idx {}: {:?}", i, e)))?;
+            meta.insert_key(key, v);
+            meta.lifetime.total_adds += 1;
+        }
+
+        drop(meta);
+        drop(index);
+        log_growth_events(py, "add_batch", &growth_events);
+        Ok(growth_events)
+    }
+
+    /// 搜索
+    /// calibrated=true 时 score 使用 fit_score_calibration 拟合的映射，落在 [0,1]
+    /// timeout_ms: 在工作线程中执行搜索，超时抛 TimeoutError。
+    /// 注意 usearch 的调用无法中途打断，超时只限制调用方观察到的延迟，
+    /// 工作线程会继续跑完并丢弃结果，CPU 占用并不会因此减少。
+    /// order: "best_first" (默认，最相关在前) 或 "worst_first"，所有搜索变体含义一致
+    /// require_labels: 只返回同时带有这些标签 (set_labels) 的向量。按标签倒排索引求出候选集，
+    /// 候选不超过 LABEL_EXACT_MAX 个时对候选逐个精确计算距离 (稀有标签也能完整召回)，
+    /// 否则用带过滤器的 HNSW 搜索；所选策略写入结果的 strategy 字段。不能与 timeout_ms 同时使用
+    /// raw=true 时在 distance 字段返回 usearch 的原始距离: l2sq 为平方欧氏距离，cos 为 1 - 余弦相似度，
+    /// ip 为 1 - 内积；越小越近。未校准时 score = 1 - distance
+    #[pyo3(signature = (query, k, calibrated=false, timeout_ms=None, order="best_first", require_labels=None, raw=false))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn search(
+        &self,
+        py: Python<'_>,
+        query: Vec<u8>,
+        k: u32,
+        calibrated: bool,
+        timeout_ms: Option<u64>,
+        order: &str,
+        require_labels: Option<Vec<String>>,
+        raw: bool,
+    ) -> PyResult<Vec<SearchResult>> {
+        self.check_result_size("k", k)?;
+        let order_name = order;
+        let order = ResultOrder::parse(order)?;
+
+        let query_slice = &*f32_view(&query, "query", Some(self.config.dimensions as usize))?;
+
+        let mut strategy = None;
+        let matches: Vec<(u64, f32)> = match (&require_labels, timeout_ms) {
+            (Some(_), Some(_)) => {
+                return Err(PyErr::new::<InvalidArgumentError, _>(
+                    "timeout_ms cannot be combined with require_labels".to_string(),
+                ));
+            }
+            (Some(labels), None) => {
+                let index = self.read_index()?;
+                let (hits, chosen) = self.search_labeled(&index, query_slice, k as usize, labels)?;
+                strategy = Some(chosen.to_string());
+                hits
+            }
+            (None, Some(ms)) => {
+                self.check_open()?;
+                let owned_query = query_slice.to_vec();
+                let matches = py.allow_threads(|| {
+                    search_with_timeout(&self.index, owned_query, k as usize, std::time::Duration::from_millis(ms))
+                })?;
+                matches.keys.into_iter().zip(matches.distances).collect()
+            }
+            (None, None) => {
+                let index = self.read_index()?;
+
+                let matches = index
+                    .search(query_slice, k as usize)
+                    .map_err(|e| PyErr::new::<VexusError, _>(format!("Search failed: {:?}", e)))?;
+                matches.keys.into_iter().zip(matches.distances).collect()
+            }
+        };
+
+        let mut results = self.score_matches(&matches, calibrated)?;
+        for (result, &(_, dist)) in results.iter_mut().zip(matches.iter()) {
+            result.strategy = strategy.clone();
+            result.distance = raw.then_some(dist as f64);
+        }
+
+        order.apply(&mut results);
+        self.log_query(
+            "search",
+            k,
+            || {
+                format!(
+                    "calibrated={}, timeout_ms={:?}, order={}, require_labels={:?}, raw={}",
+                    calibrated, timeout_ms, order_name, require_labels, raw
+                )
+            },
+            query_slice,
+            results.iter().map(|r| (r.id, r.score)),
+        );
+        Ok(results)
+    }
+
+    /// 搜索结果写入调用方预先分配的 numpy 数组 (复用缓冲区，不创建结果对象)
+    /// out_ids / out_scores (以及给出时的 out_namespaces) 长度都必须 >= k；按 best_first 写入前缀，返回写入的条数。
+    /// 分数与同参数的 search 一致 (calibrated 含义相同)；out_namespaces 接收各结果的命名空间
+    #[pyo3(signature = (query, k, out_ids, out_scores, calibrated=false, out_namespaces=None))]
+    pub fn search_into(
+        &self,
+        query: Vec<u8>,
+        k: u32,
+        mut out_ids: PyReadwriteArray1<'_, u32>,
+        mut out_scores: PyReadwriteArray1<'_, f32>,
+        calibrated: bool,
+        mut out_namespaces: Option<PyReadwriteArray1<'_, u8>>,
+    ) -> PyResult<u32> {
+        self.check_result_size("k", k)?;
+        let query_slice = &*f32_view(&query, "query", Some(self.config.dimensions as usize))?;
+        let ids = out_ids.as_slice_mut()
+            .map_err(|e| PyErr::new::<InvalidArgumentError, _>(format!("out_ids: {}", e)))?;
+        let scores = out_scores.as_slice_mut()
+            .map_err(|e| PyErr::new::<InvalidArgumentError, _>(format!("out_scores: {}", e)))?;
+        let mut namespaces = match out_namespaces.as_mut() {
+            Some(array) => Some(array.as_slice_mut()
+                .map_err(|e| PyErr::new::<InvalidArgumentError, _>(format!("out_namespaces: {}", e)))?),
+            None => None,
+        };
+        let lengths = [("out_ids", ids.len()), ("out_scores", scores.len())]
+            .into_iter()
+            .chain(namespaces.as_ref().map(|ns| ("out_namespaces", ns.len())));
+        for (name, len) in lengths {
+            if len < k as usize {
+                return Err(PyErr::new::<InvalidArgumentError, _>(format!(
+                    "{} has length {}, expected at least k={}",
+                    name, len, k
+                )));
+            }
+        }
+
+        let matches = self.read_index()?
+            .search(query_slice, k as usize)
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Search failed: {:?}", e)))?;
+        let matches: Vec<(u64, f32)> = matches.keys.into_iter().zip(matches.distances).collect();
+        let results = self.score_matches(&matches, calibrated)?;
+
+        let written = results.len();
+        for (i, result) in results.iter().enumerate() {
+            ids[i] = result.id;
+            scores[i] = result.score as f32;
+            if let Some(ns) = namespaces.as_mut() {
+                ns[i] = result.namespace;
+            }
+        }
+        self.log_query(
+            "search_into",
+            k,
+            || format!("calibrated={}", calibrated),
+            query_slice,
+            ids[..written].iter().copied().zip(scores[..written].iter().map(|&s| s as f64)),
+        );
+        Ok(written as u32)
+    }
+
+    /// 批量搜索，结果展平为三个平行数组 (query 下标, 索引 key, score)，便于直接构造 DataFrame
+    /// 未用 id_namespace 写入的 key 即 ID，带命名空间的可用 unpack_id 拆开
+    /// order 只作用于每个 query 组内部，组之间仍按 query 下标递增
+    #[pyo3(signature = (queries, n, k, order="best_first"))]
+    pub fn search_batch_flat(&self, py: Python<'_>, queries: Vec<u8>, n: u32, k: u32, order: &str) -> PyResult<(Vec<u32>, Vec<u64>, Vec<f64>)> {
+        self.check_result_size("k", k)?;
+        self.check_result_size("n*k", n as u64 * k as u64)?;
+        let order = ResultOrder::parse(order)?;
+        let dim = self.config.dimensions as usize;
+        let n = n as usize;
+
+        let queries_slice = &*f32_view(&queries, "queries", Some(n * dim))?;
+
+        let index = self.read_index()?;
+
+        let all_matches = py.allow_threads(|| {
+            queries_slice
+                .par_chunks(dim.max(1))
+                .take(n)
+                .map(|q| index.search(q, k as usize))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| PyErr::new::<VexusError, _>(format!("Search failed: {:?}", e)))?;
+
+        let total: usize = all_matches.iter().map(|m| m.keys.len()).sum();
+        let mut query_idx = Vec::with_capacity(total);
+        let mut ids = Vec::with_capacity(total);
+        let mut scores = Vec::with_capacity(total);
+
+        for (qi, matches) in all_matches.iter().enumerate() {
+            let mut group: Vec<(u64, f32)> = matches.keys.iter().copied().zip(matches.distances.iter().copied()).collect();
+            order.apply(&mut group);
+            for (key, dist) in group {
+                query_idx.push(qi as u32);
+                ids.push(key);
+                scores.push(1.0 - dist as f64);
+            }
+        }
+
+        Ok((query_idx, ids, scores))
+    }
+
+    /// 带强制包含 ID 的搜索
+    /// forced_ids 一定出现在结果最前面 (按真实距离排序)，其余 k - |forced| 个位置由普通近邻补齐
+    /// forced_ids 数量超过 k 时全部返回，不做截断 (forced_ids 本身同样受 max_results 限制)
+    /// worst_first 时整体倒序 (强制 ID 落在末尾)
+    #[pyo3(signature = (query, k, forced_ids, order="best_first"))]
+    pub fn search_with_forced(&self, query: Vec<u8>, k: u32, forced_ids: Vec<u32>, order: &str) -> PyResult<Vec<SearchResult>> {
+        self.check_result_size("k", k)?;
+        self.check_result_size("forced_ids", forced_ids.len() as u64)?;
+        let order_name = order;
+        let order = ResultOrder::parse(order)?;
+        let index = self.read_index()?;
+
+        let dim = self.config.dimensions as usize;
+        let query_slice = &*f32_view(&query, "query", Some(dim))?;
+
+        let mut forced_set: HashSet<u64> = HashSet::with_capacity(forced_ids.len());
+        let mut results = Vec::with_capacity((k as usize).max(forced_ids.len()));
+
+        for &id in &forced_ids {
+            if !forced_set.insert(id as u64) {
+                continue;
+            }
+            let vector = get_vector(&index, id as u64, dim)
+                .ok_or_else(|| PyErr::new::<NotFoundError, _>(format!("Forced ID {} not found in index", id)))?;
+            results.push(SearchResult {
+                id,
+                namespace: 0,
+                score: 1.0 - l2sq(query_slice, &vector),
+                hop: 0,
+                strategy: None,
+                distance: None,
+            });
+        }
+        results.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+        let remaining = (k as usize).saturating_sub(results.len());
+        if remaining > 0 {
+            let matches = index
+                .search(query_slice, remaining + forced_set.len())
+                .map_err(|e| PyErr::new::<VexusError, _>(format!("Search failed: {:?}", e)))?;
+
+            for (key, &dist) in matches.keys.iter().zip(matches.distances.iter()) {
+                if forced_set.contains(key) {
+                    continue;
+                }
+                if results.len() >= k as usize {
+                    break;
+                }
+                results.push(SearchResult::from_key(*key, 1.0 - dist as f64, 0));
+            }
+        }
+        drop(index);
+
+        order.apply(&mut results);
+        self.log_query(
+            "search_with_forced",
+            k,
+            || format!("forced_ids={:?}, order={}", forced_ids, order_name),
+            query_slice,
+            results.iter().map(|r| (r.id, r.score)),
+        );
+        Ok(results)
+    }
+
+    /// 解释某个 ID 与 query 的匹配: 返回贡献最大的 top_dims 个维度
+    /// 度量为 L2sq，维度 d 的贡献为 -(q_d - v_d)^2，绝对值越大说明该维度越拉开距离
+    pub fn explain(&self, query: Vec<u8>, id: u64, top_dims: u32) -> PyResult<ExplainResult> {
+        let index = self.read_index()?;
+
+        let query_slice = &*f32_view(&query, "query", Some(self.config.dimensions as usize))?;
+
+        explain_one(&index, query_slice, id, top_dims as usize)
+    }
+
+    /// 批量解释一组检索结果 (通常直接传入 search 的返回值)
+    #[pyo3(signature = (query, results, top_dims=10))]
+    pub fn explain_results(&self, query: Vec<u8>, results: Vec<PyRef<'_, SearchResult>>, top_dims: u32) -> PyResult<Vec<ExplainResult>> {
+        let index = self.read_index()?;
+
+        let query_slice = &*f32_view(&query, "query", Some(self.config.dimensions as usize))?;
+
+        results
+            .iter()
+            .map(|r| explain_one(&index, query_slice, r.id as u64, top_dims as usize))
+            .collect()
+    }
+
+    /// 多探针鲁棒搜索
+    /// 对 query 加 n_probes 次高斯噪声 (sigma=noise_sigma) 并连同原始查询一起搜索，
+    /// 按每个 ID 出现的比例 (stability) 与平均名次聚合，按稳定性加权分数排序返回前 k 个。
+    /// 所有探针在同一把读锁下并行执行，期间释放 GIL。
+    #[pyo3(signature = (query, k, n_probes, noise_sigma, seed=0, order="best_first"))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn search_robust(
+        &self,
+        py: Python<'_>,
+        query: Vec<u8>,
+        k: u32,
+        n_probes: u32,
+        noise_sigma: f64,
+        seed: u64,
+        order: &str,
+    ) -> PyResult<Vec<RobustSearchResult>> {
+        self.check_result_size("k", k)?;
+        // 各探针的结果在聚合前同时驻留内存
+        self.check_result_size("(n_probes+1)*k", (n_probes as u64 + 1) * k as u64)?;
+        let order_name = order;
+        let order = ResultOrder::parse(order)?;
+        let query_slice = &*f32_view(&query, "query", Some(self.config.dimensions as usize))?;
+
+        let mut rng = SplitMix64::new(seed);
+        let mut probes: Vec<Vec<f32>> = Vec::with_capacity(n_probes as usize + 1);
+        probes.push(query_slice.to_vec());
+        for _ in 0..n_probes {
+            probes.push(
+                query_slice
+                    .iter()
+                    .map(|&x| x + (rng.next_gaussian() * noise_sigma) as f32)
+                    .collect(),
+            );
+        }
+
+        let index = self.read_index()?;
+
+        let all_matches = py.allow_threads(|| {
+            probes
+                .par_iter()
+                .map(|probe| index.search(probe, k as usize))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| PyErr::new::<VexusError, _>(format!("Search failed: {:?}", e)))?;
+        drop(index);
+
+        // id -> (出现次数, 名次之和, 分数之和)
+        let mut stats: hashbrown::HashMap<u64, (u32, f64, f64)> = hashbrown::HashMap::new();
+        for matches in &all_matches {
+            for (rank, (&key, &dist)) in matches.keys.iter().zip(matches.distances.iter()).enumerate() {
+                let entry = stats.entry(key).or_insert((0, 0.0, 0.0));
+                entry.0 += 1;
+                entry.1 += rank as f64;
+                entry.2 += 1.0 - dist as f64;
+            }
+        }
+
+        let total_probes = probes.len() as f64;
+        let mut results: Vec<RobustSearchResult> = stats
+            .into_iter()
+            .map(|(key, (hits, rank_sum, score_sum))| {
+                let stability = hits as f64 / total_probes;
+                let (namespace, id) = unpack_id(key);
+                RobustSearchResult {
+                    id: id as u32,
+                    namespace,
+                    score: score_sum / hits as f64 * stability,
+                    stability,
+                    mean_rank: rank_sum / hits as f64,
+                }
+            })
+            .collect();
+
+        results.sort_by(|a, b| b.score.total_cmp(&a.score).then((a.namespace, a.id).cmp(&(b.namespace, b.id))));
+        results.truncate(k as usize);
+        order.apply(&mut results);
+
+        self.log_query(
+            "search_robust",
+            k,
+            || format!("n_probes={}, noise_sigma={}, seed={}, order={}", n_probes, noise_sigma, seed, order_name),
+            query_slice,
+            results.iter().map(|r| (r.id, r.score)),
+        );
+        Ok(results)
+    }
+
+    /// Minkowski (Lp) 距离重排搜索
+    /// 先用 ANN 取 max(candidates, k) 个候选，再按 Lp 距离 (p >= 1) 在 Rust 中重排取前 k 个；
+    /// 召回率受候选集限制: 不在 ANN 候选中的向量即使 Lp 距离更近也不会返回，必要时调大 candidates。
+    /// score = 1 - Lp 距离 (越大越相关)
+    #[pyo3(signature = (query, k, p, candidates, order="best_first"))]
+    pub fn search_minkowski(&self, query: Vec<u8>, k: u32, p: f64, candidates: u32, order: &str) -> PyResult<Vec<SearchResult>> {
+        self.check_result_size("k", k)?;
+        self.check_result_size("candidates", candidates)?;
+        let order_name = order;
+        let order = ResultOrder::parse(order)?;
+        if !(p.is_finite() && p >= 1.0) {
+            return Err(PyErr::new::<InvalidArgumentError, _>(format!("p must be a finite number >= 1.0, got {}", p)));
+        }
+        let dim = self.config.dimensions as usize;
+        let query_slice = &*f32_view(&query, "query", Some(dim))?;
+
+        let index = self.read_index()?;
+        let matches = index
+            .search(query_slice, candidates.max(k) as usize)
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Search failed: {:?}", e)))?;
+
+        let mut results: Vec<SearchResult> = matches
+            .keys
+            .iter()
+            .filter_map(|&key| {
+                let v = get_vector(&index, key, dim)?;
+                Some(SearchResult::from_key(key, 1.0 - minkowski(query_slice, &v, p), 0))
+            })
+            .collect();
+        drop(index);
+
+        results.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.id.cmp(&b.id)));
+        results.truncate(k as usize);
+        order.apply(&mut results);
+
+        self.log_query(
+            "search_minkowski",
+            k,
+            || format!("p={}, candidates={}, order={}", p, candidates, order_name),
+            query_slice,
+            results.iter().map(|r| (r.id, r.score)),
+        );
+        Ok(results)
+    }
+
+    /// 按 Python 回调重排
+    /// 先取 max(pool, k) 个 ANN 候选 (释放 GIL)，再对每个候选调用 scorer(id, distance) -> float，
+    /// 按回调返回的分数取前 k 个 (score 即回调返回值)。回调抛异常时中止并原样抛出。
+    /// 回调执行时不持有索引锁，scorer 内部可以再查询本索引
+    #[pyo3(signature = (query, k, pool, scorer, order="best_first"))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn search_rerank(
+        &self,
+        py: Python<'_>,
+        query: Vec<u8>,
+        k: u32,
+        pool: u32,
+        scorer: PyObject,
+        order: &str,
+    ) -> PyResult<Vec<SearchResult>> {
+        self.check_result_size("k", k)?;
+        self.check_result_size("pool", pool)?;
+        let order_name = order;
+        let order = ResultOrder::parse(order)?;
+        let query_slice = &*f32_view(&query, "query", Some(self.config.dimensions as usize))?;
+
+        let candidates: Vec<(u64, f32)> = {
+            let index = self.read_index()?;
+            let matches = py
+                .allow_threads(|| index.search(query_slice, pool.max(k) as usize))
+                .map_err(|e| PyErr::new::<VexusError, _>(format!("Search failed: {:?}", e)))?;
+            matches.keys.into_iter().zip(matches.distances).collect()
+        };
+
+        let mut results = Vec::with_capacity(candidates.len());
+        for (key, dist) in candidates {
+            let mut result = SearchResult::from_key(key, 0.0, 0);
+            result.score = scorer.call1(py, (result.id, dist as f64))?.extract(py)?;
+            results.push(result);
+        }
+
+        results.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.id.cmp(&b.id)));
+        results.truncate(k as usize);
+        order.apply(&mut results);
+
+        self.log_query(
+            "search_rerank",
+            k,
+            || format!("pool={}, order={}", pool, order_name),
+            query_slice,
+            results.iter().map(|r| (r.id, r.score)),
+        );
+        Ok(results)
+    }
+
+    /// 遗忘曲线加权搜索 (Ebbinghaus)
+    /// 先按纯距离取 2k 个近邻，再把每个结果的相关度 1 / (1 + distance) 乘以 exp(-ln2 * (current_time - t) / half_life_seconds)
+    /// 后重排取前 k 个。相关度恒在 (0, 1]，衰减只会让旧记忆排得更靠后 (1 - distance 在距离大于 1 时为负，不能直接衰减)。
+    /// t 取 timestamps[key] (按索引 key 下标，未用 id_namespace 写入的 key 即 ID)；key 超出 timestamps 长度时
+    /// (带命名空间的 key 总是如此) 取 set_side_data 设置的时间戳，仍没有时不衰减。晚于 current_time 的时间戳按 0 秒计
+    #[pyo3(signature = (query, k, timestamps, current_time, half_life_seconds, order="best_first"))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn search_with_decay(
+        &self,
+        query: Vec<u8>,
+        k: u32,
+        timestamps: Vec<u64>,
+        current_time: u64,
+        half_life_seconds: u64,
+        order: &str,
+    ) -> PyResult<Vec<SearchResult>> {
+        self.check_result_size("k", k)?;
+        let order_name = order;
+        let order = ResultOrder::parse(order)?;
+        if half_life_seconds == 0 {
+            return Err(PyErr::new::<InvalidArgumentError, _>("half_life_seconds must be positive".to_string()));
+        }
+        let query_slice = &*f32_view(&query, "query", Some(self.config.dimensions as usize))?;
+
+        let matches = {
+            let index = self.read_index()?;
+            index
+                .search(query_slice, 2 * k as usize)
+                .map_err(|e| PyErr::new::<VexusError, _>(format!("Search failed: {:?}", e)))?
+        };
+
+        let mut results = {
+            let meta = self.meta.read()
+                .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+            matches
+                .keys
+                .iter()
+                .zip(matches.distances.iter())
+                .map(|(&key, &dist)| {
+                    let mut result = SearchResult::from_key(key, relevance(dist), 0);
+                    let timestamp = usize::try_from(key)
+                        .ok()
+                        .and_then(|i| timestamps.get(i))
+                        .copied()
+                        .or_else(|| meta.side.get(&key).and_then(|r| r.timestamp));
+                    if let Some(t) = timestamp {
+                        let age = current_time.saturating_sub(t) as f64;
+                        result.score *= (-std::f64::consts::LN_2 * age / half_life_seconds as f64).exp();
+                    }
+                    result
+                })
+                .collect::<Vec<_>>()
+        };
+
+        results.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.id.cmp(&b.id)));
+        results.truncate(k as usize);
+        order.apply(&mut results);
+
+        self.log_query(
+            "search_with_decay",
+            k,
+            || format!("current_time={}, half_life_seconds={}, order={}", current_time, half_life_seconds, order_name),
+            query_slice,
+            results.iter().map(|r| (r.id, r.score)),
+        );
+        Ok(results)
+    }
+
+    /// 按相似度加权随机抽取 n 个不重复的记忆 (为回复增加一点意外感)
+    /// 先取 pool 个近邻，按 softmax(score / temperature) 的分布无放回抽样 (Gumbel top-k: score / temperature
+    /// 加 Gumbel 噪声后取前 n)；temperature → 0 退化为普通 top-n，temperature 很大时接近在候选池中均匀抽样。
+    /// 同一 seed 结果确定；返回的 score 为原始分数，按抽中的先后排列
+    #[pyo3(signature = (query, n, temperature, pool, seed=0))]
+    pub fn sample_weighted(&self, query: Vec<u8>, n: u32, temperature: f64, pool: u32, seed: u64) -> PyResult<Vec<SearchResult>> {
+        self.check_result_size("pool", pool)?;
+        if !(temperature.is_finite() && temperature > 0.0) {
+            return Err(PyErr::new::<InvalidArgumentError, _>(format!(
+                "temperature must be a positive finite number, got {}",
+                temperature
+            )));
+        }
+        if n > pool {
+            return Err(PyErr::new::<InvalidArgumentError, _>(format!("n={} exceeds pool={}", n, pool)));
+        }
+        let query_slice = &*f32_view(&query, "query", Some(self.config.dimensions as usize))?;
+
+        let matches = {
+            let index = self.read_index()?;
+            index
+                .search(query_slice, pool as usize)
+                .map_err(|e| PyErr::new::<VexusError, _>(format!("Search failed: {:?}", e)))?
+        };
+
+        let mut rng = SplitMix64::new(seed);
+        let mut keyed: Vec<(f64, SearchResult)> = matches
+            .keys
+            .iter()
+            .zip(matches.distances.iter())
+            .map(|(&key, &dist)| {
+                let result = SearchResult::from_key(key, 1.0 - dist as f64, 0);
+                (result.score / temperature + rng.next_gumbel(), result)
+            })
+            .collect();
+        keyed.sort_by(|(a, ra), (b, rb)| b.total_cmp(a).then(ra.id.cmp(&rb.id)));
+        let results: Vec<SearchResult> = keyed.into_iter().take(n as usize).map(|(_, r)| r).collect();
+
+        self.log_query(
+            "sample_weighted",
+            n,
+            || format!("temperature={}, pool={}, seed={}", temperature, pool, seed),
+            query_slice,
+            results.iter().map(|r| (r.id, r.score)),
+        );
+        Ok(results)
+    }
+
+    /// SM-2 间隔重复的复习优先级 (越大越该复习，>= 1 表示已到期)，时间单位为秒
+    /// 难度取 difficulty (0..=1) 与该向量偏离全体质心程度 ((1 - cos) / 2) 的平均，越偏离常见情绪簇越难；
+    /// 质量分 q = 5 * (1 - 难度) 按 SM-2 推算 n_reviews 次复习后的间隔，返回 已过时间 / 间隔。
+    /// 每次调用都要重新计算质心，代价 O(n * dim)，期间释放 GIL
+    #[allow(clippy::too_many_arguments)]
+    pub fn compute_review_priority(
+        &self,
+        py: Python<'_>,
+        id: u32,
+        last_review_time: u64,
+        n_reviews: u32,
+        difficulty: f64,
+        current_time: u64,
+    ) -> PyResult<f64> {
+        if !(0.0..=1.0).contains(&difficulty) {
+            return Err(PyErr::new::<InvalidArgumentError, _>(format!("difficulty must be in [0, 1], got {}", difficulty)));
+        }
+        let index = self.read_index()?;
+        let ids: Vec<u64> = {
+            let meta = self.meta.read()
+                .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+            meta.keys.iter().copied().collect()
+        };
+
+        let dim = self.config.dimensions as usize;
+        let vector = get_vector(&index, id as u64, dim)
+            .ok_or_else(|| PyErr::new::<NotFoundError, _>(format!("ID {} not found in index", id)))?;
+        let centroid: Vec<f32> = py
+            .allow_threads(|| compute_centroid(&index, &ids, dim))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|x| x as f32)
+            .collect();
+
+        let hardness = (1.0 - cosine(&vector, &centroid)) / 2.0;
+        let quality = 5.0 * (1.0 - (difficulty + hardness) / 2.0);
+        let elapsed_days = current_time.saturating_sub(last_review_time) as f64 / SECONDS_PER_DAY;
+        Ok(elapsed_days / sm2_interval_days(quality, n_reviews))
+    }
+
+    /// 语义与时间接近度混合排序
+    /// 先按纯距离取 2k 个近邻，最终分数 = alpha * semantic_score + (1 - alpha) * recency_score 后重排取前 k 个。
+    /// semantic_score 为相关度 1 / (1 + distance)；recency_score 为 1 / (1 + |current_time - t|)
+    /// 再除以候选中的最大值归一化到 [0, 1]，t 取 ids_with_timestamps 中该 ID 的时间戳，没有时记 0。
+    /// 两项都在 [0, 1] 内，alpha 即语义所占的权重
+    #[pyo3(signature = (query, k, ids_with_timestamps, current_time, alpha, order="best_first"))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn search_recency_biased(
+        &self,
+        query: Vec<u8>,
+        k: u32,
+        ids_with_timestamps: Vec<(u32, u64)>,
+        current_time: u64,
+        alpha: f64,
+        order: &str,
+    ) -> PyResult<Vec<SearchResult>> {
+        self.check_result_size("k", k)?;
+        let order_name = order;
+        let order = ResultOrder::parse(order)?;
+        if !(0.0..=1.0).contains(&alpha) {
+            return Err(PyErr::new::<InvalidArgumentError, _>(format!("alpha must be in [0, 1], got {}", alpha)));
+        }
+        let query_slice = &*f32_view(&query, "query", Some(self.config.dimensions as usize))?;
+
+        let matches = {
+            let index = self.read_index()?;
+            index
+                .search(query_slice, 2 * k as usize)
+                .map_err(|e| PyErr::new::<VexusError, _>(format!("Search failed: {:?}", e)))?
+        };
+
+        let timestamps: HashMap<u32, u64> = ids_with_timestamps.into_iter().collect();
+        let candidates: Vec<(SearchResult, f64)> = matches
+            .keys
+            .iter()
+            .zip(matches.distances.iter())
+            .map(|(&key, &dist)| {
+                let result = SearchResult::from_key(key, relevance(dist), 0);
+                let inverse = timestamps
+                    .get(&result.id)
+                    .map_or(0.0, |&t| 1.0 / (1.0 + current_time.abs_diff(t) as f64));
+                (result, inverse)
+            })
+            .collect();
+        let max_inverse = candidates.iter().map(|(_, inv)| *inv).fold(0.0f64, f64::max);
+
+        let mut results: Vec<SearchResult> = candidates
+            .into_iter()
+            .map(|(mut result, inverse)| {
+                let recency = if max_inverse > 0.0 { inverse / max_inverse } else { 0.0 };
+                result.score = alpha * result.score + (1.0 - alpha) * recency;
+                result
+            })
+            .collect();
+
+        results.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.id.cmp(&b.id)));
+        results.truncate(k as usize);
+        order.apply(&mut results);
+
+        self.log_query(
+            "search_recency_biased",
+            k,
+            || format!("current_time={}, alpha={}, order={}", current_time, alpha, order_name),
+            query_slice,
+            results.iter().map(|r| (r.id, r.score)),
+        );
+        Ok(results)
+    }
+
+    /// 搜索并在同一次调用中从 SQLite 取回文本，返回 (id, score, text)，顺序与 search 相同
+    /// 在一个读事务内用预编译语句逐个按 id_col 查 text_col；表中没有对应行或文本为 NULL 的结果跳过。
+    /// table / id_col / text_col 只允许字母、数字与下划线 (会直接拼进 SQL)
+    #[allow(clippy::too_many_arguments)]
+    pub fn search_with_text(
+        &self,
+        py: Python<'_>,
+        query: Vec<u8>,
+        k: u32,
+        db_path: String,
+        table: String,
+        id_col: String,
+        text_col: String,
+    ) -> PyResult<Vec<(u32, f64, String)>> {
+        self.check_open()?;
+        for (name, value) in [("table", &table), ("id_col", &id_col), ("text_col", &text_col)] {
+            if value.is_empty() || !value.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                return Err(PyErr::new::<InvalidArgumentError, _>(format!(
+                    "Invalid {} '{}': only ASCII letters, digits and '_' are allowed",
+                    name, value
+                )));
+            }
+        }
+        let results = self.search(py, query, k, false, None, "best_first", None, false)?;
+
+        let mut conn = Connection::open(&db_path)
+            .map_err(|e| PyErr::new::<SqliteError, _>(format!("Failed to open DB: {}", e)))?;
+        let sqlite_err = |e: rusqlite::Error| PyErr::new::<SqliteError, _>(format!("Text lookup failed: {}", e));
+        let tx = conn.transaction().map_err(sqlite_err)?;
+        let mut enriched = Vec::with_capacity(results.len());
+        {
+            let mut stmt = tx
+                .prepare(&format!("SELECT \"{}\" FROM \"{}\" WHERE \"{}\" = ?1", text_col, table, id_col))
+                .map_err(sqlite_err)?;
+            for result in &results {
+                let text: Option<Option<String>> = stmt
+                    .query_row([result.id], |row| row.get(0))
+                    .optional()
+                    .map_err(sqlite_err)?;
+                if let Some(Some(text)) = text {
+                    enriched.push((result.id, result.score, text));
+                }
+            }
+        }
+        tx.commit().map_err(sqlite_err)?;
+        Ok(enriched)
+    }
+
+    /// 前 k 个近邻的分数，从高到低，供界面绘制相似度衰减曲线
+    /// 等同于只取 score 的 search (calibrated 含义相同)，不记查询日志
+    #[pyo3(signature = (query, k, calibrated=false))]
+    pub fn score_profile(&self, query: Vec<u8>, k: u32, calibrated: bool) -> PyResult<Vec<f64>> {
+        self.check_result_size("k", k)?;
+        let query_slice = &*f32_view(&query, "query", Some(self.config.dimensions as usize))?;
+        let matches = {
+            let index = self.read_index()?;
+            index
+                .search(query_slice, k as usize)
+                .map_err(|e| PyErr::new::<VexusError, _>(format!("Search failed: {:?}", e)))?
+        };
+        let matches: Vec<(u64, f32)> = matches.keys.into_iter().zip(matches.distances).collect();
+        let mut scores: Vec<f64> = self.score_matches(&matches, calibrated)?.into_iter().map(|r| r.score).collect();
+        scores.sort_by(|a, b| b.total_cmp(a));
+        Ok(scores)
+    }
+
+    /// 按距离断层自适应选择 k: 取 max_k 个近邻后在距离序列中找截断点，返回 (截断后的结果, 截断依据)
+    /// method: "elbow" 取相邻距离相对跳变最大处；"threshold_knee" 为 Kneedle 式拐点
+    /// (距离曲线归一化到 [0, 1] 后 x - y 最大处)。不足 3 个结果时全部返回，距离全相同时返回全部 max_k 个
+    #[pyo3(signature = (query, max_k, method="elbow"))]
+    pub fn search_adaptive(&self, query: Vec<u8>, max_k: u32, method: &str) -> PyResult<(Vec<SearchResult>, AdaptiveCutoff)> {
+        self.check_result_size("max_k", max_k)?;
+        if method != "elbow" && method != "threshold_knee" {
+            return Err(PyErr::new::<InvalidArgumentError, _>(format!(
+                "method must be 'elbow' or 'threshold_knee', got '{}'",
+                method
+            )));
+        }
+        let query_slice = &*f32_view(&query, "query", Some(self.config.dimensions as usize))?;
+
+        let matches = {
+            let index = self.read_index()?;
+            index
+                .search(query_slice, max_k as usize)
+                .map_err(|e| PyErr::new::<VexusError, _>(format!("Search failed: {:?}", e)))?
+        };
+
+        let distances: Vec<f64> = matches.distances.iter().map(|&d| d as f64).collect();
+        let (suggested_k, gaps, criterion) = adaptive_cutoff(&distances, method);
+
+        let results: Vec<SearchResult> = matches
+            .keys
+            .iter()
+            .zip(matches.distances.iter())
+            .take(suggested_k)
+            .map(|(&key, &dist)| SearchResult::from_key(key, 1.0 - dist as f64, 0))
+            .collect();
+
+        self.log_query(
+            "search_adaptive",
+            max_k,
+            || format!("method={}, suggested_k={}", method, suggested_k),
+            query_slice,
+            results.iter().map(|r| (r.id, r.score)),
+        );
+        let cutoff = AdaptiveCutoff { suggested_k: suggested_k as u32, method: method.to_string(), gaps, criterion };
+        Ok((results, cutoff))
+    }
+
+    /// 分页搜索: 返回 (本页结果, 下一页游标)，最后一页的游标为 None
+    /// 首页 (cursor=None) 按 PAGE_CANDIDATE_CAP 过采样并缓存候选列表 (按距离升序、同距离按 ID 升序)，
+    /// 之后的页从缓存中上一条 (距离, ID) 之后继续，不重新搜索，翻页结果与首页时的快照一致。
+    /// 游标为不透明字节，包含查询哈希与上一条的距离和 ID；会话超过 PAGE_SESSION_TTL 或
+    /// 游标与 query 不符时抛 CursorExpiredError，调用方应以 cursor=None 重新开始
+    #[pyo3(signature = (query, page_size, cursor=None))]
+    pub fn search_paged(
+        &self,
+        py: Python<'_>,
+        query: Vec<u8>,
+        page_size: u32,
+        cursor: Option<Vec<u8>>,
+    ) -> PyResult<(Vec<SearchResult>, Option<Py<PyBytes>>)> {
+        self.check_open()?;
+        self.check_result_size("page_size", page_size)?;
+        if page_size == 0 {
+            return Err(PyErr::new::<InvalidArgumentError, _>("page_size must be at least 1".to_string()));
+        }
+        let query_slice = &*f32_view(&query, "query", Some(self.config.dimensions as usize))?;
+        let query_hash = vector_hash(query_slice);
+        let expired = |reason: &str| {
+            PyErr::new::<CursorExpiredError, _>(format!("{}, restart paging with cursor=None", reason))
+        };
+
+        let fresh = match cursor {
+            Some(_) => None,
+            None => {
+                let index = self.read_index()?;
+                let matches = index
+                    .search(query_slice, index.size().min(PAGE_CANDIDATE_CAP))
+                    .map_err(|e| PyErr::new::<VexusError, _>(format!("Search failed: {:?}", e)))?;
+                let mut candidates: Vec<(u64, f32)> = matches.keys.into_iter().zip(matches.distances).collect();
+                candidates.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+                Some(candidates)
+            }
+        };
+
+        let mut cache = self.page_cache.lock()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+        let now = std::time::Instant::now();
+        cache.sessions.retain(|_, session| now.duration_since(session.created) < PAGE_SESSION_TTL);
+
+        let (session_id, start) = match (fresh, &cursor) {
+            (Some(candidates), _) => {
+                if cache.sessions.len() >= PAGE_MAX_SESSIONS
+                    && let Some(oldest) = cache.sessions.iter().min_by_key(|(_, s)| s.created).map(|(&id, _)| id)
+                {
+                    cache.sessions.remove(&oldest);
+                }
+                let session_id = cache.next_id;
+                cache.next_id += 1;
+                cache.sessions.insert(session_id, PageSession { query_hash, candidates, created: now });
+                (session_id, 0)
+            }
+            (None, Some(bytes)) => {
+                if bytes.len() != PAGE_CURSOR_LEN {
+                    return Err(expired("Malformed cursor"));
+                }
+                let session_id = u64::from_le_bytes(bytes[0..8].try_into().unwrap());
+                let cursor_hash = u64::from_le_bytes(bytes[8..16].try_into().unwrap());
+                let last_dist = f32::from_le_bytes(bytes[16..20].try_into().unwrap());
+                let last_key = u64::from_le_bytes(bytes[20..28].try_into().unwrap());
+                let session = cache.sessions.get(&session_id).ok_or_else(|| expired("Cursor expired"))?;
+                if cursor_hash != query_hash || session.query_hash != query_hash {
+                    return Err(expired("Cursor does not belong to this query"));
+                }
+                let start = session
+                    .candidates
+                    .partition_point(|&(key, dist)| dist.total_cmp(&last_dist).then(key.cmp(&last_key)).is_le());
+                (session_id, start)
+            }
+            (None, None) => unreachable!(),
+        };
+
+        let candidates = &cache.sessions[&session_id].candidates;
+        let end = (start + page_size as usize).min(candidates.len());
+        let page = &candidates[start..end];
+        let results = page
+            .iter()
+            .map(|&(key, dist)| SearchResult::from_key(key, 1.0 - dist as f64, 0))
+            .collect();
+        let next = match page.last() {
+            Some(&(key, dist)) if end < candidates.len() => {
+                let mut bytes = Vec::with_capacity(PAGE_CURSOR_LEN);
+                bytes.extend_from_slice(&session_id.to_le_bytes());
+                bytes.extend_from_slice(&query_hash.to_le_bytes());
+                bytes.extend_from_slice(&dist.to_le_bytes());
+                bytes.extend_from_slice(&key.to_le_bytes());
+                Some(PyBytes::new_bound(py, &bytes).unbind())
+            }
+            _ => None,
+        };
+        Ok((results, next))
+    }
+
+    /// 多跳搜索 (沿 KNN 图做 BFS)
+    /// 从 query_id 出发，每一层取 k_per_hop 个近邻，按 ID 去重，共扩展 n_hops 层。
+    /// score 相对于起点向量计算，hop 为首次发现该向量时的跳数；结果按 score 排序。
+    #[pyo3(signature = (query_id, n_hops, k_per_hop, order="best_first"))]
+    pub fn search_multi_hop(&self, query_id: u32, n_hops: u32, k_per_hop: u32, order: &str) -> PyResult<Vec<SearchResult>> {
+        self.check_result_size("k_per_hop", k_per_hop)?;
+        let order = ResultOrder::parse(order)?;
+        let index = self.read_index()?;
+
+        let dim = self.config.dimensions as usize;
+        let root = get_vector(&index, query_id as u64, dim)
+            .ok_or_else(|| PyErr::new::<NotFoundError, _>(format!("ID {} not found in index", query_id)))?;
+
+        let mut visited: HashSet<u64> = HashSet::new();
+        visited.insert(query_id as u64);
+
+        let mut frontier: VecDeque<Vec<f32>> = VecDeque::new();
+        frontier.push_back(root.clone());
+
+        let mut results = Vec::new();
+
+        // 结果总数随跳数近似指数增长，达到 max_results 即停止扩展
+        'hops: for hop in 1..=n_hops {
+            let mut next = VecDeque::new();
+
+            while let Some(vector) = frontier.pop_front() {
+                // 多取一个，因为结果中通常包含节点自身
+                let matches = index
+                    .search(&vector, k_per_hop as usize + 1)
+                    .map_err(|e| PyErr::new::<VexusError, _>(format!("Search failed: {:?}", e)))?;
+
+                for &key in matches.keys.iter() {
+                    if !visited.insert(key) {
+                        continue;
+                    }
+                    if let Some(neighbor) = get_vector(&index, key, dim) {
+                        results.push(SearchResult::from_key(key, 1.0 - l2sq(&root, &neighbor), hop));
+                        next.push_back(neighbor);
+                        if results.len() >= self.max_results as usize {
+                            break 'hops;
+                        }
+                    }
+                }
+            }
+
+            if next.is_empty() {
+                break;
+            }
+            frontier = next;
+        }
+
+        results.sort_by(|a, b| b.score.total_cmp(&a.score));
+        order.apply(&mut results);
+
+        Ok(results)
+    }
+
+    /// 拟合分数校准参数
+    /// 随机采样 sample 个已存向量，统计两两距离的经验分布并保存分位点 (随 save/load 持久化)
+    pub fn fit_score_calibration(&self, sample: u32, seed: u64) -> PyResult<CalibrationInfo> {
+        self.check_writable()?;
+        let index = self.read_index()?;
+
+        let dim = self.config.dimensions as usize;
+        let ids = {
+            let meta = self.meta.read()
+                .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+            sample_keys(&meta.keys, sample as usize, seed)
+        };
+
+        let vectors: Vec<Vec<f32>> = ids.iter().filter_map(|&id| get_vector(&index, id, dim)).collect();
+        if vectors.len() < 2 {
+            return Err(PyErr::new::<InvalidArgumentError, _>(
+                "Score calibration needs at least 2 stored vectors".to_string(),
+            ));
+        }
+
+        let mut distances = Vec::with_capacity(vectors.len() * (vectors.len() - 1) / 2);
+        for i in 0..vectors.len() {
+            for j in (i + 1)..vectors.len() {
+                distances.push(l2sq(&vectors[i], &vectors[j]));
+            }
+        }
+        distances.sort_by(|a, b| a.total_cmp(b));
+
+        let last = distances.len() - 1;
+        let quantiles = (0..=100)
+            .map(|p| distances[(p * last + 50) / 100])
+            .collect();
+
+        let info = CalibrationInfo {
+            quantiles,
+            sample_size: vectors.len() as u32,
+            seed,
+            n_pairs: distances.len() as u64,
+        };
+
+        drop(index);
+        self.meta.write()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?
+            .calibration = Some(info.clone());
+
+        Ok(info)
+    }
+
+    /// 当前的分数校准参数 (未拟合时返回 None)
+    pub fn calibration_info(&self) -> PyResult<Option<CalibrationInfo>> {
+        self.check_open()?;
+        let meta = self.meta.read()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+
+        Ok(meta.calibration.clone())
+    }
+
+    /// 均匀随机采样 n 个已索引 key (蓄水池采样 Algorithm R)，相同 seed 结果可复现
+    /// 返回索引 key: 未用 id_namespace 写入的即 ID，带命名空间的可用 unpack_id 拆开
+    pub fn sample_random(&self, n: u32, seed: u64) -> PyResult<Vec<u64>> {
+        self.check_open()?;
+        let meta = self.meta.read()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+
+        let mut ids: Vec<u64> = meta.keys.iter().copied().collect();
+        ids.sort_unstable();
+
+        let n = n as usize;
+        let mut rng = SplitMix64::new(seed);
+        let mut reservoir: Vec<u64> = Vec::with_capacity(n.min(ids.len()));
+
+        for (i, &key) in ids.iter().enumerate() {
+            if i < n {
+                reservoir.push(key);
+            } else {
+                let j = rng.below(i + 1);
+                if j < n {
+                    reservoir[j] = key;
+                }
+            }
+        }
+
+        Ok(reservoir)
+    }
+
+    /// 分层采样: 对 labels (ID -> 类别) 中的每个类别各抽 n_per_class 个已索引的 ID
+    /// 任一类别的已索引成员不足 n_per_class 时抛 ValueError；结果按类别升序排列
+    pub fn sample_stratified(&self, labels: HashMap<u32, u32>, n_per_class: u32, seed: u64) -> PyResult<Vec<u32>> {
+        self.check_open()?;
+        let meta = self.meta.read()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+
+        let mut classes: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+        for (&id, &label) in &labels {
+            let members = classes.entry(label).or_default();
+            if meta.keys.contains(&(id as u64)) {
+                members.push(id);
+            }
+        }
+
+        let n = n_per_class as usize;
+        let mut rng = SplitMix64::new(seed);
+        let mut result = Vec::with_capacity(classes.len() * n);
+
+        for (label, members) in classes.iter_mut() {
+            if members.len() < n {
+                return Err(PyErr::new::<InvalidArgumentError, _>(format!(
+                    "Class {} has only {} indexed members, fewer than n_per_class={}",
+                    label,
+                    members.len(),
+                    n
+                )));
+            }
+
+            members.sort_unstable();
+            for i in 0..n {
+                let j = i + rng.below(members.len() - i);
+                members.swap(i, j);
+            }
+            result.extend_from_slice(&members[..n]);
+        }
+
+        Ok(result)
+    }
+
+    /// 多样性采样 (度量空间上的 Bridson 泊松圆盘采样)
+    /// 返回最多 n 个索引 key (带命名空间的见 unpack_id)，任意两个被选中的向量欧氏距离 (L2sq 开方) 不小于 min_distance。
+    /// 从活动点的近邻中挑选落在 [r, 2r] 环带内的候选；活动列表耗尽时从剩余 ID 中随机选新种子。
+    #[pyo3(signature = (n, min_distance, seed=0))]
+    pub fn sample_diverse(&self, n: u32, min_distance: f64, seed: u64) -> PyResult<Vec<u64>> {
+        const ATTEMPTS: usize = 30;
+
+        let index = self.read_index()?;
+
+        let dim = self.config.dimensions as usize;
+        let candidates = {
+            let meta = self.meta.read()
+                .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+            sample_keys(&meta.keys, meta.keys.len(), seed)
+        };
+
+        let r_sq = min_distance * min_distance;
+        let mut accepted: Vec<(u64, Vec<f32>)> = Vec::new();
+        let mut visited: HashSet<u64> = HashSet::new();
+        let mut active: Vec<usize> = Vec::new();
+        let mut rng = SplitMix64::new(seed);
+
+        let fits = |accepted: &[(u64, Vec<f32>)], v: &[f32]| accepted.iter().all(|(_, a)| l2sq(a, v) >= r_sq);
+
+        let mut next_seed = candidates.iter();
+        while accepted.len() < n as usize {
+            if active.is_empty() {
+                // 活动列表耗尽，随机取一个尚未访问的 ID 作为新种子
+                let Some(&id) = next_seed.by_ref().find(|id| !visited.contains(*id)) else {
+                    break;
+                };
+                visited.insert(id);
+                if let Some(v) = get_vector(&index, id, dim)
+                    && fits(&accepted, &v)
+                {
+                    accepted.push((id, v));
+                    active.push(accepted.len() - 1);
+                }
+                continue;
+            }
+
+            let slot = rng.below(active.len());
+            let center = accepted[active[slot]].1.clone();
+            let matches = index
+                .search(&center, ATTEMPTS)
+                .map_err(|e| PyErr::new::<VexusError, _>(format!("Search failed: {:?}", e)))?;
+
+            let mut found = false;
+            for (&key, &dist) in matches.keys.iter().zip(matches.distances.iter()) {
+                let dist = dist as f64;
+                if dist < r_sq || dist > 4.0 * r_sq || !visited.insert(key) {
+                    continue;
+                }
+                if let Some(v) = get_vector(&index, key, dim)
+                    && fits(&accepted, &v)
+                {
+                    accepted.push((key, v));
+                    active.push(accepted.len() - 1);
+                    found = true;
+                    break;
+                }
+            }
+
+            if !found {
+                active.swap_remove(slot);
+            }
+        }
+
+        Ok(accepted.into_iter().map(|(key, _)| key).collect())
+    }
+
+    /// 所有已存向量的均值，以 f32 字节返回 (可直接作为 project 的 mean_vector)
+    /// 并行流式累加，不会一次性物化全部向量；期间释放 GIL
+    pub fn centroid(&self, py: Python<'_>) -> PyResult<Py<PyBytes>> {
+        let index = self.read_index()?;
+        let ids: Vec<u64> = {
+            let meta = self.meta.read()
+                .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+            meta.keys.iter().copied().collect()
+        };
+
+        let dim = self.config.dimensions as usize;
+        let mean = py
+            .allow_threads(|| compute_centroid(&index, &ids, dim))
+            .ok_or_else(|| PyErr::new::<InvalidArgumentError, _>("Cannot compute centroid of an empty index".to_string()))?;
+
+        let mean_f32: Vec<f32> = mean.iter().map(|&x| x as f32).collect();
+        Ok(PyBytes::new_bound(py, &f32_to_bytes(&mean_f32)).unbind())
+    }
+
+    /// 把所有已存向量减去质心 (原地重新居中)，返回被减去的质心 (f32 字节) 以便调用方还原
+    /// 代价: 逐个 remove + 重新 add，相当于全量重建，期间持有写锁
+    /// 开启写入归一化时报 ValueError: 居中后的向量不再是单位长度，重新归一化又无法用返回的质心还原
+    pub fn recenter(&self, py: Python<'_>) -> PyResult<Py<PyBytes>> {
+        let index = self.write_index()?;
+        let mut meta = self.meta.write()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+        if meta.normalize.is_some() {
+            return Err(PyErr::new::<InvalidArgumentError, _>(
+                "Cannot recenter while normalization is enabled".to_string(),
+            ));
+        }
+
+        let dim = self.config.dimensions as usize;
+        let mut ids: Vec<u64> = meta.keys.iter().copied().collect();
+        ids.sort_unstable();
+
+        let mean: Vec<f32> = py
+            .allow_threads(|| compute_centroid(&index, &ids, dim))
+            .ok_or_else(|| PyErr::new::<InvalidArgumentError, _>("Cannot recenter an empty index".to_string()))?
+            .into_iter()
+            .map(|x| x as f32)
+            .collect();
+
+        let hashing = meta.hashes.is_some();
+        let rewritten = py.allow_threads(|| {
+            // (id, 新向量哈希)，未开启哈希登记时哈希为 0 且不会被使用
+            let mut rewritten = Vec::with_capacity(ids.len());
+            for &id in &ids {
+                let Some(mut v) = get_vector(&index, id, dim) else {
+                    continue;
+                };
+                for (x, &m) in v.iter_mut().zip(mean.iter()) {
+                    *x -= m;
+                }
+                index.remove(id)
+                    .map_err(|e| PyErr::new::<VexusError, _>(format!("Remove failed for id {}: {:?}", id, e)))?;
+                index.add(id, &v)
+                    .map_err(|e| PyErr::new::<VexusError, _>(format!("Re-add failed for id {}: {:?}", id, e)))?;
+                rewritten.push((id, if hashing { vector_hash(&v) } else { 0 }));
+            }
+            Ok::<_, PyErr>(rewritten)
+        })?;
+        // 每个重写的向量都在 usearch 中留下一个被删除的旧节点
+        meta.tombstones += rewritten.len() as u64;
+        if let Some(hashes) = &mut meta.hashes {
+            hashes.extend(rewritten);
+        }
+        meta.lifetime.last_compaction = Some(now_secs());
+
+        Ok(PyBytes::new_bound(py, &f32_to_bytes(&mean)).unbind())
+    }
+
+    /// 预热索引，避免 load 后第一次查询的缺页延迟
+    /// 以随机抽取的已存向量 (或调用方提供的 sample，扁平 f32 字节) 为查询顺序执行 n_queries 次搜索，
+    /// 期间释放 GIL，返回每次耗时供运维确认效果。空索引直接返回空报告；任何一次搜索失败都直接抛出。
+    #[pyo3(signature = (n_queries, seed=0, sample=None))]
+    pub fn warmup(&self, py: Python<'_>, n_queries: u32, seed: u64, sample: Option<Vec<u8>>) -> PyResult<WarmupReport> {
+        let index = self.read_index()?;
+
+        let dim = self.config.dimensions as usize;
+        let queries: Vec<Vec<f32>> = match &sample {
+            Some(bytes) => {
+                let sample_slice = &*f32_view(bytes, "sample", None)?;
+                if !sample_slice.len().is_multiple_of(dim) {
+                    return Err(PyErr::new::<DimensionMismatchError, _>(format!(
+                        "sample has {} f32 elements, not a multiple of dimension {}",
+                        sample_slice.len(),
+                        dim
+                    )));
+                }
+                sample_slice.chunks(dim).map(|c| c.to_vec()).collect()
+            }
+            None => {
+                let meta = self.meta.read()
+                    .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+                sample_keys(&meta.keys, n_queries as usize, seed)
+                    .into_iter()
+                    .filter_map(|id| get_vector(&index, id, dim))
+                    .collect()
+            }
+        };
+
+        if index.size() == 0 || queries.is_empty() {
+            return Ok(WarmupReport {
+                n_queries: 0,
+                latencies_ms: Vec::new(),
+                first_ms: 0.0,
+                steady_ms: 0.0,
+                improvement: 1.0,
+            });
+        }
+
+        let latencies_ms = py.allow_threads(|| {
+            (0..n_queries as usize)
+                .map(|i| {
+                    let start = std::time::Instant::now();
+                    index.search(&queries[i % queries.len()], 10)
+                        .map_err(|e| PyErr::new::<VexusError, _>(format!("Warmup search failed: {:?}", e)))?;
+                    Ok(start.elapsed().as_secs_f64() * 1000.0)
+                })
+                .collect::<PyResult<Vec<f64>>>()
+        })?;
+
+        let first_ms = latencies_ms.first().copied().unwrap_or(0.0);
+        let tail = &latencies_ms[latencies_ms.len() / 2..];
+        let steady_ms = if tail.is_empty() { first_ms } else { tail.iter().sum::<f64>() / tail.len() as f64 };
+
+        Ok(WarmupReport {
+            n_queries: latencies_ms.len() as u32,
+            improvement: if steady_ms > 0.0 { first_ms / steady_ms } else { 1.0 },
+            latencies_ms,
+            first_ms,
+            steady_ms,
+        })
+    }
+
+    /// 抽样 sample 个向量计算健康指标 (同一 seed 结果确定)，向量数不足 sample 时用全部
+    /// 阈值判断留给 Python，这里只做测量
+    #[pyo3(signature = (sample, seed=0))]
+    pub fn corpus_health(&self, py: Python<'_>, sample: u32, seed: u64) -> PyResult<CorpusHealth> {
+        let vectors: Vec<Vec<f32>> = {
+            let index = self.read_index()?;
+            let meta = self.meta.read()
+                .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+            let dim = self.config.dimensions as usize;
+            sample_keys(&meta.keys, sample as usize, seed)
+                .into_iter()
+                .filter_map(|id| get_vector(&index, id, dim))
+                .collect()
+        };
+        let dim = self.config.dimensions as usize;
+
+        Ok(py.allow_threads(|| corpus_health(&vectors, dim)))
+    }
+
+    /// 启动时的完整性自检，不修改任何状态: size() 与登记的 ID 数是否一致、索引维度是否与配置一致、
+    /// 按 seed 抽样 sample 个 ID 能否取回分量有限的向量、以抽到的向量为查询 (最多 SELF_CHECK_QUERIES 次)
+    /// 的搜索能否完成并返回结果。全程持有读锁，检查时释放 GIL
+    #[pyo3(signature = (sample, seed=0))]
+    pub fn self_check(&self, py: Python<'_>, sample: u32, seed: u64) -> PyResult<IntegrityReport> {
+        let index = self.read_index()?;
+        let meta = self.meta.read()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+        let keys = &meta.keys;
+        let dim = self.config.dimensions as usize;
+
+        Ok(py.allow_threads(|| {
+            let index_size = index.size() as u64;
+            let key_count = keys.len() as u64;
+            let index_dim = index.dimensions();
+
+            let sampled = sample_keys(keys, sample as usize, seed);
+            let mut missing_keys = Vec::new();
+            let mut non_finite_keys = Vec::new();
+            let mut queries = Vec::new();
+            for &key in &sampled {
+                match get_vector(&index, key, dim) {
+                    None => missing_keys.push(key),
+                    Some(v) if !v.iter().all(|x| x.is_finite()) => non_finite_keys.push(key),
+                    Some(v) => queries.push(v),
+                }
+            }
+            missing_keys.sort_unstable();
+            non_finite_keys.sort_unstable();
+
+            let mut query_errors = Vec::new();
+            let mut queries_run = 0u32;
+            for q in queries.iter().take(SELF_CHECK_QUERIES) {
+                queries_run += 1;
+                match index.search(q, 1) {
+                    Ok(matches) if !matches.keys.is_empty() => {}
+                    Ok(_) => query_errors.push("search for a stored vector returned no results".to_string()),
+                    Err(e) => query_errors.push(format!("search failed: {:?}", e)),
+                }
+            }
+
+            let size_ok = index_size == key_count;
+            let dimensions_ok = index_dim == dim;
+            let vectors_ok = missing_keys.is_empty() && non_finite_keys.is_empty();
+            let queries_ok = query_errors.is_empty();
+
+            let mut failures = Vec::new();
+            if !size_ok {
+                failures.push(format!("size mismatch: index holds {} vectors, sidecar lists {} ids", index_size, key_count));
+            }
+            if !dimensions_ok {
+                failures.push(format!("dimension mismatch: index file has {}, config has {}", index_dim, dim));
+            }
+            if !missing_keys.is_empty() {
+                failures.push(format!("{} sampled keys have no stored vector: {:?}", missing_keys.len(), missing_keys));
+            }
+            if !non_finite_keys.is_empty() {
+                failures.push(format!("{} sampled keys have non-finite vectors: {:?}", non_finite_keys.len(), non_finite_keys));
+            }
+            failures.extend(query_errors.iter().cloned());
+
+            IntegrityReport {
+                passed: failures.is_empty(),
+                size_ok,
+                index_size,
+                key_count,
+                dimensions_ok,
+                vectors_ok,
+                sampled: sampled.len() as u32,
+                missing_keys,
+                non_finite_keys,
+                queries_ok,
+                queries_run,
+                query_errors,
+                failures,
+            }
+        }))
+    }
+
+    /// 生命周期统计 (累计增删、恢复、保存次数)
+    pub fn lifetime_stats(&self) -> PyResult<LifetimeStats> {
+        self.check_open()?;
+        let meta = self.meta.read()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+
+        Ok(meta.lifetime.clone())
+    }
+
+    /// 标签传播 (半监督)
+    /// 从带标签的种子出发按 BFS 扩展 max_hops 层，每个节点取 k 个近邻；
+    /// 未标注的近邻继承本层中离它最近的已标注节点的标签。返回完整的 key -> 标签映射。
+    /// seed_ids 与返回值都以索引 key 为键: 未用 id_namespace 写入的即 ID，带命名空间的见 pack_id / unpack_id
+    pub fn propagate_labels(&self, seed_ids: HashMap<u64, u32>, k: u32, max_hops: u32) -> PyResult<HashMap<u64, u32>> {
+        let index = self.read_index()?;
+
+        let dim = self.config.dimensions as usize;
+        let mut labels: HashMap<u64, u32> = seed_ids.clone();
+        let mut frontier: Vec<u64> = seed_ids.keys().copied().collect();
+        frontier.sort_unstable();
+
+        for _ in 0..max_hops {
+            // 新节点 -> (最近距离, 标签)
+            let mut candidates: BTreeMap<u64, (f32, u32)> = BTreeMap::new();
+
+            for &key in &frontier {
+                let Some(vector) = get_vector(&index, key, dim) else {
+                    continue;
+                };
+                let label = labels[&key];
+                let matches = index
+                    .search(&vector, k as usize + 1)
+                    .map_err(|e| PyErr::new::<VexusError, _>(format!("Search failed: {:?}", e)))?;
+
+                for (&neighbor, &dist) in matches.keys.iter().zip(matches.distances.iter()) {
+                    if labels.contains_key(&neighbor) {
+                        continue;
+                    }
+                    let entry = candidates.entry(neighbor).or_insert((dist, label));
+                    if dist < entry.0 {
+                        *entry = (dist, label);
+                    }
+                }
+            }
+
+            if candidates.is_empty() {
+                break;
+            }
+
+            frontier = candidates.keys().copied().collect();
+            for (id, (_, label)) in candidates {
+                labels.insert(id, label);
+            }
+        }
+
+        Ok(labels)
+    }
+
+    /// 加权 k-NN 标签聚合: 根据近邻的标签给出 id 的标签概率分布
+    /// distance_weights=true 时按 1 / (L2sq + 1e-9) 加权，否则每个近邻权重相同
+    pub fn aggregate_labels(
+        &self,
+        id: u32,
+        neighbor_ids: Vec<u32>,
+        neighbor_labels: Vec<u32>,
+        distance_weights: bool,
+    ) -> PyResult<HashMap<u32, f64>> {
+        if neighbor_ids.len() != neighbor_labels.len() {
+            return Err(PyErr::new::<InvalidArgumentError, _>(format!(
+                "neighbor_ids and neighbor_labels length mismatch: {} vs {}",
+                neighbor_ids.len(),
+                neighbor_labels.len()
+            )));
+        }
+
+        let index = self.read_index()?;
+
+        let dim = self.config.dimensions as usize;
+        let target = if distance_weights {
+            Some(get_vector(&index, id as u64, dim)
+                .ok_or_else(|| PyErr::new::<NotFoundError, _>(format!("ID {} not found in index", id)))?)
+        } else {
+            None
+        };
+
+        let mut weights: HashMap<u32, f64> = HashMap::new();
+        for (&neighbor, &label) in neighbor_ids.iter().zip(neighbor_labels.iter()) {
+            let w = match &target {
+                Some(t) => {
+                    let v = get_vector(&index, neighbor as u64, dim)
+                        .ok_or_else(|| PyErr::new::<NotFoundError, _>(format!("Neighbor ID {} not found in index", neighbor)))?;
+                    1.0 / (l2sq(t, &v) + 1e-9)
+                }
+                None => 1.0,
+            };
+            *weights.entry(label).or_insert(0.0) += w;
+        }
+
+        let total: f64 = weights.values().sum();
+        if total > 0.0 {
+            for w in weights.values_mut() {
+                *w /= total;
+            }
+        }
+
+        Ok(weights)
+    }
+
+    /// 是否包含该 ID
+    /// 启用 Bloom 过滤器时先查过滤器: 判否即确定不存在，直接返回；
+    /// 判"可能存在" (含假阳性及已删除 ID 的残留位) 时落到索引的真实检查
+    #[pyo3(signature = (id, id_namespace=None))]
+    pub fn contains(&self, id: u32, id_namespace: Option<u8>) -> PyResult<bool> {
+        Ok(self.contains_batch(vec![id], id_namespace)?[0])
+    }
+
+    /// 批量 contains，语义同 contains
+    #[pyo3(signature = (ids, id_namespace=None))]
+    pub fn contains_batch(&self, ids: Vec<u32>, id_namespace: Option<u8>) -> PyResult<Vec<bool>> {
+        self.check_open()?;
+        let ids = namespaced_keys(id_namespace, &ids)?;
+        let mut result = vec![true; ids.len()];
+        {
+            let meta = self.meta.read()
+                .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+            if let Some(bloom) = &meta.bloom {
+                for (hit, &key) in result.iter_mut().zip(ids.iter()) {
+                    *hit = bloom.may_contain(key);
+                }
+                let maybe = result.iter().filter(|&&hit| hit).count() as u64;
+                bloom.fallthroughs.fetch_add(maybe, Ordering::Relaxed);
+                bloom.definite_negatives.fetch_add(ids.len() as u64 - maybe, Ordering::Relaxed);
+            }
+        }
+
+        if result.iter().any(|&hit| hit) {
+            let index = self.read_index()?;
+            for (hit, &key) in result.iter_mut().zip(ids.iter()) {
+                if *hit {
+                    *hit = index.contains(key);
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// Bloom 过滤器计数: (直接判否数, 落到真实检查数)；未启用时为 None
+    pub fn bloom_stats(&self) -> PyResult<Option<(u64, u64)>> {
+        self.check_open()?;
+        let meta = self.meta.read()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+        Ok(meta.bloom.as_ref().map(|b| {
+            (b.definite_negatives.load(Ordering::Relaxed), b.fallthroughs.load(Ordering::Relaxed))
+        }))
+    }
+
+    /// 遍历所有 (id, 向量)，也可直接 `for id, vec in index`
+    pub fn iter_vectors(&self) -> PyResult<VexusIterator> {
+        self.check_open()?;
+        let meta = self.meta.read()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+        let mut ids: Vec<u64> = meta.keys.iter().copied().collect();
+        ids.sort_unstable();
+
+        Ok(VexusIterator {
+            index: Arc::clone(&self.index),
+            ids: ids.into_iter(),
+            dim: self.config.dimensions as usize,
+        })
+    }
+
+    fn __iter__(&self) -> PyResult<VexusIterator> {
+        self.iter_vectors()
+    }
+
+    /// 对每个 (id, 向量) 调用 func(id, bytes)，返回未抛异常的调用次数
+    /// func 抛出的 Exception 只计为失败；KeyboardInterrupt / SystemExit 等非 Exception 的异常直接向上抛出并中止遍历
+    /// 调用期间不持有任何锁，func 内部可以再操作本索引
+    pub fn map_vectors(&self, py: Python<'_>, func: PyObject) -> PyResult<u32> {
+        self.check_open()?;
+        let mut succeeded = 0u32;
+        for (id, v) in self.iter_vectors()? {
+            let bytes = PyBytes::new_bound(py, &f32_to_bytes(&v));
+            match func.call1(py, (id, bytes)) {
+                Ok(_) => succeeded += 1,
+                Err(e) if e.is_instance_of::<pyo3::exceptions::PyException>(py) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(succeeded)
+    }
+
+    /// 批量取向量，直接返回 numpy 数组: (找到的 ID, 形状为 (m, dim) 的 f32 矩阵)
+    /// 不存在的 ID 跳过，两个数组按行对齐；id_namespace 对所有 ID 生效
+    #[pyo3(signature = (ids, id_namespace=None))]
+    pub fn get_vectors_array(&self, py: Python<'_>, ids: Vec<u32>, id_namespace: Option<u8>) -> PyResult<VectorArrays> {
+        let dim = self.config.dimensions as usize;
+        let keys = namespaced_keys(id_namespace, &ids)?;
+        let index = self.read_index()?;
+
+        let mut found = Vec::with_capacity(ids.len());
+        let mut flat = Vec::with_capacity(ids.len() * dim);
+        for (id, key) in ids.into_iter().zip(keys) {
+            if let Some(v) = get_vector(&index, key, dim) {
+                found.push(id);
+                flat.extend_from_slice(&v);
+            }
+        }
+        drop(index);
+
+        let rows = found.len();
+        let matrix = Array2::from_shape_vec((rows, dim), flat)
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Failed to shape vectors: {}", e)))?;
+
+        Ok((found.into_pyarray_bound(py).unbind(), matrix.into_pyarray_bound(py).unbind()))
+    }
+
+    /// 设置 ID 的附加数据 (元信息文本、时间戳)，随 save/load 持久化
+    #[pyo3(signature = (id, meta=None, timestamp=None, id_namespace=None))]
+    pub fn set_side_data(&self, id: u32, meta: Option<String>, timestamp: Option<u64>, id_namespace: Option<u8>) -> PyResult<()> {
+        self.check_writable()?;
+        let key = namespaced_key(id_namespace, id as i64)?;
+        let mut index_meta = self.meta.write()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+        if !index_meta.keys.contains(&key) {
+            return Err(PyErr::new::<NotFoundError, _>(format!("ID {} not found in index", id)));
+        }
+        index_meta.side.insert(key, SideRecord { meta, timestamp, tombstone: false });
+        Ok(())
+    }
+
+    /// 读取 ID 的附加数据: (meta, timestamp, tombstone)，从未设置过时为 None
+    /// 删除向量后附加数据仍保留，tombstone 为 True
+    #[pyo3(signature = (id, id_namespace=None))]
+    pub fn get_side_data(&self, id: u32, id_namespace: Option<u8>) -> PyResult<Option<SideDataTuple>> {
+        self.check_open()?;
+        let key = namespaced_key(id_namespace, id as i64)?;
+        let meta = self.meta.read()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+        Ok(meta.side.get(&key).map(|r| (r.meta.clone(), r.timestamp, r.tombstone)))
+    }
+
+    /// 设置 ID 的标签 (替换原有标签，空列表即清除)，随 save/load 持久化
+    /// 删除向量时标签一并移除；search(require_labels=...) 据此过滤
+    #[pyo3(signature = (id, labels, id_namespace=None))]
+    pub fn set_labels(&self, id: u32, labels: Vec<String>, id_namespace: Option<u8>) -> PyResult<()> {
+        self.check_writable()?;
+        let key = namespaced_key(id_namespace, id as i64)?;
+        let mut meta = self.meta.write()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+        if !meta.keys.contains(&key) {
+            return Err(PyErr::new::<NotFoundError, _>(format!("ID {} not found in index", id)));
+        }
+        meta.set_labels(key, labels.into_iter().collect());
+        Ok(())
+    }
+
+    /// 读取 ID 的标签 (按字典序)，没有标签时为空列表
+    #[pyo3(signature = (id, id_namespace=None))]
+    pub fn get_labels(&self, id: u32, id_namespace: Option<u8>) -> PyResult<Vec<String>> {
+        self.check_open()?;
+        let key = namespaced_key(id_namespace, id as i64)?;
+        let meta = self.meta.read()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+        Ok(meta.id_labels.get(&key).map(|labels| labels.iter().cloned().collect()).unwrap_or_default())
+    }
+
+    /// 开启向量内容哈希登记: 之后每次写入都记录向量字节的 xxh3 哈希，随 save/load 持久化
+    /// 已有向量按索引中当前存储的内容回填；重复调用无副作用
+    pub fn enable_vector_hashes(&self) -> PyResult<()> {
+        self.check_writable()?;
+        let index = self.read_index()?;
+        let mut meta = self.meta.write()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+        if meta.hashes.is_some() {
+            return Ok(());
+        }
+        let dim = self.config.dimensions as usize;
+        let hashes = meta
+            .keys
+            .iter()
+            .filter_map(|&id| get_vector(&index, id, dim).map(|v| (id, vector_hash(&v))))
+            .collect();
+        meta.hashes = Some(hashes);
+        Ok(())
+    }
+
+    /// 读取 ID 登记的向量哈希，未开启哈希登记或 ID 不存在时为 None
+    #[pyo3(signature = (id, id_namespace=None))]
+    pub fn vector_hash(&self, id: u32, id_namespace: Option<u8>) -> PyResult<Option<u64>> {
+        self.check_open()?;
+        let key = namespaced_key(id_namespace, id as i64)?;
+        let meta = self.meta.read()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+        Ok(meta.hashes.as_ref().and_then(|hashes| hashes.get(&key).copied()))
+    }
+
+    /// 开启写入归一化: 之后 add / add_batch / recover_from_sqlite(_multi) 把向量缩放为单位 L2 长度再存入
+    /// (零向量原样存入)，适合按余弦相似度检索。record_norms=true 时同时记录每个向量归一化前的模长，
+    /// 随 save/load 持久化，用 get_norm 取回。已存的向量不会被改写，也没有模长记录；重复调用只会追加开启 record_norms。
+    /// 内容哈希仍按归一化前的原始向量计算，verify_against_sqlite 不受影响
+    #[pyo3(signature = (record_norms=false))]
+    pub fn enable_normalization(&self, record_norms: bool) -> PyResult<()> {
+        // 与 recenter / apply_transform 的检查串行: 它们在索引写锁下读取开关并持锁改写向量
+        let _index = self.write_index()?;
+        let mut meta = self.meta.write()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+        let mode = meta.normalize.get_or_insert_with(NormalizeMode::default);
+        if record_norms && mode.norms.is_none() {
+            mode.norms = Some(HashMap::new());
+        }
+        Ok(())
+    }
+
+    /// 向量归一化前的 L2 模长；未开启 record_norms、ID 不存在或在开启之前写入时为 None
+    #[pyo3(signature = (id, id_namespace=None))]
+    pub fn get_norm(&self, id: u32, id_namespace: Option<u8>) -> PyResult<Option<f64>> {
+        self.check_open()?;
+        let key = namespaced_key(id_namespace, id as i64)?;
+        let meta = self.meta.read()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+        Ok(meta.normalize.as_ref().and_then(|mode| mode.norms.as_ref()).and_then(|norms| norms.get(&key).copied()))
+    }
+
+    /// 检查索引是否与 SQLite 悄悄分叉: 对两边共有的 ID 比较登记的哈希与 SQLite 中 vector BLOB 的哈希
+    /// table_type 为 "tags" 或 "chunks" (不按日记过滤)；id_namespace 与恢复时一致。
+    /// sample 为 None 时检查全部共有 ID，否则按 seed 随机抽取 sample 个。只负责发现，修复由调用方处理
+    /// byte_order 与恢复时一致 (哈希按解码后的本机字节计算)
+    #[pyo3(signature = (db_path, table_type, sample=None, id_namespace=None, seed=0, byte_order="le"))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_against_sqlite(
+        &self,
+        db_path: String,
+        table_type: String,
+        sample: Option<u32>,
+        id_namespace: Option<u8>,
+        seed: u64,
+        byte_order: &str,
+    ) -> PyResult<VerifyReport> {
+        self.check_open()?;
+        let byte_order = ByteOrder::parse(byte_order)?;
+        let table = match table_type.as_str() {
+            "tags" => "tags",
+            "chunks" => "chunks",
+            other => {
+                return Err(PyErr::new::<InvalidArgumentError, _>(format!(
+                    "Invalid table_type '{}': expected 'tags' or 'chunks'",
+                    other
+                )))
+            }
+        };
+        let conn = Connection::open(&db_path)
+            .map_err(|e| PyErr::new::<SqliteError, _>(format!("Failed to open DB: {}", e)))?;
+        let sqlite_err = |e: rusqlite::Error| PyErr::new::<SqliteError, _>(format!("Verify query failed: {}", e));
+
+        let mut stmt = conn
+            .prepare(&format!("SELECT id FROM {} WHERE vector IS NOT NULL ORDER BY id", table))
+            .map_err(sqlite_err)?;
+        let db_ids = stmt
+            .query_map([], |row| row.get::<_, i64>(0))
+            .map_err(sqlite_err)?
+            .collect::<Result<Vec<i64>, _>>()
+            .map_err(sqlite_err)?;
+
+        // (表内 ID, 索引 key, 登记的哈希)
+        let mut shared: Vec<(i64, u64, Option<u64>)> = {
+            let meta = self.meta.read()
+                .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+            let mut shared = Vec::new();
+            for id in db_ids {
+                let key = namespaced_key(id_namespace, id)?;
+                if meta.keys.contains(&key) {
+                    shared.push((id, key, meta.hashes.as_ref().and_then(|hashes| hashes.get(&key).copied())));
+                }
+            }
+            shared
+        };
+
+        if let Some(n) = sample {
+            let mut rng = SplitMix64::new(seed);
+            let n = (n as usize).min(shared.len());
+            for i in 0..n {
+                let j = i + rng.below(shared.len() - i);
+                shared.swap(i, j);
+            }
+            shared.truncate(n);
+            shared.sort_unstable_by_key(|&(id, _, _)| id);
+        }
+
+        let mut report = VerifyReport { checked: 0, mismatched: Vec::new(), unhashed: 0 };
+        let mut blob_stmt = conn
+            .prepare(&format!("SELECT vector FROM {} WHERE id = ?1", table))
+            .map_err(sqlite_err)?;
+        for (id, _, recorded) in shared {
+            let Some(recorded) = recorded else {
+                report.unhashed += 1;
+                continue;
+            };
+            let blob: Vec<u8> = blob_stmt.query_row([id], |row| row.get(0)).map_err(sqlite_err)?;
+            report.checked += 1;
+            let matches = match byte_order.decode(&blob, "vector", None) {
+                Ok(values) => vector_hash(&values) == recorded,
+                Err(_) => false,
+            };
+            if !matches {
+                report.mismatched.push(id as u32);
+            }
+        }
+        Ok(report)
+    }
+
+    /// 审计登记的 ID: 能取回向量的为存活，取不到的为死 ID (已在 usearch 层删除但仍被登记)
+    /// usearch 无法枚举 key，这里遍历的是侧车维护的 ID 集合；返回值为索引 key (带命名空间的见 unpack_id)，均升序
+    pub fn audit_live_ids(&self) -> PyResult<(Vec<u64>, Vec<u64>)> {
+        let index = self.read_index()?;
+        let meta = self.meta.read()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+        Ok(partition_live(&index, &meta.keys, self.config.dimensions as usize))
+    }
+
+    /// 清除死 ID: 从 ID 集合中移除，并在 usearch 层再删除一次，返回清除的数量
+    pub fn cleanup_dead(&self) -> PyResult<u32> {
+        let mut index = self.write_index()?;
+        let dead = {
+            let mut meta = self.meta.write()
+                .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+            let (_, dead) = partition_live(&index, &meta.keys, self.config.dimensions as usize);
+            for &key in &dead {
+                meta.remove_key(key);
+            }
+            dead
+        };
+        // 删除重试期间不能持有 meta 锁
+        for &key in &dead {
+            index = self.remove_with_retry(index, key)?.0;
+        }
+        Ok(dead.len() as u32)
+    }
+
+    /// 立即释放索引内存 (不等 Python GC)
+    /// 之后除 close 外的所有索引操作都报 IndexClosedError; 重复调用无副作用
+    pub fn close(&self) -> PyResult<()> {
+        let mut index = self.index.write()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+        if self.closed.swap(true, Ordering::AcqRel) {
+            return Ok(());
+        }
+        release_shared_view(self.shared_path.as_deref());
+        // 用一个未 reserve 的空索引替换，旧索引在此处 drop
+        let empty = self.config.create_index()?;
+        drop(std::mem::replace(&mut *index, empty));
+
+        let mut meta = self.meta.write()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+        *meta = IndexMeta::default();
+        drop(meta);
+
+        // 已发出的游标随之失效，缓存的候选列表一并释放
+        self.page_cache.lock()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?
+            .sessions
+            .clear();
+        Ok(())
+    }
+
+    /// 删除 (按 ID)
+    #[pyo3(signature = (id, id_namespace=None))]
+    pub fn remove(&self, id: u32, id_namespace: Option<u8>) -> PyResult<()> {
+        let key = namespaced_key(id_namespace, id as i64)?;
+        let index = self.write_index()?;
+
+        let (_index, removed) = self.remove_with_retry(index, key)?;
+
+        let mut meta = self.meta.write()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+        meta.remove_key(key);
+        if removed > 0 {
+            meta.lifetime.total_removes += 1;
+            meta.tombstones += removed as u64;
+        }
+
+        Ok(())
+    }
+
+    /// 批量删除, 返回实际删除的数量 (不存在的 ID 忽略)
+    /// 某个 ID 重试后仍失败时抛 RemoveError, 之前已删除的保持删除
+    #[pyo3(signature = (ids, id_namespace=None))]
+    pub fn remove_batch(&self, ids: Vec<u32>, id_namespace: Option<u8>) -> PyResult<u32> {
+        self.check_open()?;
+        self.remove_keys(&namespaced_keys(id_namespace, &ids)?)
+    }
+
+    /// 墓碑比例: 上次 compact 以来删除留下的墓碑节点数 / 存活向量数 (无存活向量时分母按 1 计)
+    /// 墓碑越多 HNSW 图中的死节点越多，召回率随之下降
+    #[getter]
+    pub fn tombstone_ratio(&self) -> PyResult<f64> {
+        self.check_open()?;
+        let meta = self.meta.read()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+        Ok(meta.tombstones as f64 / meta.keys.len().max(1) as f64)
+    }
+
+    /// 墓碑比例超过 tombstone_ratio 时返回 True，供后台任务决定何时调用 compact
+    pub fn should_compact(&self, tombstone_ratio: f64) -> PyResult<bool> {
+        self.check_open()?;
+        if !(tombstone_ratio.is_finite() && tombstone_ratio >= 0.0) {
+            return Err(PyErr::new::<InvalidArgumentError, _>(format!(
+                "tombstone_ratio must be a non-negative finite number, got {}",
+                tombstone_ratio
+            )));
+        }
+        Ok(self.tombstone_ratio()? > tombstone_ratio)
+    }
+
+    /// 压缩: 用存活向量重建 usearch 索引，清除删除留下的墓碑节点，返回保留的向量数
+    /// 重建期间持有写锁；容量保持不变，墓碑计数清零并记录 last_compaction。
+    /// 没有 .meta 的旧索引无法枚举 ID，此时报 ValueError
+    pub fn compact(&self) -> PyResult<u32> {
+        let mut index = self.write_index()?;
+        let mut meta = self.meta.write()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+
+        if meta.keys.is_empty() && index.size() > 0 {
+            return Err(PyErr::new::<InvalidArgumentError, _>(
+                "Cannot compact a legacy index without an ID list (.meta)".to_string(),
+            ));
+        }
+
+        let dim = self.config.dimensions as usize;
+        let rebuilt = self.config.create_index()?;
+        rebuilt
+            .reserve(index.capacity().max(meta.keys.len()))
+            .map_err(|e| PyErr::new::<CapacityError, _>(format!("Failed to reserve capacity: {:?}", e)))?;
+
+        let mut ids: Vec<u64> = meta.keys.iter().copied().collect();
+        ids.sort_unstable();
+        let mut kept = 0u32;
+        for id in ids {
+            // 取不回向量的死 ID 一并丢弃 (同 cleanup_dead)
+            match get_vector(&index, id, dim) {
+                Some(v) => {
+                    rebuilt.add(id, &v)
+                        .map_err(|e| PyErr::new::<VexusError, _>(format!("Add failed for id {}: {:?}", id, e)))?;
+                    kept += 1;
+                }
+                None => meta.remove_key(id),
+            }
+        }
+
+        *index = rebuilt;
+        meta.tombstones = 0;
+        meta.lifetime.last_compaction = Some(now_secs());
+        Ok(kept)
+    }
+
+    /// 对每个已索引 key 调用 predicate(key)，删除返回 False 的 key，返回删除数量
+    /// 未用 id_namespace 写入的向量 key 即 ID，带命名空间的可用 unpack_id 拆开
+    /// 先对全部 ID 求值 (不持有锁)，predicate 抛异常时不删除任何 ID
+    /// 本索引没有软删除层，删除立即生效 (附加数据会被标记 tombstone)
+    pub fn filter_by_predicate(&self, py: Python<'_>, predicate: PyObject) -> PyResult<u32> {
+        self.check_open()?;
+        let ids: Vec<u64> = {
+            let meta = self.meta.read()
+                .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+            let mut ids: Vec<u64> = meta.keys.iter().copied().collect();
+            ids.sort_unstable();
+            ids
+        };
+
+        let mut rejected = Vec::new();
+        for key in ids {
+            let keep: bool = predicate.call1(py, (key,))?.is_truthy(py)?;
+            if !keep {
+                rejected.push(key);
+            }
+        }
+
+        self.remove_keys(&rejected)
+    }
+
+    /// 获取当前索引状态
+    pub fn stats(&self) -> PyResult<VexusStats> {
+        let index = self.read_index()?;
+
+        Ok(VexusStats {
+            total_vectors: index.size() as u32,
+            dimensions: self.config.dimensions,
+            capacity: index.capacity() as u32,
+            memory_usage: index.memory_usage() as u32,
+        })
+    }
+
+    /// 按命名空间细分的向量数、内存 (按向量数比例分摊) 与带时间戳的向量数
+    /// 只遍历 IndexMeta 中登记的 key 与附加数据，不读取向量
+    pub fn stats_detailed(&self) -> PyResult<DetailedStats> {
+        let index = self.read_index()?;
+        let meta = self.meta.read()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+
+        let memory_usage = index.memory_usage() as u64;
+        let per_vector = memory_usage as f64 / meta.keys.len().max(1) as f64;
+
+        let mut namespaces: HashMap<u8, NamespaceStats> = HashMap::new();
+        let mut timestamped = 0u32;
+        for &key in &meta.keys {
+            let entry = namespaces.entry(unpack_id(key).0).or_insert(NamespaceStats {
+                vectors: 0,
+                memory_bytes: 0,
+                timestamped: 0,
+            });
+            entry.vectors += 1;
+            if meta.side.get(&key).is_some_and(|r| r.timestamp.is_some()) {
+                entry.timestamped += 1;
+                timestamped += 1;
+            }
+        }
+        for st in namespaces.values_mut() {
+            st.memory_bytes = (st.vectors as f64 * per_vector).round() as u64;
+        }
+
+        Ok(DetailedStats {
+            total_vectors: meta.keys.len() as u32,
+            memory_usage,
+            timestamped,
+            namespaces,
+        })
+    }
+
+    /// 当前索引配置 (load 后为保存时的配置)
+    pub fn info(&self) -> PyResult<IndexInfo> {
+        self.check_open()?;
+        let c = &self.config;
+        Ok(IndexInfo {
+            dimensions: c.dimensions,
+            metric: c.metric.clone(),
+            quantization: c.quantization.clone(),
+            connectivity: c.connectivity,
+            expansion_add: c.expansion_add,
+            expansion_search: c.expansion_search,
+            value_bounds: c.value_bounds,
+        })
+    }
+
+    /// 导出 JSON 格式的统计信息 (供监控抓取)
+    /// 包含 stats() 的全部字段、跟踪的 ID 数、是否已校准、生命周期计数器以及索引配置
+    pub fn stats_json(&self) -> PyResult<String> {
+        let index = self.read_index()?;
+        let meta = self.meta.read()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+
+        let value = serde_json::json!({
+            "total_vectors": index.size(),
+            "dimensions": self.config.dimensions,
+            "capacity": index.capacity(),
+            "memory_usage": index.memory_usage(),
+            "tracked_ids": meta.keys.len(),
+            "calibrated": meta.calibration.is_some(),
+            "lifetime": meta.lifetime,
+            "config": self.config,
+        });
+
+        serde_json::to_string(&value)
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Failed to encode stats: {}", e)))
+    }
+
+    /// 抽样实测图的连通性: 第 0 层平均度数、最高层与自召回率
+    #[pyo3(signature = (sample=256, seed=0))]
+    pub fn graph_stats(&self, sample: u32, seed: u64) -> PyResult<GraphStats> {
+        let index = self.read_index()?;
+        let meta = self.meta.read()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+
+        let dim = self.config.dimensions as usize;
+        let ids = sample_keys(&meta.keys, sample as usize, seed);
+        let degree_sum: usize = ids.iter().map(|&id| index.neighbors(id, 0).len()).sum();
+        let avg_degree = if ids.is_empty() { 0.0 } else { degree_sum as f64 / ids.len() as f64 };
+        let max_level = ids.iter().map(|&id| index.level_of_key(id)).max();
+
+        let hits = ids
+            .iter()
+            .filter(|&&id| {
+                get_vector(&index, id, dim)
+                    .and_then(|v| index.search(&v, 1).ok())
+                    .is_some_and(|found| found.keys.first() == Some(&id))
+            })
+            .count();
+        let self_recall = if ids.is_empty() { 0.0 } else { hits as f64 / ids.len() as f64 };
+
+        Ok(GraphStats {
+            n_nodes: index.size() as u64,
+            connectivity: index.connectivity() as u32,
+            avg_degree,
+            n_levels: max_level.map_or(0, |level| level as u32 + 1),
+            max_level: max_level.unwrap_or(0) as u32,
+            self_recall,
+            sample_size: ids.len() as u32,
+        })
+    }
+
+    /// 从 SQLite 数据库恢复索引 (同步版本)
+    /// include_ids: 只恢复这些行; exclude_ids: 跳过这些行 (如隔离的坏向量)
+    /// 返回 RecoverReport: inserted 为写入数，excluded 为被过滤的行数，skipped_row_errors 为读取失败的行数
+    /// id_namespace: 行 ID 打包进该命名空间 (见 pack_id)，tags/chunks 恢复到同一索引时用于区分
+    /// 设置了 set_memory_limit 时，预估内存超限会抛 MemoryError，force=True 跳过检查
+    /// byte_order: vector BLOB 的字节序，"le" (默认) 或 "be"
+    /// 扩容事件以 debug、维度不符跳过的行数以 warning 级别写入 vector_db 日志器
+    #[pyo3(signature = (db_path, table_type, filter_diary_name=None, include_ids=None, exclude_ids=None, id_namespace=None, force=false, byte_order="le"))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn recover_from_sqlite(
+        &self,
+        py: Python<'_>,
+        db_path: String,
+        table_type: String,
+        filter_diary_name: Option<String>,
+        include_ids: Option<Vec<i64>>,
+        exclude_ids: Option<Vec<i64>>,
+        id_namespace: Option<u8>,
+        force: bool,
+        byte_order: &str,
+    ) -> PyResult<RecoverReport> {
+        let byte_order = ByteOrder::parse(byte_order)?;
+        let index = self.write_index()?;
+        let mut meta = self.meta.write()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+
+        let id_filter = RecoverIdFilter {
+            include: include_ids,
+            exclude: exclude_ids,
+        };
+        let counts = self.recover_file(&index, &mut meta, &db_path, &table_type, filter_diary_name.as_deref(), &id_filter, id_namespace, force, byte_order)?;
+
+        drop(meta);
+        drop(index);
+
+        let mut report = RecoverReport::default();
+        report.absorb(counts);
+        report.log(py, "recover_from_sqlite", self.config.dimensions);
+        Ok(report)
+    }
+
+    /// 从多个 SQLite 分片文件恢复索引，计数汇总到同一个报告中 (日志同 recover_from_sqlite)
+    /// strict=false 时单个文件打不开/查询失败只记录在 failed_files 中，继续处理其余文件
+    #[pyo3(signature = (db_paths, table_type, filter=None, strict=false, include_ids=None, exclude_ids=None, id_namespace=None, force=false, byte_order="le"))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn recover_from_sqlite_multi(
+        &self,
+        py: Python<'_>,
+        db_paths: Vec<String>,
+        table_type: String,
+        filter: Option<String>,
+        strict: bool,
+        include_ids: Option<Vec<i64>>,
+        exclude_ids: Option<Vec<i64>>,
+        id_namespace: Option<u8>,
+        force: bool,
+        byte_order: &str,
+    ) -> PyResult<RecoverReport> {
+        let byte_order = ByteOrder::parse(byte_order)?;
+        let index = self.write_index()?;
+        let mut meta = self.meta.write()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+
+        let id_filter = RecoverIdFilter {
+            include: include_ids,
+            exclude: exclude_ids,
+        };
+        let mut report = RecoverReport::default();
+
+        for db_path in &db_paths {
+            if !std::path::Path::new(db_path).exists() {
+                let msg = format!("DB file not found: {}", db_path);
+                if strict {
+                    return Err(PyErr::new::<MissingFileError, _>(msg));
+                }
+                report.failed_files.push((db_path.clone(), msg));
+                continue;
+            }
+
+            match self.recover_file(&index, &mut meta, db_path, &table_type, filter.as_deref(), &id_filter, id_namespace, force, byte_order) {
+                Ok(counts) => report.absorb(counts),
+                Err(e) if !strict => report.failed_files.push((db_path.clone(), e.to_string())),
+                Err(e) => return Err(e),
+            }
+        }
+
+        drop(meta);
+        drop(index);
+        report.log(py, "recover_from_sqlite_multi", self.config.dimensions);
+        Ok(report)
+    }
+
+    /// 按标签求原型向量: 每个标签下所有 ID 对应向量的均值
+    pub fn compute_prototypes(&self, ids: Vec<u32>, labels: Vec<u32>) -> PyResult<HashMap<u32, Vec<f64>>> {
+        if ids.len() != labels.len() {
+            return Err(PyErr::new::<InvalidArgumentError, _>(format!(
+                "ids and labels length mismatch: {} vs {}",
+                ids.len(),
+                labels.len()
+            )));
+        }
+
+        let index = self.read_index()?;
+
+        let dim = self.config.dimensions as usize;
+        let mut sums: HashMap<u32, (Vec<f64>, usize)> = HashMap::new();
+
+        for (&id, &label) in ids.iter().zip(labels.iter()) {
+            let v = get_vector(&index, id as u64, dim)
+                .ok_or_else(|| PyErr::new::<NotFoundError, _>(format!("ID {} not found in index", id)))?;
+            let (sum, count) = sums.entry(label).or_insert_with(|| (vec![0.0; dim], 0));
+            for (s, &x) in sum.iter_mut().zip(v.iter()) {
+                *s += x as f64;
+            }
+            *count += 1;
+        }
+
+        Ok(sums
+            .into_iter()
+            .map(|(label, (sum, count))| (label, sum.into_iter().map(|x| x / count as f64).collect()))
+            .collect())
+    }
+
+    /// 对指定向量做线性变换 y = M·x, M 为 n_rows × dim 的 f32 矩阵 (行优先)
+    /// in_place=true 时要求 n_rows == dim, 用变换结果替换索引中的向量 (remove + add)；
+    /// 开启写入归一化时 in_place 报 ValueError (变换结果不再是单位长度，也没有对应的原始模长)
+    pub fn apply_transform(&self, ids: Vec<u32>, matrix: Vec<u8>, n_rows: u32, in_place: bool) -> PyResult<Vec<Vec<f64>>> {
+        let dim = self.config.dimensions as usize;
+        let rows = n_rows as usize;
+
+        let m = &*f32_view(&matrix, "matrix", Some(rows * dim))?;
+        if in_place && rows != dim {
+            return Err(PyErr::new::<DimensionMismatchError, _>(format!(
+                "In-place transform requires a square matrix: n_rows {} != dim {}",
+                rows, dim
+            )));
+        }
+        // 先全部算完再写回, 任何 ID 缺失都不会留下半变换的索引
+        let transform = |index: &Index| {
+            ids.iter()
+                .map(|&id| {
+                    let v = get_vector(index, id as u64, dim)
+                        .ok_or_else(|| PyErr::new::<NotFoundError, _>(format!("ID {} not found in index", id)))?;
+                    Ok(m.chunks_exact(dim)
+                        .map(|row| row.iter().zip(v.iter()).map(|(&a, &x)| a as f64 * x as f64).sum())
+                        .collect::<Vec<f64>>())
+                })
+                .collect::<PyResult<Vec<_>>>()
+        };
+
+        // 只读时不阻塞其他读者，只读视图 (open_shared) 上同样可用
+        if !in_place {
+            let index = self.read_index()?;
+            return transform(&index);
+        }
+
+        // 先取索引锁再读开关 (锁顺序: index → meta)，enable_normalization 无法插在检查与写回之间
+        let mut index = self.write_index()?;
+        let normalizing = self.meta.read()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?
+            .normalize
+            .is_some();
+        if normalizing {
+            return Err(PyErr::new::<InvalidArgumentError, _>(
+                "Cannot transform in place while normalization is enabled".to_string(),
+            ));
+        }
+        let transformed = transform(&index)?;
+
+        let new_vectors: Vec<Vec<f32>> = transformed
+            .iter()
+            .map(|y| y.iter().map(|&x| x as f32).collect())
+            .collect();
+        for (&id, v) in ids.iter().zip(new_vectors.iter()) {
+            self.check_bounds(id as u64, v)?;
+        }
+        // 删除重试期间不能持有 meta 锁，先改完索引再统一登记；中途失败时已改写的照常登记后再抛出
+        let mut tombstones = 0u64;
+        let mut rewritten = 0;
+        let mut failure = None;
+        for (&id, v) in ids.iter().zip(new_vectors.iter()) {
+            match self.remove_with_retry(index, id as u64) {
+                Ok((guard, removed)) => {
+                    index = guard;
+                    tombstones += removed as u64;
+                }
+                Err(e) => {
+                    failure = Some(e);
+                    break;
+                }
+            }
+            if let Err(e) = index.add(id as u64, v) {
+                failure = Some(PyErr::new::<VexusError, _>(format!("Add failed for id {}: {:?}", id, e)));
+                break;
+            }
+            rewritten += 1;
+        }
+
+        let mut meta = self.meta.write()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+        meta.tombstones += tombstones;
+        for (&id, v) in ids.iter().zip(new_vectors.iter()).take(rewritten) {
+            meta.record_hash(id as u64, v);
+        }
+        if let Some(e) = failure {
+            return Err(e);
+        }
+        meta.lifetime.last_compaction = Some(now_secs());
+
+        Ok(transformed)
+    }
+
+    /// 零样本分类: query 与每个类别原型向量的余弦相似度经 softmax 得到概率
+    /// 返回按概率降序排列的 (标签, 概率)
+    pub fn classify_zero_shot(
+        &self,
+        query: Vec<u8>,
+        class_prototypes: Vec<u8>,
+        n_classes: u32,
+        class_labels: Vec<String>,
+    ) -> PyResult<Vec<(String, f64)>> {
+        self.check_open()?;
+        let dim = self.config.dimensions as usize;
+        let n = n_classes as usize;
+
+        let q = &*f32_view(&query, "query", Some(dim))?;
+        let prototypes = &*f32_view(&class_prototypes, "class_prototypes", Some(n * dim))?;
+        if class_labels.len() != n {
+            return Err(PyErr::new::<InvalidArgumentError, _>(format!(
+                "Expected {} class labels, got {}",
+                n,
+                class_labels.len()
+            )));
+        }
+
+        let similarities: Vec<f64> = prototypes.chunks(dim.max(1)).take(n).map(|p| cosine(q, p)).collect();
+        let probabilities = softmax(&similarities);
+
+        let mut result: Vec<(String, f64)> = class_labels.into_iter().zip(probabilities).collect();
+        result.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        Ok(result)
+    }
+
+    /// 缩放点积注意力: softmax(q · k_i / sqrt(dim) / temperature)，k_i 为 key_ids 对应的向量
+    pub fn compute_attention(&self, query: Vec<u8>, key_ids: Vec<u32>, temperature: f64) -> PyResult<Vec<f64>> {
+        self.check_open()?;
+        Ok(self.attend(&query, &key_ids, temperature)?.0)
+    }
+
+    /// 批量交叉注意力: 对每个 query_ids 向量按 compute_attention 求出对 key_ids 的权重 (逐行 softmax)，
+    /// 再对 key 向量加权求和；返回按行展平的 (len(query_ids) × dim) 矩阵
+    pub fn compute_cross_attention(&self, query_ids: Vec<u32>, key_ids: Vec<u32>, temperature: f64) -> PyResult<Vec<f64>> {
+        let dim = self.config.dimensions as usize;
+        check_temperature(temperature)?;
+
+        let (queries, keys) = {
+            let index = self.read_index()?;
+            (fetch_vectors(&index, &query_ids, dim)?, fetch_vectors(&index, &key_ids, dim)?)
+        };
+
+        let mut out = vec![0.0f64; queries.len() * dim];
+        for (q, row) in queries.iter().zip(out.chunks_exact_mut(dim.max(1))) {
+            for (w, k) in attention_weights(q, &keys, temperature).iter().zip(keys.iter()) {
+                for (o, &x) in row.iter_mut().zip(k.iter()) {
+                    *o += w * x as f64;
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// 情绪强度: 把 id 的向量投影到 (valence_negative → valence_positive) 轴上
+    /// 返回 (valence, intensity): valence 以两端点中点为 0、正端为 +1、负端为 -1；
+    /// intensity 为相对该轴的垂直分量模长
+    pub fn compute_emotion_intensity(&self, id: u32, valence_positive_id: u32, valence_negative_id: u32) -> PyResult<(f64, f64)> {
+        let dim = self.config.dimensions as usize;
+        let index = self.read_index()?;
+        let vectors = fetch_vectors(&index, &[id, valence_positive_id, valence_negative_id], dim)?;
+        axis_projection(&vectors[0], &vectors[1], &vectors[2])
+            .ok_or_else(|| degenerate_axis_err("Valence", valence_positive_id, valence_negative_id))
+    }
+
+    /// 情绪环状模型 (circumplex) 坐标: 分别投影到唤醒轴 (高, 低) 与效价轴 (正, 负)
+    /// 返回 (arousal, valence)，含义同 compute_emotion_intensity 的 valence (正端 +1、负端 -1)
+    pub fn compute_arousal_valence(&self, id: u32, arousal_axis_ids: (u32, u32), valence_axis_ids: (u32, u32)) -> PyResult<(f64, f64)> {
+        let dim = self.config.dimensions as usize;
+        let index = self.read_index()?;
+        let ids = [id, arousal_axis_ids.0, arousal_axis_ids.1, valence_axis_ids.0, valence_axis_ids.1];
+        let vectors = fetch_vectors(&index, &ids, dim)?;
+
+        let (arousal, _) = axis_projection(&vectors[0], &vectors[1], &vectors[2])
+            .ok_or_else(|| degenerate_axis_err("Arousal", arousal_axis_ids.0, arousal_axis_ids.1))?;
+        let (valence, _) = axis_projection(&vectors[0], &vectors[3], &vectors[4])
+            .ok_or_else(|| degenerate_axis_err("Valence", valence_axis_ids.0, valence_axis_ids.1))?;
+        Ok((arousal, valence))
+    }
+
+    /// 按顺序 (如日记时间) 计算 ID 序列的语义轨迹，至少需要 2 个 ID
+    pub fn compute_trajectory(&self, ordered_ids: Vec<u32>) -> PyResult<TrajectoryResult> {
+        if ordered_ids.len() < 2 {
+            return Err(PyErr::new::<InvalidArgumentError, _>(format!(
+                "compute_trajectory needs at least 2 IDs, got {}",
+                ordered_ids.len()
+            )));
+        }
+        let dim = self.config.dimensions as usize;
+        let points = {
+            let index = self.read_index()?;
+            fetch_vectors(&index, &ordered_ids, dim)?
+        };
+
+        let velocities: Vec<f64> = points.windows(2).map(|w| l2sq(&w[0], &w[1]).sqrt()).collect();
+        let accelerations: Vec<f64> = velocities.windows(2).map(|w| w[1] - w[0]).collect();
+        let total_path_length: f64 = velocities.iter().sum();
+        let net_displacement = l2sq(&points[0], &points[points.len() - 1]).sqrt();
+
+        let mean_velocity = total_path_length / velocities.len() as f64;
+        let smoothness = if accelerations.is_empty() || mean_velocity < 1e-12 {
+            1.0
+        } else {
+            let mean_accel = accelerations.iter().map(|a| a.abs()).sum::<f64>() / accelerations.len() as f64;
+            1.0 - mean_accel / mean_velocity
+        };
+
+        Ok(TrajectoryResult {
+            velocities,
+            accelerations,
+            total_path_length,
+            net_displacement,
+            smoothness,
+        })
+    }
+
+    /// 轨迹平滑: 第 i 个位置取 [i-window, i+window] (截断到序列范围) 内向量的平均
+    /// window=0 时原样返回各向量
+    pub fn smooth_trajectory(&self, ordered_ids: Vec<u32>, window: u32) -> PyResult<Vec<Vec<f64>>> {
+        let dim = self.config.dimensions as usize;
+        let points = {
+            let index = self.read_index()?;
+            fetch_vectors(&index, &ordered_ids, dim)?
+        };
+
+        let n = points.len();
+        let w = window as usize;
+        Ok((0..n)
+            .map(|i| {
+                let span = &points[i.saturating_sub(w)..(i + w + 1).min(n)];
+                let mut avg = vec![0.0f64; dim];
+                for p in span {
+                    for (a, &x) in avg.iter_mut().zip(p.iter()) {
+                        *a += x as f64;
+                    }
+                }
+                avg.iter_mut().for_each(|a| *a /= span.len() as f64);
+                avg
+            })
+            .collect())
+    }
+
+    /// 情绪轨迹突变点检测
+    /// 速度同 compute_trajectory (相邻向量的欧氏距离)，第 i 段速度 (ordered_ids[i] -> ordered_ids[i+1])
+    /// 超过 sensitivity * 平均速度时返回 i + 1，即突变后到达的条目在 ordered_ids 中的下标，升序；
+    /// 少于 2 个 ID 或轨迹完全静止时返回空列表
+    pub fn detect_change_points(&self, ordered_ids: Vec<u32>, sensitivity: f64) -> PyResult<Vec<u32>> {
+        if !(sensitivity.is_finite() && sensitivity >= 0.0) {
+            return Err(PyErr::new::<InvalidArgumentError, _>(format!(
+                "sensitivity must be a non-negative finite number, got {}",
+                sensitivity
+            )));
+        }
+        let dim = self.config.dimensions as usize;
+        let points = {
+            let index = self.read_index()?;
+            fetch_vectors(&index, &ordered_ids, dim)?
+        };
+        if points.len() < 2 {
+            return Ok(Vec::new());
+        }
+
+        let velocities: Vec<f64> = points.windows(2).map(|w| l2sq(&w[0], &w[1]).sqrt()).collect();
+        let mean_velocity = velocities.iter().sum::<f64>() / velocities.len() as f64;
+        if mean_velocity < 1e-12 {
+            return Ok(Vec::new());
+        }
+        let threshold = sensitivity * mean_velocity;
+        Ok(velocities
+            .iter()
+            .enumerate()
+            .filter(|&(_, &v)| v > threshold)
+            .map(|(i, _)| i as u32 + 1)
+            .collect())
+    }
+
+    /// 轨迹位移自相关 (检测周期性情绪循环)
+    /// 位移 d_i = v[i+1] - v[i]；第 τ 项 (τ = 1..=max_lag) 为所有 cos(d_i, d_{i+τ}) 的平均，
+    /// 返回长度为 max_lag 的列表，峰值对应周期；位移对数不足 (τ >= 位移数) 的项为 0.0
+    pub fn compute_trajectory_autocorrelation(&self, ordered_ids: Vec<u32>, max_lag: u32) -> PyResult<Vec<f64>> {
+        let dim = self.config.dimensions as usize;
+        let points = {
+            let index = self.read_index()?;
+            fetch_vectors(&index, &ordered_ids, dim)?
+        };
+
+        let displacements: Vec<Vec<f32>> = points
+            .windows(2)
+            .map(|w| w[1].iter().zip(w[0].iter()).map(|(&b, &a)| b - a).collect())
+            .collect();
+
+        Ok((1..=max_lag as usize)
+            .map(|lag| {
+                if lag >= displacements.len() {
+                    return 0.0;
+                }
+                let pairs = displacements.len() - lag;
+                (0..pairs).map(|i| cosine(&displacements[i], &displacements[i + lag])).sum::<f64>() / pairs as f64
+            })
+            .collect())
+    }
+
+    /// 滑动窗口情绪波动: 长度为 window 的窗口逐位滑过轨迹，每个位置取窗口内各向量到窗口质心的平均欧氏距离
+    /// 返回 len(ordered_ids) - window + 1 个值 (轨迹短于窗口时为空列表)，值越大该时段越动荡
+    pub fn compute_window_variance(&self, ordered_ids: Vec<u32>, window: u32) -> PyResult<Vec<f64>> {
+        if window == 0 {
+            return Err(PyErr::new::<InvalidArgumentError, _>("window must be at least 1".to_string()));
+        }
+        let dim = self.config.dimensions as usize;
+        let points = {
+            let index = self.read_index()?;
+            fetch_vectors(&index, &ordered_ids, dim)?
+        };
+
+        Ok(points
+            .windows(window as usize)
+            .map(|span| {
+                let mut centroid = vec![0.0f64; dim];
+                for p in span {
+                    for (c, &x) in centroid.iter_mut().zip(p.iter()) {
+                        *c += x as f64;
+                    }
+                }
+                centroid.iter_mut().for_each(|c| *c /= span.len() as f64);
+                span.iter()
+                    .map(|p| p.iter().zip(centroid.iter()).map(|(&x, &c)| (x as f64 - c).powi(2)).sum::<f64>().sqrt())
+                    .sum::<f64>()
+                    / span.len() as f64
+            })
+            .collect())
+    }
+
+    /// 两组向量经验分布 (均匀权重) 之间的 Sinkhorn 近似 Wasserstein 距离，代价为欧氏距离
+    /// 在对数域迭代 n_iter 次 Sinkhorn-Knopp (reg 较小时不会下溢)，返回运输方案下的期望代价 Σ P_ij·C_ij
+    /// (不含熵正则项)。reg 越小越接近精确 EMD，但需要更多迭代才能收敛
+    pub fn compute_sinkhorn_distance(&self, ids_a: Vec<u32>, ids_b: Vec<u32>, reg: f64, n_iter: u32) -> PyResult<f64> {
+        if ids_a.is_empty() || ids_b.is_empty() {
+            return Err(PyErr::new::<InvalidArgumentError, _>("Both id lists must be non-empty".to_string()));
+        }
+        if !(reg.is_finite() && reg > 0.0) {
+            return Err(PyErr::new::<InvalidArgumentError, _>(format!("reg must be a positive finite number, got {}", reg)));
+        }
+        if n_iter == 0 {
+            return Err(PyErr::new::<InvalidArgumentError, _>("n_iter must be at least 1".to_string()));
+        }
+        let dim = self.config.dimensions as usize;
+        let (a, b) = {
+            let index = self.read_index()?;
+            (fetch_vectors(&index, &ids_a, dim)?, fetch_vectors(&index, &ids_b, dim)?)
+        };
+
+        let cost: Vec<Vec<f64>> = a.iter().map(|x| b.iter().map(|y| l2sq(x, y).sqrt()).collect()).collect();
+        let log_a = -(a.len() as f64).ln();
+        let log_b = -(b.len() as f64).ln();
+        let mut f = vec![0.0f64; a.len()];
+        let mut g = vec![0.0f64; b.len()];
+        for _ in 0..n_iter {
+            for (i, fi) in f.iter_mut().enumerate() {
+                *fi = reg * (log_a - logsumexp(g.iter().zip(&cost[i]).map(|(gj, c)| (gj - c) / reg)));
+            }
+            for (j, gj) in g.iter_mut().enumerate() {
+                *gj = reg * (log_b - logsumexp(f.iter().zip(&cost).map(|(fi, row)| (fi - row[j]) / reg)));
+            }
+        }
+
+        Ok(cost
+            .iter()
+            .zip(&f)
+            .map(|(row, fi)| row.iter().zip(&g).map(|(c, gj)| ((fi + gj - c) / reg).exp() * c).sum::<f64>())
+            .sum())
+    }
+
+    /// 两条有序向量序列之间的离散 Fréchet 距离 (欧氏距离): 沿两条路径各自只进不退地同时行走时，
+    /// 所需"牵绳"长度的最小值。动态规划 O(len_a × len_b)，只保留一行状态
+    pub fn compute_frechet_distance(&self, ids_a: Vec<u32>, ids_b: Vec<u32>) -> PyResult<f64> {
+        if ids_a.is_empty() || ids_b.is_empty() {
+            return Err(PyErr::new::<InvalidArgumentError, _>("Both id lists must be non-empty".to_string()));
+        }
+        let dim = self.config.dimensions as usize;
+        let (a, b) = {
+            let index = self.read_index()?;
+            (fetch_vectors(&index, &ids_a, dim)?, fetch_vectors(&index, &ids_b, dim)?)
+        };
+
+        // row[j] = 走到 (a[i], b[j]) 时的最小牵绳长度
+        let mut row = vec![0.0f64; b.len()];
+        for (i, x) in a.iter().enumerate() {
+            let mut diagonal = 0.0f64;
+            for (j, y) in b.iter().enumerate() {
+                let d = l2sq(x, y).sqrt();
+                let reach = match (i, j) {
+                    (0, 0) => 0.0,
+                    (0, _) => row[j - 1],
+                    (_, 0) => row[0],
+                    _ => diagonal.min(row[j]).min(row[j - 1]),
+                };
+                diagonal = row[j];
+                row[j] = d.max(reach);
+            }
+        }
+        Ok(row[b.len() - 1])
+    }
+
+    /// 动态时间规整 (DTW): 返回 (累计欧氏距离, 对齐路径)，适合比较长度不同的情绪轨迹
+    /// 路径为从 (0, 0) 到 (len_a - 1, len_b - 1) 的 (ids_a 下标, ids_b 下标) 序列；
+    /// 回溯时多个前驱代价相同优先走对角线。标准动态规划，时间与内存均为 O(len_a × len_b)
+    pub fn compute_dtw(&self, ids_a: Vec<u32>, ids_b: Vec<u32>) -> PyResult<(f64, Vec<(u32, u32)>)> {
+        if ids_a.is_empty() || ids_b.is_empty() {
+            return Err(PyErr::new::<InvalidArgumentError, _>("Both id lists must be non-empty".to_string()));
+        }
+        let dim = self.config.dimensions as usize;
+        let (a, b) = {
+            let index = self.read_index()?;
+            (fetch_vectors(&index, &ids_a, dim)?, fetch_vectors(&index, &ids_b, dim)?)
+        };
+
+        let (n, m) = (a.len(), b.len());
+        // acc[i * m + j] = 对齐 a[..=i] 与 b[..=j] 的最小累计代价
+        let mut acc = vec![f64::INFINITY; n * m];
+        for (i, x) in a.iter().enumerate() {
+            for (j, y) in b.iter().enumerate() {
+                let best_prev = match (i, j) {
+                    (0, 0) => 0.0,
+                    (0, _) => acc[j - 1],
+                    (_, 0) => acc[(i - 1) * m],
+                    _ => acc[(i - 1) * m + j - 1].min(acc[(i - 1) * m + j]).min(acc[i * m + j - 1]),
+                };
+                acc[i * m + j] = l2sq(x, y).sqrt() + best_prev;
+            }
+        }
+
+        let mut path = vec![(n as u32 - 1, m as u32 - 1)];
+        let (mut i, mut j) = (n - 1, m - 1);
+        while i > 0 || j > 0 {
+            (i, j) = match (i, j) {
+                (0, _) => (0, j - 1),
+                (_, 0) => (i - 1, 0),
+                _ => {
+                    let diagonal = acc[(i - 1) * m + j - 1];
+                    let up = acc[(i - 1) * m + j];
+                    let left = acc[i * m + j - 1];
+                    if diagonal <= up && diagonal <= left {
+                        (i - 1, j - 1)
+                    } else if up <= left {
+                        (i - 1, j)
+                    } else {
+                        (i, j - 1)
+                    }
+                }
+            };
+            path.push((i as u32, j as u32));
+        }
+        path.reverse();
+        Ok((acc[n * m - 1], path))
+    }
+
+    /// 标签共现矩阵: 统计每对标签出现在同一篇日记中的次数，返回 n_tags × n_tags 的对称矩阵 (行优先展开)
+    /// 标签 ID 为 0..n_tags 的下标；对角线为包含该标签的日记数，同一篇内重复的标签只计一次。
+    /// 纯统计，不访问索引，可作为 PMI 标签嵌入训练的输入
+    pub fn compute_cooccurrence_matrix(&self, entry_tag_lists: Vec<Vec<u32>>, n_tags: u32) -> PyResult<Vec<f64>> {
+        self.check_open()?;
+        let n = n_tags as usize;
+        let mut matrix = vec![0.0f64; n * n];
+        for tags in &entry_tag_lists {
+            let unique: BTreeSet<u32> = tags.iter().copied().collect();
+            if let Some(&bad) = unique.iter().find(|&&t| t >= n_tags) {
+                return Err(PyErr::new::<InvalidArgumentError, _>(format!(
+                    "Tag ID {} out of range for n_tags={}",
+                    bad, n_tags
+                )));
+            }
+            let unique: Vec<usize> = unique.into_iter().map(|t| t as usize).collect();
+            for (i, &a) in unique.iter().enumerate() {
+                matrix[a * n + a] += 1.0;
+                for &b in &unique[i + 1..] {
+                    matrix[a * n + b] += 1.0;
+                    matrix[b * n + a] += 1.0;
+                }
+            }
+        }
+        Ok(matrix)
+    }
+
+    /// 从共现计数构建 PPMI 矩阵并做 SVD，得到 k_pmi 维标签嵌入 (n_tags × k_pmi，行优先展开)
+    /// PPMI_ij = max(0, log(C_ij · N / (r_i · r_j)))，N 为总计数、r 为行和；嵌入取 U_k · sqrt(S_k)。
+    /// 每列符号固定为绝对值最大的分量为正，结果确定。没有任何共现的标签嵌入为零向量
+    pub fn compute_pmi_embedding(&self, cooccurrence_matrix: Vec<f64>, n_tags: u32, k_pmi: u32) -> PyResult<Vec<f64>> {
+        self.check_open()?;
+        let n = n_tags as usize;
+        let k = k_pmi as usize;
+        if cooccurrence_matrix.len() != n * n {
+            return Err(PyErr::new::<DimensionMismatchError, _>(format!(
+                "cooccurrence_matrix has {} entries, expected {} × {}",
+                cooccurrence_matrix.len(), n, n
+            )));
+        }
+        if k == 0 || k > n {
+            return Err(PyErr::new::<InvalidArgumentError, _>(format!(
+                "k_pmi must be between 1 and n_tags={}, got {}",
+                n, k
+            )));
+        }
+        if let Some(bad) = cooccurrence_matrix.iter().find(|c| !c.is_finite() || **c < 0.0) {
+            return Err(PyErr::new::<InvalidArgumentError, _>(format!(
+                "Co-occurrence counts must be finite and non-negative, got {}",
+                bad
+            )));
+        }
+
+        let row_sums: Vec<f64> = cooccurrence_matrix.chunks(n).map(|row| row.iter().sum()).collect();
+        let total: f64 = row_sums.iter().sum();
+        let ppmi: Vec<f64> = cooccurrence_matrix
+            .iter()
+            .enumerate()
+            .map(|(idx, &c)| {
+                let (i, j) = (idx / n, idx % n);
+                if c > 0.0 { (c * total / (row_sums[i] * row_sums[j])).ln().max(0.0) } else { 0.0 }
+            })
+            .collect();
+
+        use nalgebra::DMatrix;
+        let svd = DMatrix::from_row_slice(n, n, &ppmi).svd(true, false);
+        let u = svd.u.ok_or_else(|| PyErr::new::<VexusError, _>("Failed to compute U matrix".to_string()))?;
+
+        let mut columns: Vec<Vec<f64>> = (0..k)
+            .map(|c| {
+                let scale = svd.singular_values[c].sqrt();
+                u.column(c).iter().map(|&x| x * scale).collect()
+            })
+            .collect();
+        for column in &mut columns {
+            let pivot = column.iter().copied().fold(0.0f64, |m, x| if x.abs() > m.abs() { x } else { m });
+            if pivot < 0.0 {
+                column.iter_mut().for_each(|x| *x = -*x);
+            }
+        }
+
+        Ok((0..n).flat_map(|row| columns.iter().map(move |column| column[row])).collect())
+    }
+
+    /// 软检索: 按 compute_attention 的权重对 key_ids 的向量加权求和，得到单个向量
+    pub fn retrieve_weighted(&self, query: Vec<u8>, key_ids: Vec<u32>, temperature: f64) -> PyResult<Vec<f64>> {
+        self.check_open()?;
+        let (weights, values) = self.attend(&query, &key_ids, temperature)?;
+        let mut out = vec![0.0f64; self.config.dimensions as usize];
+        for (w, v) in weights.iter().zip(values.iter()) {
+            for (o, &x) in out.iter_mut().zip(v.iter()) {
+                *o += w * x as f64;
+            }
+        }
+        Ok(out)
+    }
+
+    /// 高性能 SVD 分解
+    /// 最少样本数: 提取 max_k 个主成分至少需要 max_k 个向量 (且至少 1 个)，不足时报 ValueError
+    pub fn compute_svd(&self, flattened_vectors: Vec<u8>, n: u32, max_k: u32) -> PyResult<SvdResult> {
+        self.check_open()?;
+        let dim = self.config.dimensions as usize;
+        let n = n as usize;
+        let max_k = max_k as usize;
+
+        if n < max_k.max(1) {
+            return Err(PyErr::new::<InvalidArgumentError, _>(format!(
+                "compute_svd needs at least {} vectors to extract {} components, got {}",
+                max_k.max(1), max_k, n
+            )));
+        }
+
+        let vec_slice = &*f32_view(&flattened_vectors, "flattened_vectors", Some(n * dim))?;
+
+        use nalgebra::DMatrix;
+        let matrix = DMatrix::from_row_slice(n, dim, vec_slice);
+        let svd = matrix.svd(false, true);
+
+        let s = svd.singular_values.as_slice().iter().map(|&x| x as f64).collect::<Vec<_>>();
+        let v_t = svd.v_t.ok_or_else(|| PyErr::new::<VexusError, _>("Failed to compute V^T matrix".to_string()))?;
+
+        let k = std::cmp::min(s.len(), max_k);
+        let mut u_flattened = Vec::with_capacity(k * dim);
+
+        for i in 0..k {
+            let row = v_t.row(i);
+            for &val in row.iter() {
+                u_flattened.push(val as f64);
+            }
+        }
+
+        Ok(SvdResult {
+            u: u_flattened,
+            s: s[..k].to_vec(),
+            k: k as u32,
+            dim: dim as u32,
+            error_bound: None,
+        })
+    }
+
+    /// 流式近似 SVD (Frequent Directions)，用于放不进内存的大数据
+    /// chunks_iterator 逐批产出扁平 f32 字节 (每批若干个 dim_check 维向量，空批跳过)，
+    /// sketch 只保留 2k 行，内存为 O(k × dim)，与总行数无关。结果格式同 compute_svd (不去均值)。
+    /// 近似界: error_bound 为累计收缩量 Δ，满足 ||AᵀA - BᵀB||₂ <= Δ <= ||A - A_k||_F² / k，
+    /// 即每个 σ_i² 至多被低估 Δ (σ_i(B)² ∈ [σ_i(A)² - Δ, σ_i(A)²])；数据秩不超过 k 时 Δ = 0、结果精确。
+    /// Frequent Directions 为确定性算法，seed 不影响结果。迭代器中途抛出的异常原样传出
+    #[pyo3(signature = (chunks_iterator, dim_check, k, seed=0))]
+    pub fn compute_svd_streaming(&self, py: Python<'_>, chunks_iterator: PyObject, dim_check: u32, k: u32, seed: u64) -> PyResult<SvdResult> {
+        self.check_open()?;
+        let _ = seed;
+        let dim = self.config.dimensions as usize;
+        if dim_check as usize != dim {
+            return Err(PyErr::new::<DimensionMismatchError, _>(format!(
+                "dim_check {} does not match index dimension {}",
+                dim_check, dim
+            )));
+        }
+        let k = k as usize;
+        if k == 0 || k > dim {
+            return Err(PyErr::new::<InvalidArgumentError, _>(format!(
+                "compute_svd_streaming needs between 1 and {} components, got k={}",
+                dim, k
+            )));
+        }
+
+        let ell = 2 * k;
+        let mut sketch = vec![0.0f64; ell * dim];
+        let mut filled = 0usize;
+        let mut total_rows = 0u64;
+        let mut shrinkage = 0.0f64;
+
+        for item in chunks_iterator.bind(py).iter()? {
+            let bytes: Vec<u8> = item?.extract()?;
+            if bytes.is_empty() {
+                continue;
+            }
+            let rows = &*f32_view(&bytes, "chunk", None)?;
+            if !rows.len().is_multiple_of(dim) {
+                return Err(PyErr::new::<DimensionMismatchError, _>(format!(
+                    "chunk has {} f32 elements, not a multiple of dim {}",
+                    rows.len(),
+                    dim
+                )));
+            }
+            for row in rows.chunks_exact(dim) {
+                if filled == ell {
+                    shrinkage += fd_shrink(&mut sketch, ell, dim, k);
+                    filled = k;
+                }
+                for (dst, &x) in sketch[filled * dim..(filled + 1) * dim].iter_mut().zip(row.iter()) {
+                    *dst = x as f64;
+                }
+                filled += 1;
+                total_rows += 1;
+            }
+        }
+
+        if total_rows < k as u64 {
+            return Err(PyErr::new::<InvalidArgumentError, _>(format!(
+                "compute_svd_streaming needs at least {} vectors to extract {} components, got {}",
+                k, k, total_rows
+            )));
+        }
+
+        let svd = nalgebra::DMatrix::from_row_slice(filled, dim, &sketch[..filled * dim]).svd(false, true);
+        let v_t = svd.v_t.ok_or_else(|| PyErr::new::<VexusError, _>("Failed to compute V^T matrix".to_string()))?;
+        let k = k.min(svd.singular_values.len());
+        let u = (0..k).flat_map(|i| v_t.row(i).iter().copied().collect::<Vec<f64>>()).collect();
+
+        Ok(SvdResult {
+            u,
+            s: svd.singular_values.as_slice()[..k].to_vec(),
+            k: k as u32,
+            dim: dim as u32,
+            error_bound: Some(shrinkage),
+        })
+    }
+
+    /// 高性能 Gram-Schmidt 正交投影
+    /// assume_orthonormal=true 时跳过正交化, 直接对给定基 (如 compute_svd 的结果) 求系数;
+    /// verify=true 时先检查基两两内积与模长, 偏离超过容差则报错
+    /// 全零 tag 不参与正交化，系数为 0；全零 vector 的投影、残差、系数全为 0。
+    /// skip_zero_vectors=true 时去掉全零 tag 并在 skipped_rows 中报告其下标；strict=true 时遇到全零 vector / tag 直接报错
+    #[pyo3(signature = (vector, flattened_tags, n_tags, assume_orthonormal=false, verify=false, skip_zero_vectors=false, strict=false))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn compute_orthogonal_projection(
+        &self,
+        vector: Vec<u8>,
+        flattened_tags: Vec<u8>,
+        n_tags: u32,
+        assume_orthonormal: bool,
+        verify: bool,
+        skip_zero_vectors: bool,
+        strict: bool,
+    ) -> PyResult<OrthogonalProjectionResult> {
+        self.check_open()?;
+        let dim = self.config.dimensions as usize;
+        let n = n_tags as usize;
+
+        let query = &*f32_view(&vector, "vector", Some(dim))?;
+        let tags_slice = &*f32_view(&flattened_tags, "flattened_tags", Some(n * dim))?;
+        check_nonzero_vector(query, "vector", strict)?;
+        let (tags_slice, skipped_rows) = drop_zero_rows(tags_slice, dim, "flattened_tags", skip_zero_vectors, strict)?;
+
+        let mut result = if assume_orthonormal {
+            if verify {
+                verify_orthonormal(&tags_slice, dim)?;
+            }
+            project_onto_orthonormal(query, &tags_slice, dim)
+        } else {
+            project_onto_basis(query, &gram_schmidt(&tags_slice, dim), tags_slice.len() / dim.max(1))
+        };
+        result.skipped_rows = skipped_rows;
+        Ok(result)
+    }
+
+    /// 高性能握手分析
+    /// extended=true 时额外返回 relative_magnitudes / query_norm / tag_norms (同一遍计算)
+    /// 与 query 重合的 tag 方向为零向量；全零 tag 的 relative_magnitude 为 +inf。
+    /// skip_zero_vectors=true 时去掉全零 tag 并在 skipped_rows 中报告其下标；strict=true 时遇到全零 query / tag 直接报错
+    #[pyo3(signature = (query, flattened_tags, n_tags, extended=false, skip_zero_vectors=false, strict=false))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn compute_handshakes(
+        &self,
+        query: Vec<u8>,
+        flattened_tags: Vec<u8>,
+        n_tags: u32,
+        extended: bool,
+        skip_zero_vectors: bool,
+        strict: bool,
+    ) -> PyResult<HandshakeResult> {
+        self.check_open()?;
+        let dim = self.config.dimensions as usize;
+        let n = n_tags as usize;
+
+        let q = &*f32_view(&query, "query", Some(dim))?;
+        let tags = &*f32_view(&flattened_tags, "flattened_tags", Some(n * dim))?;
+        check_nonzero_vector(q, "query", strict)?;
+        let (tags, skipped_rows) = drop_zero_rows(tags, dim, "flattened_tags", skip_zero_vectors, strict)?;
+
+        let mut result = handshakes(q, &tags, dim, extended, None);
+        result.skipped_rows = skipped_rows;
+        Ok(result)
+    }
+
+    /// 批量判断每个 query 是否 (近似) 落在 tags 张成的子空间内: ||residual|| / ||query|| < tol
+    /// Gram-Schmidt 正交基只算一次 (同 compute_orthogonal_projection)，各 query 并行计算；
+    /// 全零 query 属于任何子空间，返回 True
+    #[allow(clippy::too_many_arguments)]
+    pub fn in_span(
+        &self,
+        py: Python<'_>,
+        queries: Vec<u8>,
+        n: u32,
+        flattened_tags: Vec<u8>,
+        n_tags: u32,
+        tol: f64,
+    ) -> PyResult<Vec<bool>> {
+        self.check_open()?;
+        if !(tol.is_finite() && tol > 0.0) {
+            return Err(PyErr::new::<InvalidArgumentError, _>(format!("tol must be a positive finite number, got {}", tol)));
+        }
+        let dim = self.config.dimensions as usize;
+        let q = &*f32_view(&queries, "queries", Some(n as usize * dim))?;
+        let tags = &*f32_view(&flattened_tags, "flattened_tags", Some(n_tags as usize * dim))?;
+
+        Ok(py.allow_threads(|| {
+            let basis = gram_schmidt(tags, dim);
+            q.par_chunks_exact(dim.max(1))
+                .map(|query| {
+                    let query_norm = dot(query, query).sqrt();
+                    if query_norm == 0.0 {
+                        return true;
+                    }
+                    let residual = project_onto_basis(query, &basis, n_tags as usize).residual;
+                    residual.iter().map(|r| r * r).sum::<f64>().sqrt() / query_norm < tol
+                })
+                .collect()
+        }))
+    }
+
+    /// 只计算握手的 magnitudes (query 到每个 tag 的欧氏距离)，不分配 n*dim 的 directions
+    /// 结果与 compute_handshakes(...).magnitudes 相同；skip_zero_vectors=true 时去掉全零 tag，
+    /// 被去掉的下标与同参数 compute_handshakes 的 skipped_rows 相同；strict=true 时遇到全零 query / tag 直接报错
+    #[pyo3(signature = (query, flattened_tags, n_tags, skip_zero_vectors=false, strict=false))]
+    pub fn compute_handshake_magnitudes(
+        &self,
+        query: Vec<u8>,
+        flattened_tags: Vec<u8>,
+        n_tags: u32,
+        skip_zero_vectors: bool,
+        strict: bool,
+    ) -> PyResult<Vec<f64>> {
+        self.check_open()?;
+        let dim = self.config.dimensions as usize;
+        let n = n_tags as usize;
+
+        let q = &*f32_view(&query, "query", Some(dim))?;
+        let tags = &*f32_view(&flattened_tags, "flattened_tags", Some(n * dim))?;
+        check_nonzero_vector(q, "query", strict)?;
+        let (tags, _) = drop_zero_rows(tags, dim, "flattened_tags", skip_zero_vectors, strict)?;
+
+        Ok(tags.chunks_exact(dim.max(1)).map(|t| l2sq(q, t).sqrt()).collect())
+    }
+
+    /// 每个 query 余弦相似度最高的 tag: 返回 (tag 下标, 相似度)，并列时取下标小的
+    /// tag 模长只算一次，各 query 并行计算；零向量与任何 tag 的相似度记为 0 (全零 query 返回 (0, 0.0))
+    /// skip_zero_vectors=true 时全零 tag 不参与比较 (返回的仍是原下标，全零 query 返回第一个非零 tag)；
+    /// strict=true 时遇到全零 query / tag 直接报错
+    #[pyo3(signature = (queries, n_queries, flattened_tags, n_tags, strict=false, skip_zero_vectors=false))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn best_tag(
+        &self,
+        py: Python<'_>,
+        queries: Vec<u8>,
+        n_queries: u32,
+        flattened_tags: Vec<u8>,
+        n_tags: u32,
+        strict: bool,
+        skip_zero_vectors: bool,
+    ) -> PyResult<(Vec<u32>, Vec<f64>)> {
+        self.check_open()?;
+        let dim = self.config.dimensions as usize;
+        if n_tags == 0 {
+            return Err(PyErr::new::<InvalidArgumentError, _>("best_tag needs at least 1 tag".to_string()));
+        }
+
+        let q = &*f32_view(&queries, "queries", Some(n_queries as usize * dim))?;
+        let tags = &*f32_view(&flattened_tags, "flattened_tags", Some(n_tags as usize * dim))?;
+        drop_zero_rows(q, dim, "queries", false, strict)?;
+        drop_zero_rows(tags, dim, "flattened_tags", false, strict)?;
+        let tag_norms: Vec<f64> = tags.chunks_exact(dim.max(1)).map(|t| dot(t, t).sqrt()).collect();
+        if skip_zero_vectors && tag_norms.iter().all(|&tn| tn == 0.0) {
+            return Err(PyErr::new::<InvalidArgumentError, _>("best_tag: every tag is an all-zero vector".to_string()));
+        }
+
+        let best: Vec<(u32, f64)> = py.allow_threads(|| {
+            q.par_chunks_exact(dim.max(1))
+                .map(|query| {
+                    let qn = dot(query, query).sqrt();
+                    let mut best = (0u32, f64::NEG_INFINITY);
+                    for (i, (t, &tn)) in tags.chunks_exact(dim.max(1)).zip(tag_norms.iter()).enumerate() {
+                        if skip_zero_vectors && tn == 0.0 {
+                            continue;
+                        }
+                        let sim = if qn > 0.0 && tn > 0.0 { dot(query, t) / (qn * tn) } else { 0.0 };
+                        if sim > best.1 {
+                            best = (i as u32, sim);
+                        }
+                    }
+                    best
+                })
+                .collect()
+        });
+
+        Ok(best.into_iter().unzip())
+    }
+
+    /// 高性能 EPA 投影
+    /// 基向量个数 k 须在 [1, dim] 之间 (dim 维空间最多 dim 个正交成分)
+    /// 去均值后能量为零 (如 vector 等于 mean) 时 probabilities 全为 0、entropy 为 0；
+    /// strict=true 时遇到全零 vector / 基向量直接报错
+    #[pyo3(signature = (vector, flattened_basis, mean_vector, k, strict=false))]
+    pub fn project(
+        &self,
+        vector: Vec<u8>,
+        flattened_basis: Vec<u8>,
+        mean_vector: Vec<u8>,
+        k: u32,
+        strict: bool,
+    ) -> PyResult<ProjectResult> {
+        self.check_open()?;
+        let dim = self.config.dimensions as usize;
+        let k = k as usize;
+
+        if k == 0 || k > dim {
+            return Err(PyErr::new::<InvalidArgumentError, _>(format!(
+                "project needs between 1 and {} basis components, got k={}",
+                dim, k
+            )));
+        }
+
+        let vec = &*f32_view(&vector, "vector", Some(dim))?;
+        let basis_slice = &*f32_view(&flattened_basis, "flattened_basis", Some(k * dim))?;
+        let mean = &*f32_view(&mean_vector, "mean_vector", Some(dim))?;
+        check_nonzero_vector(vec, "vector", strict)?;
+        drop_zero_rows(basis_slice, dim, "flattened_basis", false, strict)?;
+
+        let mut centered = vec![0.0; dim];
+        for d in 0..dim {
+            centered[d] = (vec[d] - mean[d]) as f64;
+        }
+
+        let mut projections = vec![0.0; k];
+        let mut total_energy = 0.0;
+
+        for i in 0..k {
+            let start = i * dim;
+            let b = &basis_slice[start..start + dim];
+            let mut dot = 0.0;
+            for d in 0..dim {
+                dot += centered[d] * (b[d] as f64);
+            }
+            projections[i] = dot;
+            total_energy += dot * dot;
+        }
+
+        let mut probabilities = vec![0.0; k];
+        let mut entropy = 0.0;
+
+        if total_energy > 1e-12 {
+            for i in 0..k {
+                let p = (projections[i] * projections[i]) / total_energy;
+                probabilities[i] = p;
+                if p > 1e-9 {
+                    entropy -= p * p.log2();
+                }
+            }
+        }
+
+        Ok(ProjectResult {
+            projections,
+            probabilities,
+            entropy,
+            total_energy,
+        })
+    }
+
+    /// 主成分载荷: 对 components (k × dim，如 compute_svd 的 u) 中的每个成分，
+    /// 返回按 |载荷| 从大到小的前 top_n 个原始维度 (维度下标, 带符号的载荷)，并列时下标小的在前；top_n 超过 dim 时取全部
+    pub fn component_loadings(&self, components: Vec<u8>, k: u32, top_n: u32) -> PyResult<Vec<Vec<(u32, f64)>>> {
+        self.check_open()?;
+        let dim = self.config.dimensions as usize;
+        let rows = &*f32_view(&components, "components", Some(k as usize * dim))?;
+
+        Ok(rows
+            .chunks_exact(dim.max(1))
+            .map(|component| {
+                let mut loadings: Vec<(u32, f64)> =
+                    component.iter().enumerate().map(|(d, &x)| (d as u32, x as f64)).collect();
+                loadings.sort_by(|a, b| b.1.abs().total_cmp(&a.1.abs()).then(a.0.cmp(&b.0)));
+                loadings.truncate(top_n as usize);
+                loadings
+            })
+            .collect())
+    }
+
+    /// 批量 EPA 分类: 对每个向量返回 project(...).probabilities 的 argmax (并列取最小下标)
+    /// 能量为零的向量概率全为 0，返回 0；skip_zero_vectors=true 时全零基向量不参与 argmax
+    /// (返回的仍是原下标，能量为零时返回第一个非零基向量)；strict=true 时遇到全零向量 / 基向量直接报错
+    #[pyo3(signature = (flattened, n, flattened_basis, mean_vector, k, strict=false, skip_zero_vectors=false))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn classify_epa(
+        &self,
+        py: Python<'_>,
+        flattened: Vec<u8>,
+        n: u32,
+        flattened_basis: Vec<u8>,
+        mean_vector: Vec<u8>,
+        k: u32,
+        strict: bool,
+        skip_zero_vectors: bool,
+    ) -> PyResult<Vec<u32>> {
+        self.check_open()?;
+        let dim = self.config.dimensions as usize;
+        let k = k as usize;
+
+        let vectors = &*f32_view(&flattened, "flattened", Some(n as usize * dim))?;
+        let basis = &*f32_view(&flattened_basis, "flattened_basis", Some(k * dim))?;
+        let mean = &*f32_view(&mean_vector, "mean_vector", Some(dim))?;
+        drop_zero_rows(vectors, dim, "flattened", false, strict)?;
+        let skipped = drop_zero_rows(basis, dim, "flattened_basis", skip_zero_vectors, strict)?.1;
+        let candidates: Vec<usize> = (0..k).filter(|i| skipped.binary_search(&(*i as u32)).is_err()).collect();
+        if k == 0 {
+            return Err(PyErr::new::<InvalidArgumentError, _>("classify_epa needs at least 1 basis component".to_string()));
+        }
+        if candidates.is_empty() {
+            return Err(PyErr::new::<InvalidArgumentError, _>("classify_epa: every basis component is an all-zero vector".to_string()));
+        }
+
+        Ok(py.allow_threads(|| {
+            vectors
+                .par_chunks(dim)
+                .map(|v| {
+                    let projections: Vec<f64> = basis
+                        .chunks_exact(dim)
+                        .map(|b| {
+                            v.iter()
+                                .zip(mean.iter())
+                                .zip(b.iter())
+                                .map(|((&x, &m), &bd)| ((x - m) as f64) * (bd as f64))
+                                .sum()
+                        })
+                        .collect();
+                    let total_energy: f64 = projections.iter().map(|p| p * p).sum();
+                    if total_energy <= 1e-12 {
+                        return candidates[0] as u32;
+                    }
+
+                    let mut best = candidates[0];
+                    let mut best_p = f64::NEG_INFINITY;
+                    for &i in &candidates {
+                        let prob = projections[i] * projections[i] / total_energy;
+                        if prob > best_p {
+                            best = i;
+                            best_p = prob;
+                        }
+                    }
+                    best as u32
+                })
+                .collect()
+        }))
+    }
+}
+
+impl Drop for VexusIndex {
+    fn drop(&mut self) {
+        // close() 已经注销过
+        if !*self.closed.get_mut() {
+            release_shared_view(self.shared_path.as_deref());
+        }
+    }
+}
+
+impl VexusIndex {
+    /// search 的标签过滤路径，返回 ((key, 距离) 按距离升序, 所用策略)
+    fn search_labeled(
+        &self,
+        index: &Index,
+        query: &[f32],
+        k: usize,
+        labels: &[String],
+    ) -> PyResult<(Vec<(u64, f32)>, &'static str)> {
+        if labels.is_empty() {
+            return Err(PyErr::new::<InvalidArgumentError, _>("require_labels must not be empty".to_string()));
+        }
+        let candidates = {
+            let meta = self.meta.read()
+                .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+            meta.ids_with_all_labels(labels)
+        };
+
+        if candidates.len() <= LABEL_EXACT_MAX {
+            let metric = DistanceMetric::parse(&self.config.metric)?;
+            let dim = self.config.dimensions as usize;
+            let mut hits: Vec<(u64, f32)> = candidates
+                .iter()
+                .filter_map(|&key| get_vector(index, key, dim).map(|v| (key, metric.distance(query, &v) as f32)))
+                .collect();
+            hits.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+            hits.truncate(k);
+            return Ok((hits, "exact"));
+        }
+
+        let matches = index
+            .filtered_search(query, k, |key| candidates.binary_search(&key).is_ok())
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Search failed: {:?}", e)))?;
+        Ok((matches.keys.into_iter().zip(matches.distances).collect(), "filtered_hnsw"))
+    }
+
+    /// (key, 距离) 转为结果，拆出命名空间并打分 (search / search_into / score_profile 共用)
+    /// calibrated=true 时使用 fit_score_calibration 拟合的映射 (未拟合时报错)，否则 score = 1 - distance
+    fn score_matches(&self, matches: &[(u64, f32)], calibrated: bool) -> PyResult<Vec<SearchResult>> {
+        let calibration = if calibrated {
+            let meta = self.meta.read()
+                .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+            Some(meta.calibration.clone().ok_or_else(|| {
+                PyErr::new::<InvalidArgumentError, _>("Score calibration not fitted, call fit_score_calibration first".to_string())
+            })?)
+        } else {
+            None
+        };
+
+        Ok(matches
+            .iter()
+            .map(|&(key, dist)| {
+                let score = match &calibration {
+                    Some(c) => c.apply(dist as f64),
+                    None => 1.0 - dist as f64,
+                };
+                SearchResult::from_key(key, score, 0)
+            })
+            .collect())
+    }
+
+    /// 单次调用请求的结果数 (k / 候选数 / 页大小 / 批量的 n*k) 超过 max_results 时报 InvalidArgumentError
+    fn check_result_size(&self, name: &str, requested: impl Into<u64>) -> PyResult<()> {
+        let requested = requested.into();
+        if requested > self.max_results as u64 {
+            return Err(PyErr::new::<InvalidArgumentError, _>(format!(
+                "{}={} exceeds max_results={}",
+                name, requested, self.max_results
+            )));
+        }
+        Ok(())
+    }
+
+    /// 获取索引读锁 (已 close 时报错)
+    fn read_index(&self) -> PyResult<RwLockReadGuard<'_, Index>> {
+        self.check_open()?;
+        let guard = self.index.read()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+        // 等锁期间可能被 close
+        self.check_open()?;
+        Ok(guard)
+    }
+
+    /// 获取索引写锁 (已 close 或为只读共享视图时报错)
+    fn write_index(&self) -> PyResult<RwLockWriteGuard<'_, Index>> {
+        self.check_writable()?;
+        self.lock_index_exclusive()
+    }
+
+    /// 获取索引写锁但不检查只读 (save 只需要独占快照，不修改索引)
+    fn lock_index_exclusive(&self) -> PyResult<RwLockWriteGuard<'_, Index>> {
+        self.check_open()?;
+        let guard = self.index.write()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+        self.check_open()?;
+        Ok(guard)
+    }
+
+    /// 记录一次搜索到查询日志；未开启或锁中毒时直接跳过，不影响搜索本身
+    fn log_query<I>(&self, method: &str, k: u32, options: impl FnOnce() -> String, query: &[f32], results: I)
+    where
+        I: IntoIterator<Item = (u32, f64)>,
+    {
+        let Ok(mut guard) = self.query_log.lock() else {
+            return;
+        };
+        let Some(log) = guard.as_mut() else {
+            return;
+        };
+        let (ids, scores) = results.into_iter().unzip();
+        if log.entries.len() >= log.capacity {
+            log.entries.pop_front();
+        }
+        log.entries.push_back(QueryLogEntry {
+            timestamp: now_secs(),
+            method: method.to_string(),
+            k,
+            options: options(),
+            ids,
+            scores,
+            query: log.store_vectors.then(|| query.to_vec()),
+        });
+    }
+
+    /// open_shared 打开的视图不允许任何修改 (已 close 时先报 IndexClosedError)
+    fn check_writable(&self) -> PyResult<()> {
+        self.check_open()?;
+        if let Some(path) = &self.shared_path {
+            return Err(ReadOnlyIndexError::new_err(format!(
+                "Index is a read-only shared view of {}",
+                path.display()
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_open(&self) -> PyResult<()> {
+        if self.closed.load(Ordering::Acquire) {
+            return Err(IndexClosedError::new_err("Index is closed"));
+        }
+        Ok(())
+    }
+
+    /// 按 value_bounds 校验向量分量，报错信息包含 ID 与维度
+    fn check_bounds(&self, id: u64, vector: &[f32]) -> PyResult<()> {
+        let Some((lo, hi)) = self.config.value_bounds else {
+            return Ok(());
+        };
+
+        for (d, &x) in vector.iter().enumerate() {
+            if !(lo..=hi).contains(&x) {
+                return Err(PyErr::new::<InvalidArgumentError, _>(format!(
+                    "Value out of bounds for id {} at dimension {}: {} not in [{}, {}]",
+                    id, d, x, lo, hi
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 带有限次重试 (指数退避 1ms, 2ms, 4ms...) 的删除，返回重新持有的写锁与删除的条数
+    /// 失败后若 key 已不在索引中视为删除了 0 条；否则先释放写锁再退避，让其他线程在等待期间继续读写，
+    /// 退避结束后重新加锁再试。调用方不能同时持有 meta 锁 (否则与先拿索引锁再拿 meta 锁的线程死锁)。
+    /// 最终失败映射为 RemoveError 并带上 id
+    fn remove_with_retry<'a>(
+        &'a self,
+        mut index: RwLockWriteGuard<'a, Index>,
+        id: u64,
+    ) -> PyResult<(RwLockWriteGuard<'a, Index>, usize)> {
+        let mut attempt = 0u32;
+        loop {
+            match index.remove(id) {
+                Ok(n) => return Ok((index, n)),
+                Err(e) => {
+                    if !index.contains(id) {
+                        return Ok((index, 0));
+                    }
+                    if attempt >= REMOVE_MAX_RETRIES {
+                        return Err(RemoveError::new_err(format!(
+                            "Remove failed for id {} after {} attempts: {}", id, attempt + 1, e
+                        )));
+                    }
+                    drop(index);
+                    std::thread::sleep(std::time::Duration::from_millis(1 << attempt));
+                    index = self.lock_index_exclusive()?;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    /// 按索引 key 批量删除 (remove_batch / filter_by_predicate 共用)，返回实际删除的数量
+    /// 某个 key 重试后仍失败时，之前已删除的照常登记到 meta 后再抛出
+    fn remove_keys(&self, keys: &[u64]) -> PyResult<u32> {
+        let mut index = self.write_index()?;
+        let mut removed_keys = Vec::with_capacity(keys.len());
+        let mut failure = None;
+        for &key in keys {
+            match self.remove_with_retry(index, key) {
+                Ok((guard, removed)) => {
+                    index = guard;
+                    removed_keys.push((key, removed));
+                }
+                Err(e) => {
+                    failure = Some(e);
+                    break;
+                }
+            }
+        }
+
+        let mut meta = self.meta.write()
+            .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+        let mut count = 0u32;
+        for (key, removed) in removed_keys {
+            meta.remove_key(key);
+            if removed > 0 {
+                meta.lifetime.total_removes += 1;
+                meta.tombstones += removed as u64;
+                count += 1;
+            }
+        }
+        match failure {
+            Some(e) => Err(e),
+            None => Ok(count),
+        }
+    }
+
+    /// 注意力权重及对应的 key 向量 (compute_attention / retrieve_weighted 共用)
+    fn attend(&self, query: &[u8], key_ids: &[u32], temperature: f64) -> PyResult<(Vec<f64>, Vec<Vec<f32>>)> {
+        let dim = self.config.dimensions as usize;
+        let q = &*f32_view(query, "query", Some(dim))?;
+        check_temperature(temperature)?;
+        if key_ids.is_empty() {
+            return Ok((Vec::new(), Vec::new()));
+        }
+
+        let index = self.read_index()?;
+        let keys = fetch_vectors(&index, key_ids, dim)?;
+        Ok((attention_weights(q, &keys, temperature), keys))
+    }
+
+    /// 单个 SQLite 文件的恢复逻辑 (调用方持有 index/meta 写锁)
+    /// 不支持的 table_type 直接返回全 0 计数
+    #[allow(clippy::too_many_arguments)]
+    fn recover_file(
+        &self,
+        index: &Index,
+        meta: &mut IndexMeta,
+        db_path: &str,
+        table_type: &str,
+        filter_diary_name: Option<&str>,
+        id_filter: &RecoverIdFilter,
+        id_namespace: Option<u8>,
+        force: bool,
+        byte_order: ByteOrder,
+    ) -> PyResult<RecoverCounts> {
+        let conn = Connection::open(db_path)
+            .map_err(|e| PyErr::new::<SqliteError, _>(format!("Failed to open DB: {}", e)))?;
+
+        let id_col = if table_type == "tags" { "id" } else { "c.id" };
+        let (include_sql, include_set) = prepare_id_filter(&conn, "vexus_include", id_filter.include.as_deref(), id_col)
+            .map_err(|e| PyErr::new::<SqliteError, _>(format!("Failed to prepare include filter: {}", e)))?;
+        let (exclude_sql, exclude_set) = prepare_id_filter(&conn, "vexus_exclude", id_filter.exclude.as_deref(), id_col)
+            .map_err(|e| PyErr::new::<SqliteError, _>(format!("Failed to prepare exclude filter: {}", e)))?;
+
+        let sql: String;
+
+        if table_type == "tags" {
+            sql = format!("SELECT id, vector, {}, {} FROM tags WHERE vector IS NOT NULL", include_sql, exclude_sql);
+        } else if table_type == "chunks" && filter_diary_name.is_some() {
+            sql = format!(
+                "SELECT c.id, c.vector, {}, {} FROM chunks c JOIN diary_files f ON c.file_id = f.id WHERE f.diary_name = ?1 AND c.vector IS NOT NULL",
+                include_sql, exclude_sql
+            );
+        } else {
+            return Ok(RecoverCounts::default());
+        }
+
+        let mut counts = RecoverCounts::default();
+
+        // 先用 COUNT 统计行数，一次性扩容到位，避免逐行多次扩容
+        // 同时取 ID 范围，带命名空间时在写入任何行之前检查能否打包
+        let count_sql = format!("SELECT COUNT(*), MIN(id), MAX(id) FROM ({})", sql);
+        let map_count = |row: &rusqlite::Row<'_>| Ok((row.get::<_, i64>(0)?, row.get::<_, Option<i64>>(1)?, row.get::<_, Option<i64>>(2)?));
+        let (n_rows, min_id, max_id) = match filter_diary_name {
+            Some(name) => conn.query_row(&count_sql, [name], map_count),
+            None => conn.query_row(&count_sql, [], map_count),
+        }
+        .map_err(|e| PyErr::new::<SqliteError, _>(format!("Count query failed: {}", e)))?;
+        for id in min_id.into_iter().chain(max_id) {
+            namespaced_key(id_namespace, id)?;
+        }
+        let limit = self.memory_limit.load(Ordering::Relaxed);
+        if limit > 0 && !force {
+            let forecast = index.memory_usage() as u64 + self.config.estimate_memory(n_rows.max(0) as u64);
+            if forecast > limit {
+                return Err(PyErr::new::<CapacityError, _>(format!(
+                    "Recovering {} rows from {} needs ~{} bytes, over the memory limit of {} bytes (pass force=True to override)",
+                    n_rows, db_path, forecast, limit
+                )));
+            }
+        }
+        counts.growth_events.extend(ensure_capacity(index, index.size() + n_rows.max(0) as usize));
+
+        let mut stmt = conn
+            .prepare(&sql)
+            .map_err(|e| PyErr::new::<SqliteError, _>(format!("Failed to prepare statement: {}", e)))?;
+        let expected_byte_len = self.config.dimensions as usize * std::mem::size_of::<f32>();
+
+        // 大集合的成员关系由 SQL 列给出 (Some)，小集合为 NULL (None)，在这里查 HashSet
+        // 单行类型不符计入 skipped_row_errors，其余 SQLite 错误 (如读取中途失败) 直接报错
+        let mut process_row = |row: rusqlite::Result<RecoverRow>| -> PyResult<()> {
+            let (id, vector_bytes, in_include, in_exclude) = match row {
+                Ok(row) => row,
+                Err(
+                    rusqlite::Error::InvalidColumnType(..)
+                    | rusqlite::Error::FromSqlConversionFailure(..)
+                    | rusqlite::Error::IntegralValueOutOfRange(..),
+                ) => {
+                    counts.skipped_row_errors += 1;
+                    return Ok(());
+                }
+                Err(e) => return Err(PyErr::new::<SqliteError, _>(format!("Failed to read row: {}", e))),
+            };
+            let included = in_include.unwrap_or_else(|| include_set.as_ref().is_none_or(|set| set.contains(&id)));
+            let excluded = in_exclude.unwrap_or_else(|| exclude_set.as_ref().is_some_and(|set| set.contains(&id)));
+            if !included || excluded {
+                counts.excluded += 1;
+                return Ok(());
+            }
+            let key = namespaced_key(id_namespace, id)?;
+
+            if vector_bytes.len() == expected_byte_len {
+                let vec_slice = byte_order.decode(&vector_bytes, "vector", Some(self.config.dimensions as usize))?;
+                let vec_slice = &*vec_slice;
+
+                // 行数已预先统计，这里只是兜底
+                if let Some(event) = ensure_capacity(index, index.size() + 1) {
+                    counts.growth_events.push(event);
+                }
+
+                if index.add(key, &meta.normalized(vec_slice)).is_ok() {
+                    meta.insert_key(key, vec_slice);
+                    meta.lifetime.total_adds += 1;
+                    counts.inserted += 1;
+                }
+            } else {
+                counts.skipped_dim_mismatch += 1;
+            }
+            Ok(())
+        };
+
+        let map_row = |row: &rusqlite::Row<'_>| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, Vec<u8>>(1)?,
+                row.get::<_, Option<bool>>(2)?,
+                row.get::<_, Option<bool>>(3)?,
+            ))
+        };
+
+        if let Some(name) = filter_diary_name {
+            let rows = stmt.query_map([name], map_row)
+                .map_err(|e| PyErr::new::<SqliteError, _>(format!("Query failed: {}", e)))?;
+
+            for row in rows {
+                process_row(row)?;
+            }
+        } else {
+            let rows = stmt.query_map([], map_row)
+                .map_err(|e| PyErr::new::<SqliteError, _>(format!("Query failed: {}", e)))?;
+
+            for row in rows {
+                process_row(row)?;
+            }
+        }
+
+        meta.lifetime.total_recoveries += 1;
+
+        Ok(counts)
+    }
+
+}
+
+/// 逐个取回 (id, 向量) 的迭代器
+/// 创建时只快照 ID 列表 (升序)，向量在迭代时按需读取；期间已删除的 ID 直接跳过
+#[pyclass]
+pub struct VexusIterator {
+    index: Arc<RwLock<Index>>,
+    ids: std::vec::IntoIter<u64>,
+    dim: usize,
+}
+
+impl Iterator for VexusIterator {
+    type Item = (u64, Vec<f32>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.index.read().ok()?;
+        self.ids
+            .by_ref()
+            .find_map(|id| get_vector(&index, id, self.dim).map(|v| (id, v)))
+    }
+}
+
+#[pymethods]
+impl VexusIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(mut slf: PyRefMut<'_, Self>) -> Option<(u64, Vec<f32>)> {
+        slf.next()
+    }
+}
+
+/// 多版本索引 (A/B 测试 / 滚动切换嵌入模型)
+/// 新旧版本同时提供服务，promote 切换默认版本
+#[pyclass]
+#[derive(Default)]
+pub struct VersionedIndex {
+    versions: HashMap<String, Py<VexusIndex>>,
+    active: Option<String>,
+}
+
+#[pymethods]
+impl VersionedIndex {
+    #[new]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 注册一个版本 (同名覆盖)，第一个注册的版本自动成为默认版本
+    pub fn add_version(&mut self, name: String, index: Py<VexusIndex>) {
+        if self.active.is_none() {
+            self.active = Some(name.clone());
+        }
+        self.versions.insert(name, index);
+    }
+
+    /// 已注册的版本名 (按名称排序)
+    pub fn versions(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.versions.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// 当前默认版本
+    #[getter]
+    pub fn active_version(&self) -> Option<String> {
+        self.active.clone()
+    }
+
+    /// 将指定版本设为默认版本
+    pub fn promote(&mut self, version_name: String) -> PyResult<()> {
+        if !self.versions.contains_key(&version_name) {
+            return Err(PyErr::new::<NotFoundError, _>(format!("Unknown version: {}", version_name)));
+        }
+        self.active = Some(version_name);
+        Ok(())
+    }
+
+    /// 在默认版本上搜索
+    pub fn search(&self, py: Python<'_>, query: Vec<u8>, k: u32) -> PyResult<Vec<SearchResult>> {
+        let name = self.active.clone()
+            .ok_or_else(|| PyErr::new::<NotFoundError, _>("No version registered".to_string()))?;
+        self.search_version(py, name, query, k)
+    }
+
+    /// 在指定版本上搜索
+    pub fn search_version(&self, py: Python<'_>, version_name: String, query: Vec<u8>, k: u32) -> PyResult<Vec<SearchResult>> {
+        let index = self.versions.get(&version_name)
+            .ok_or_else(|| PyErr::new::<NotFoundError, _>(format!("Unknown version: {}", version_name)))?;
+        index.borrow(py).search(py, query, k, false, None, "best_first", None, false)
+    }
+
+    /// 同一查询在两个版本上的结果，用于对比
+    pub fn compare_versions(
+        &self,
+        py: Python<'_>,
+        v1: String,
+        v2: String,
+        query: Vec<u8>,
+        k: u32,
+    ) -> PyResult<(Vec<SearchResult>, Vec<SearchResult>)> {
+        let r1 = self.search_version(py, v1, query.clone(), k)?;
+        let r2 = self.search_version(py, v2, query, k)?;
+        Ok((r1, r2))
+    }
+}
+
+/// 冷热分层索引: hot 为常驻内存的近期记忆，cold 为历史大索引
+/// 新向量只写入 hot，demote 把向量移到 cold；搜索两层后合并，同一 ID 以 hot 为准
+/// 分层归属即各层索引中登记的 ID，随各自的 .meta 侧车持久化
+#[pyclass]
+pub struct TieredIndex {
+    hot: Py<VexusIndex>,
+    cold: Py<VexusIndex>,
+}
+
+#[pymethods]
+impl TieredIndex {
+    #[new]
+    pub fn new(py: Python<'_>, hot: Py<VexusIndex>, cold: Py<VexusIndex>) -> PyResult<Self> {
+        if hot.is(&cold) {
+            return Err(PyErr::new::<InvalidArgumentError, _>("hot and cold must be different indices".to_string()));
+        }
+        let (hot_dim, cold_dim) = (hot.borrow(py).config.dimensions, cold.borrow(py).config.dimensions);
+        if hot_dim != cold_dim {
+            return Err(PyErr::new::<DimensionMismatchError, _>(format!(
+                "Dimension mismatch: hot index has dim {}, cold index has dim {}",
+                hot_dim, cold_dim
+            )));
+        }
+        Ok(Self { hot, cold })
+    }
+
+    /// 从 save 写出的 <path>.hot / <path>.cold 加载两层，max_results 同时作用于两层
+    #[classmethod]
+    #[pyo3(signature = (dim, capacity, path, max_results=DEFAULT_MAX_RESULTS))]
+    pub fn load(cls: &Bound<'_, PyType>, dim: u32, capacity: u32, path: String, max_results: u32) -> PyResult<Self> {
+        let py = cls.py();
+        let vexus = py.get_type_bound::<VexusIndex>();
+        let hot = VexusIndex::load(&vexus, dim, capacity, format!("{}.hot", path), None, None, None, max_results)?;
+        let cold = VexusIndex::load(&vexus, dim, capacity, format!("{}.cold", path), None, None, None, max_results)?;
+        Ok(Self {
+            hot: Py::new(py, hot)?,
+            cold: Py::new(py, cold)?,
+        })
+    }
+
+    /// 两层分别保存到 <path>.hot / <path>.cold (各自带 .meta 侧车)
+    pub fn save(&self, py: Python<'_>, path: String) -> PyResult<()> {
+        self.hot.borrow(py).save(py, format!("{}.hot", path))?;
+        self.cold.borrow(py).save(py, format!("{}.cold", path))
+    }
+
+    #[getter]
+    pub fn hot(&self, py: Python<'_>) -> Py<VexusIndex> {
+        self.hot.clone_ref(py)
+    }
+
+    #[getter]
+    pub fn cold(&self, py: Python<'_>) -> Py<VexusIndex> {
+        self.cold.clone_ref(py)
+    }
+
+    /// 新向量总是写入 hot 层
+    pub fn add(&self, py: Python<'_>, id: u32, vector: Vec<u8>) -> PyResult<()> {
+        self.hot.borrow(py).add(py, id, vector, None, "le")
+    }
+
+    /// ID 所在的层: "hot" / "cold"，都不在时为 None (两层都有时以 hot 为准)
+    pub fn tier_of(&self, py: Python<'_>, id: u32) -> PyResult<Option<&'static str>> {
+        if self.hot.borrow(py).contains(id, None)? {
+            Ok(Some("hot"))
+        } else if self.cold.borrow(py).contains(id, None)? {
+            Ok(Some("cold"))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// 把 hot 层中的向量移到 cold 层 (先写 cold 再删 hot)，不在 hot 层的 ID 跳过
+    /// 返回实际移动的数量
+    pub fn demote(&self, py: Python<'_>, ids: Vec<u32>) -> PyResult<u32> {
+        let hot = self.hot.borrow(py);
+        let cold = self.cold.borrow(py);
+        let dim = hot.config.dimensions as usize;
+
+        let mut moved = 0u32;
+        for id in ids {
+            // 读锁在这里释放，之后 remove 需要写锁
+            let vector = {
+                let index = hot.read_index()?;
+                get_vector(&index, id as u64, dim)
+            };
+            let Some(vector) = vector else {
+                continue;
+            };
+            cold.add(py, id, f32_to_bytes(&vector), None, "le")?;
+            hot.remove(id, None)?;
+            moved += 1;
+        }
+        Ok(moved)
+    }
+
+    /// 两层分别搜索后合并: hot 层分数加 hot_boost，cold 层取 cold_k 个 (默认同 k)
+    /// 同一 ID 两层都命中时只保留 hot 层结果，最后按调整后的分数取前 k 个
+    #[pyo3(signature = (query, k, hot_boost=0.0, cold_k=None))]
+    pub fn search(&self, py: Python<'_>, query: Vec<u8>, k: u32, hot_boost: f64, cold_k: Option<u32>) -> PyResult<Vec<SearchResult>> {
+        let mut hot_results = self.hot.borrow(py).search(py, query.clone(), k, false, None, "best_first", None, false)?;
+        let cold_results = self.cold.borrow(py).search(py, query, cold_k.unwrap_or(k), false, None, "best_first", None, false)?;
+
+        for r in hot_results.iter_mut() {
+            r.score += hot_boost;
+        }
+        let hot_ids: HashSet<(u8, u32)> = hot_results.iter().map(|r| (r.namespace, r.id)).collect();
+
+        let mut merged: Vec<SearchResult> = hot_results
+            .into_iter()
+            .chain(cold_results.into_iter().filter(|r| !hot_ids.contains(&(r.namespace, r.id))))
+            .collect();
+        merged.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.id.cmp(&b.id)));
+        merged.truncate(k as usize);
+        Ok(merged)
+    }
+}
+
+/// 预处理过的 tag 集合: 一次性保存对齐的 f32 副本、各 tag 模长与 Gram-Schmidt 正交基，
+/// 逐条消息的循环里反复做握手 / 投影 / 最相似 tag 时不再重复拷贝和归一化 tag 矩阵
+/// 结果与 VexusIndex 上对应的 compute_handshakes / compute_orthogonal_projection / best_tag 一致
+/// skip_zero_vectors=true 时构造时去掉全零 tag (下标见 skipped_rows)，strict=true 时遇到全零 tag 直接报错
+#[pyclass]
+pub struct TagSet {
+    dim: usize,
+    /// n_tags × dim，行优先 (不含被跳过的全零 tag)
+    tags: Vec<f32>,
+    norms: Vec<f64>,
+    /// (tag 下标, 单位基向量)
+    basis: Vec<(usize, Vec<f64>)>,
+    /// tags 中每一行在构造参数里的原下标
+    original_rows: Vec<u32>,
+    skipped_rows: Vec<u32>,
+}
+
+#[pymethods]
+impl TagSet {
+    #[new]
+    #[pyo3(signature = (dim, flattened_tags, n_tags, skip_zero_vectors=false, strict=false))]
+    pub fn new(dim: u32, flattened_tags: Vec<u8>, n_tags: u32, skip_zero_vectors: bool, strict: bool) -> PyResult<Self> {
+        let dim = dim as usize;
+        if dim == 0 {
+            return Err(PyErr::new::<InvalidArgumentError, _>("TagSet dim must be at least 1".to_string()));
+        }
+        let tags = &*f32_view(&flattened_tags, "flattened_tags", Some(n_tags as usize * dim))?;
+        let (tags, skipped_rows) = drop_zero_rows(tags, dim, "flattened_tags", skip_zero_vectors, strict)?;
+        let tags = tags.into_owned();
+        let original_rows = (0..n_tags).filter(|i| skipped_rows.binary_search(i).is_err()).collect();
+        let norms = tags.chunks_exact(dim).map(tag_norm).collect();
+        let basis = gram_schmidt(&tags, dim);
+        Ok(Self { dim, tags, norms, basis, original_rows, skipped_rows })
+    }
+
+    /// 实际使用的 tag 数 (不含被跳过的全零 tag)
+    #[getter]
+    pub fn n_tags(&self) -> u32 {
+        (self.tags.len() / self.dim) as u32
+    }
+
+    /// skip_zero_vectors=true 时被去掉的全零 tag 的原下标
+    #[getter]
+    pub fn skipped_rows(&self) -> Vec<u32> {
+        self.skipped_rows.clone()
+    }
+
+    #[getter]
+    pub fn dim(&self) -> u32 {
+        self.dim as u32
+    }
+
+    /// 同 VexusIndex.compute_handshakes；strict=true 时全零 query 直接报错
+    #[pyo3(signature = (query, extended=false, strict=false))]
+    pub fn handshakes(&self, query: Vec<u8>, extended: bool, strict: bool) -> PyResult<HandshakeResult> {
+        let q = &*f32_view(&query, "query", Some(self.dim))?;
+        check_nonzero_vector(q, "query", strict)?;
+        let mut result = handshakes(q, &self.tags, self.dim, extended, Some(&self.norms));
+        result.skipped_rows = self.skipped_rows.clone();
+        Ok(result)
+    }
+
+    /// 同 VexusIndex.compute_orthogonal_projection (assume_orthonormal=false)，正交基已缓存；strict=true 时全零 query 直接报错
+    #[pyo3(signature = (query, strict=false))]
+    pub fn project(&self, query: Vec<u8>, strict: bool) -> PyResult<OrthogonalProjectionResult> {
+        let q = &*f32_view(&query, "query", Some(self.dim))?;
+        check_nonzero_vector(q, "query", strict)?;
+        let mut result = project_onto_basis(q, &self.basis, self.tags.len() / self.dim);
+        result.skipped_rows = self.skipped_rows.clone();
+        Ok(result)
+    }
+
+    /// 余弦相似度最高的 tag: (原下标, 相似度)，并列取下标小的；同 VexusIndex.best_tag 的单个 query
+    /// strict=true 时全零 query 直接报错
+    #[pyo3(signature = (query, strict=false))]
+    pub fn best(&self, query: Vec<u8>, strict: bool) -> PyResult<(u32, f64)> {
+        if self.tags.is_empty() {
+            return Err(PyErr::new::<InvalidArgumentError, _>("TagSet is empty".to_string()));
+        }
+        let q = &*f32_view(&query, "query", Some(self.dim))?;
+        check_nonzero_vector(q, "query", strict)?;
+        let qn = dot(q, q).sqrt();
+        let mut best = (0usize, f64::NEG_INFINITY);
+        for (i, (t, &tn)) in self.tags.chunks_exact(self.dim).zip(self.norms.iter()).enumerate() {
+            let sim = if qn > 0.0 && tn > 0.0 { dot(q, t) / (qn * tn) } else { 0.0 };
+            if sim > best.1 {
+                best = (i, sim);
+            }
+        }
+        Ok((self.original_rows[best.0], best.1))
+    }
+}
+
+/// 单个文件的恢复计数
+#[derive(Default)]
+struct RecoverCounts {
+    inserted: u32,
+    skipped_dim_mismatch: u32,
+    skipped_row_errors: u32,
+    excluded: u32,
+    growth_events: Vec<GrowthEvent>,
+}
+
+/// 保证容量至少为 needed (留 1.5 倍余量)，发生扩容时返回扩容事件
+fn ensure_capacity(index: &Index, needed: usize) -> Option<GrowthEvent> {
+    let old_capacity = index.capacity();
+    if needed < old_capacity {
+        return None;
+    }
+
+    let new_capacity = (needed as f64 * 1.5) as usize;
+    let start = std::time::Instant::now();
+    index.reserve(new_capacity).ok()?;
+
+    Some(GrowthEvent {
+        old_capacity: old_capacity as u64,
+        new_capacity: index.capacity() as u64,
+        elapsed_ms: start.elapsed().as_secs_f64() * 1000.0,
+    })
+}
+
+/// 扩展内的调试/警告信息经 Python logging 的这个日志器输出，级别与 handler 由宿主程序配置
+const LOGGER_NAME: &str = "vector_db";
+const LOG_DEBUG: u8 = 10;
+const LOG_WARNING: u8 = 30;
+
+/// 经 Python logging 输出一条日志，该级别未开启时不构造消息；日志本身出错不影响调用方
+/// 调用时不能持有索引 / meta 锁: handler 在持有 GIL 时运行，可能回调本扩展
+fn log_to_python(py: Python<'_>, level: u8, message: impl FnOnce() -> String) {
+    let logged = (|| -> PyResult<()> {
+        let logger = py.import_bound("logging")?.call_method1("getLogger", (LOGGER_NAME,))?;
+        if logger.call_method1("isEnabledFor", (level,))?.is_truthy()? {
+            logger.call_method1("log", (level, message()))?;
+        }
+        Ok(())
+    })();
+    if let Err(e) = logged {
+        e.write_unraisable_bound(py, None);
+    }
+}
+
+/// 以 debug 级别记录扩容事件
+fn log_growth_events(py: Python<'_>, method: &str, events: &[GrowthEvent]) {
+    for event in events {
+        log_to_python(py, LOG_DEBUG, || {
+            format!(
+                "{}: capacity grew {} -> {} in {:.2} ms",
+                method, event.old_capacity, event.new_capacity, event.elapsed_ms
+            )
+        });
+    }
+}
+
+/// 恢复时的 ID 允许/排除列表
+struct RecoverIdFilter {
+    include: Option<Vec<i64>>,
+    exclude: Option<Vec<i64>>,
+}
+
+/// ID 集合超过该数量时写入 SQLite 临时表，由查询判定成员关系；否则在行循环中用 HashSet 过滤
+const RECOVER_FILTER_SQL_CUTOFF: usize = 1000;
+
+/// 准备一个 ID 过滤条件，返回 (SELECT 中的成员判定表达式, 行循环用的集合)
+/// 无过滤或小集合时表达式为 NULL
+fn prepare_id_filter(
+    conn: &Connection,
+    table: &str,
+    ids: Option<&[i64]>,
+    id_col: &str,
+) -> rusqlite::Result<(String, Option<HashSet<i64>>)> {
+    let Some(ids) = ids else {
+        return Ok(("NULL".to_string(), None));
+    };
+
+    if ids.len() <= RECOVER_FILTER_SQL_CUTOFF {
+        return Ok(("NULL".to_string(), Some(ids.iter().copied().collect())));
+    }
+
+    conn.execute_batch(&format!(
+        "DROP TABLE IF EXISTS temp.{table}; CREATE TEMP TABLE {table} (id INTEGER PRIMARY KEY);"
+    ))?;
+
+    let tx = conn.unchecked_transaction()?;
+    {
+        let mut insert = tx.prepare(&format!("INSERT OR IGNORE INTO temp.{table} (id) VALUES (?1)"))?;
+        for id in ids {
+            insert.execute([id])?;
+        }
+    }
+    tx.commit()?;
+
+    Ok((format!("{id_col} IN (SELECT id FROM temp.{table})"), None))
+}
+
+/// 从索引中取回指定 ID 的向量，不存在时返回 None
+fn get_vector(index: &Index, id: u64, dim: usize) -> Option<Vec<f32>> {
+    let mut buffer = vec![0.0f32; dim];
+    match index.get(id, &mut buffer) {
+        Ok(found) if found > 0 => Some(buffer),
+        _ => None,
+    }
+}
+
+/// 对一组向量计算 CorpusHealth 的各项指标
+fn corpus_health(vectors: &[Vec<f32>], dim: usize) -> CorpusHealth {
+    let n = vectors.len();
+    if n == 0 {
+        return CorpusHealth {
+            sample_size: 0,
+            mean_norm: 0.0,
+            norm_variance: 0.0,
+            near_zero_fraction: 0.0,
+            mean_pairwise_cosine: 0.0,
+            effective_dim: 0.0,
+        };
+    }
+
+    let norms: Vec<f64> = vectors
+        .par_iter()
+        .map(|v| v.iter().map(|&x| (x as f64) * (x as f64)).sum::<f64>().sqrt())
+        .collect();
+    let mean_norm = norms.iter().sum::<f64>() / n as f64;
+    let norm_variance = norms.iter().map(|x| (x - mean_norm).powi(2)).sum::<f64>() / n as f64;
+    let near_zero_fraction = norms.iter().filter(|&&x| x < 1e-6).count() as f64 / n as f64;
+
+    let (cos_sum, pairs) = (0..n)
+        .into_par_iter()
+        .map(|i| {
+            let row: f64 = (i + 1..n).map(|j| cosine(&vectors[i], &vectors[j])).sum();
+            (row, (n - i - 1) as u64)
+        })
+        .reduce(|| (0.0, 0), |a, b| (a.0 + b.0, a.1 + b.1));
+    let mean_pairwise_cosine = if pairs > 0 { cos_sum / pairs as f64 } else { 0.0 };
+
+    // 参与率 = tr(C)² / tr(C²)，tr(C²) 为协方差矩阵元素平方和；
+    // 样本数小于 dim 时改用 n×n 的 Gram 矩阵 XXᵀ/n，它与 C = XᵀX/n 的非零特征值相同，迹与元素平方和也相同
+    let mut mean = vec![0.0f64; dim];
+    for v in vectors {
+        for (m, &x) in mean.iter_mut().zip(v.iter()) {
+            *m += x as f64;
+        }
+    }
+    mean.iter_mut().for_each(|m| *m /= n as f64);
+    let centered: Vec<Vec<f64>> = vectors
+        .iter()
+        .map(|v| v.iter().zip(mean.iter()).map(|(&x, &m)| x as f64 - m).collect())
+        .collect();
+    let second_moment: Vec<Vec<f64>> = if n < dim {
+        (0..n)
+            .into_par_iter()
+            .map(|i| (0..n).map(|j| centered[i].iter().zip(centered[j].iter()).map(|(x, y)| x * y).sum::<f64>() / n as f64).collect())
+            .collect()
+    } else {
+        (0..dim)
+            .into_par_iter()
+            .map(|a| {
+                (0..dim)
+                    .map(|b| centered.iter().map(|c| c[a] * c[b]).sum::<f64>() / n as f64)
+                    .collect()
+            })
+            .collect()
+    };
+    let trace: f64 = second_moment.iter().enumerate().map(|(a, row)| row[a]).sum();
+    let frobenius_sq: f64 = second_moment.iter().flatten().map(|x| x * x).sum();
+    let effective_dim = if frobenius_sq > 0.0 { trace * trace / frobenius_sq } else { 0.0 };
+
+    CorpusHealth {
+        sample_size: n as u32,
+        mean_norm,
+        norm_variance,
+        near_zero_fraction,
+        mean_pairwise_cosine,
+        effective_dim,
+    }
+}
+
+/// 按能否取回向量把 ID 分为 (存活, 死)，均升序
+fn partition_live(index: &Index, keys: &HashSet<u64>, dim: usize) -> (Vec<u64>, Vec<u64>) {
+    let mut ids: Vec<u64> = keys.iter().copied().collect();
+    ids.sort_unstable();
+    ids.into_iter().partition(|&key| get_vector(index, key, dim).is_some())
+}
+
+/// 非负的相关度 1 / (1 + distance)，取值 (0, 1]，供需要与权重相乘或混合的打分使用
+fn relevance(dist: f32) -> f64 {
+    1.0 / (1.0 + dist.max(0.0) as f64)
+}
+
+/// 平方欧氏距离 (与索引的 L2sq 度量一致)
+fn l2sq(a: &[f32], b: &[f32]) -> f64 {
+    a.iter()
+        .zip(b.iter())
+        .map(|(&x, &y)| {
+            let d = (x - y) as f64;
+            d * d
+        })
+        .sum()
+}
+
+/// 在升序距离序列上找截断点，返回 (保留个数, 相邻距离差, 各截断位置的判据值)
+/// 判据见 AdaptiveCutoff；不足 3 个或距离全相同时保留全部
+fn adaptive_cutoff(distances: &[f64], method: &str) -> (usize, Vec<f64>, Vec<f64>) {
+    let n = distances.len();
+    let gaps: Vec<f64> = distances.windows(2).map(|w| w[1] - w[0]).collect();
+    let span = match (distances.first(), distances.last()) {
+        (Some(first), Some(last)) => last - first,
+        _ => 0.0,
+    };
+    if n < 3 || span <= 0.0 {
+        return (n, gaps, Vec::new());
+    }
+
+    let criterion: Vec<f64> = match method {
+        "elbow" => gaps.iter().map(|gap| gap / span).collect(),
+        _ => distances[..n - 1]
+            .iter()
+            .enumerate()
+            .map(|(i, d)| i as f64 / (n - 1) as f64 - (d - distances[0]) / span)
+            .collect(),
+    };
+    // 并列时取最靠前的截断位置
+    let best = criterion
+        .iter()
+        .enumerate()
+        .fold(0, |best, (i, &c)| if c > criterion[best] { i } else { best });
+    (best + 1, gaps, criterion)
+}
+
+/// Minkowski (Lp) 距离 (p = 1 曼哈顿, p = 2 欧氏)
+fn minkowski(a: &[f32], b: &[f32], p: f64) -> f64 {
+    a.iter()
+        .zip(b.iter())
+        .map(|(&x, &y)| ((x - y) as f64).abs().powf(p))
+        .sum::<f64>()
+        .powf(1.0 / p)
+}
 
-            let mut mag_sq = 0.0;
-            for d in 0..dim {
-                mag_sq += v[d] * v[d];
+/// parse_usearch_partial 认识的 usearch 文件主版本
+const USEARCH_PARTIAL_MAJOR_VERSION: u16 = 2;
+/// usearch scalar_kind_t 中用到的取值
+const USEARCH_SCALAR_F32: u8 = 11;
+const USEARCH_SCALAR_U64: u8 = 14;
+const USEARCH_SCALAR_U32: u8 = 15;
+
+/// 按 usearch v2 dense 索引的序列化布局尽量读出 (key, 向量)，返回 (条目, 文件声明的总数)
+/// 布局: [u32 rows][u32 cols][rows × cols 字节向量][64 字节 dense 头]
+///       [图头: size, connectivity, connectivity_base, max_level, entry_slot 各 u64]
+///       [每个节点的 level: i16 × size]
+///       [节点: key u64 + level i16 + 第 0 层邻居表 + 每高一层一个邻居表]
+/// 邻居表为 u32 计数 + 定长 u32 槽位；任何越界或不一致处即停止，已删除槽位 (key 全 1) 跳过
+/// dense 头 (magic "usearch" + u16 主/次/补丁版本 + 标量/key/槽位类型) 不是 v2、f32 向量、u64 key、u32 槽位时
+/// 布局不可信，返回 Err 而不是按错误的布局读出垃圾
+fn parse_usearch_partial(bytes: &[u8], dim: usize) -> Result<(PartialEntries, usize), String> {
+    let read_u32 = |at: usize| bytes.get(at..at + 4).map(|b| u32::from_le_bytes(b.try_into().unwrap()) as usize);
+    let read_u64 = |at: usize| bytes.get(at..at + 8).map(|b| u64::from_le_bytes(b.try_into().unwrap()));
+    let read_i16 = |at: usize| bytes.get(at..at + 2).map(|b| i16::from_le_bytes(b.try_into().unwrap()));
+
+    let (Some(rows), Some(cols)) = (read_u32(0), read_u32(4)) else {
+        return Ok((Vec::new(), 0));
+    };
+    if cols != dim * 4 {
+        return Ok((Vec::new(), rows));
+    }
+
+    let vectors_at = 8;
+    let head_at = vectors_at + rows * cols;
+    let Some(head) = bytes.get(head_at..head_at + 64) else {
+        return Ok((Vec::new(), rows));
+    };
+    if &head[..7] != b"usearch" {
+        return Err("missing usearch header magic".to_string());
+    }
+    let version: Vec<u16> = head[7..13].chunks_exact(2).map(|b| u16::from_le_bytes([b[0], b[1]])).collect();
+    if version[0] != USEARCH_PARTIAL_MAJOR_VERSION {
+        return Err(format!(
+            "usearch format v{}.{}.{} is not supported (expected v{}.x)",
+            version[0], version[1], version[2], USEARCH_PARTIAL_MAJOR_VERSION
+        ));
+    }
+    // kind_scalar / kind_key / kind_compressed_slot 紧跟 kind_metric 之后
+    let (scalar, key, slot) = (head[14], head[15], head[16]);
+    if (scalar, key, slot) != (USEARCH_SCALAR_F32, USEARCH_SCALAR_U64, USEARCH_SCALAR_U32) {
+        return Err(format!(
+            "unsupported usearch layout (scalar kind {}, key kind {}, slot kind {})",
+            scalar, key, slot
+        ));
+    }
+
+    let graph_at = head_at + 64;
+    let header: Option<Vec<u64>> = (0..5).map(|i| read_u64(graph_at + i * 8)).collect();
+    let Some(header) = header else {
+        return Ok((Vec::new(), rows));
+    };
+    let (size, connectivity, connectivity_base) = (header[0] as usize, header[1] as usize, header[2] as usize);
+    if size > rows {
+        return Ok((Vec::new(), rows));
+    }
+
+    let levels_at = graph_at + 40;
+    let mut node_at = levels_at + size * 2;
+    let base_list_bytes = 4 + connectivity_base * 4;
+    let list_bytes = 4 + connectivity * 4;
+
+    let mut entries = Vec::new();
+    for slot in 0..size {
+        let (Some(key), Some(level)) = (read_u64(node_at), read_i16(node_at + 8)) else {
+            break;
+        };
+        if level < 0 || read_i16(levels_at + slot * 2) != Some(level) {
+            break;
+        }
+        let node_bytes = 10 + base_list_bytes + level as usize * list_bytes;
+        if node_at + node_bytes > bytes.len() {
+            break;
+        }
+        node_at += node_bytes;
+
+        if key == u64::MAX {
+            continue;
+        }
+        let start = vectors_at + slot * cols;
+        let vector: Vec<f32> = bytes[start..start + cols]
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+        if vector.iter().all(|x| x.is_finite()) {
+            entries.push((key, vector));
+        }
+    }
+
+    Ok((entries, rows))
+}
+
+/// 正交基校验容差 (两两内积与模长偏差)
+const ORTHONORMAL_TOLERANCE: f64 = 1e-4;
+
+/// 检查扁平化的基向量是否两两正交且为单位长度
+fn verify_orthonormal(basis: &[f32], dim: usize) -> PyResult<()> {
+    let rows: Vec<&[f32]> = basis.chunks_exact(dim).collect();
+    for (i, a) in rows.iter().enumerate() {
+        for (j, b) in rows.iter().enumerate().skip(i) {
+            let dot: f64 = a.iter().zip(b.iter()).map(|(&x, &y)| x as f64 * y as f64).sum();
+            let expected = if i == j { 1.0 } else { 0.0 };
+            if (dot - expected).abs() > ORTHONORMAL_TOLERANCE {
+                return Err(PyErr::new::<InvalidArgumentError, _>(format!(
+                    "Basis is not orthonormal: <b{}, b{}> = {:.6}, expected {}",
+                    i, j, dot, expected
+                )));
             }
-            let mag = mag_sq.sqrt();
+        }
+    }
+    Ok(())
+}
 
-            if mag > 1e-6 {
-                for d in 0..dim {
-                    v[d] /= mag;
-                }
+/// 对已正交归一的基直接投影, 系数约定与 Gram-Schmidt 路径一致 (取绝对值)
+fn project_onto_orthonormal(query: &[f32], basis: &[f32], dim: usize) -> OrthogonalProjectionResult {
+    let mut projection = vec![0.0; dim];
+    let basis_coefficients = basis
+        .chunks_exact(dim)
+        .map(|u| {
+            let coeff: f64 = query.iter().zip(u.iter()).map(|(&q, &x)| q as f64 * x as f64).sum();
+            for (p, &x) in projection.iter_mut().zip(u.iter()) {
+                *p += coeff * x as f64;
+            }
+            coeff.abs()
+        })
+        .collect();
+    let residual = query.iter().zip(projection.iter()).map(|(&q, &p)| q as f64 - p).collect();
 
-                let mut coeff = 0.0;
-                for d in 0..dim {
-                    coeff += (query[d] as f64) * v[d];
-                }
-                basis_coefficients[i] = coeff.abs();
+    OrthogonalProjectionResult {
+        projection,
+        residual,
+        basis_coefficients,
+        skipped_rows: Vec::new(),
+    }
+}
 
-                for d in 0..dim {
-                    projection[d] += coeff * v[d];
-                }
-                basis.push(v);
+/// Frequent Directions 的收缩步: 对满的 ell 行 sketch 做 SVD，所有 σ_i² 减去 δ = σ_k² (第 k+1 大)，
+/// 前 k 行写回 sqrt(σ_i² - δ)·v_iᵀ、其余行清零，返回 δ
+fn fd_shrink(sketch: &mut [f64], ell: usize, dim: usize, k: usize) -> f64 {
+    let svd = nalgebra::DMatrix::from_row_slice(ell, dim, sketch).svd(false, true);
+    let s = svd.singular_values.as_slice();
+    let delta = s.get(k).map_or(0.0, |&x| x * x);
+    sketch.iter_mut().for_each(|x| *x = 0.0);
+    if let Some(v_t) = svd.v_t {
+        for (i, &sigma) in s.iter().take(k).enumerate() {
+            let scale = (sigma * sigma - delta).max(0.0).sqrt();
+            for (dst, &v) in sketch[i * dim..(i + 1) * dim].iter_mut().zip(v_t.row(i).iter()) {
+                *dst = scale * v;
             }
         }
+    }
+    delta
+}
 
-        let mut residual = vec![0.0; dim];
-        for d in 0..dim {
-            residual[d] = (query[d] as f64) - projection[d];
+/// Gram-Schmidt 正交化: 返回 (产生该基向量的 tag 下标, 单位向量)，与已有基线性相关 (残差模长 <= 1e-6) 的 tag 跳过
+fn gram_schmidt(tags: &[f32], dim: usize) -> Vec<(usize, Vec<f64>)> {
+    let mut basis: Vec<(usize, Vec<f64>)> = Vec::new();
+    for (i, tag) in tags.chunks_exact(dim.max(1)).enumerate() {
+        let mut v: Vec<f64> = tag.iter().map(|&x| x as f64).collect();
+        for (_, u) in &basis {
+            let d: f64 = v.iter().zip(u.iter()).map(|(&a, &b)| a * b).sum();
+            for (a, &b) in v.iter_mut().zip(u.iter()) {
+                *a -= d * b;
+            }
+        }
+        let mag = v.iter().map(|&a| a * a).sum::<f64>().sqrt();
+        if mag > 1e-6 {
+            v.iter_mut().for_each(|a| *a /= mag);
+            basis.push((i, v));
         }
+    }
+    basis
+}
 
-        Ok(OrthogonalProjectionResult {
-            projection,
-            residual,
-            basis_coefficients,
-        })
+/// 把 query 投影到 gram_schmidt 得到的正交基上；basis_coefficients 按 tag 下标 (共 n_tags 个)，被跳过的 tag 为 0
+fn project_onto_basis(query: &[f32], basis: &[(usize, Vec<f64>)], n_tags: usize) -> OrthogonalProjectionResult {
+    let mut projection = vec![0.0; query.len()];
+    let mut basis_coefficients = vec![0.0; n_tags];
+    for (i, u) in basis {
+        let coeff: f64 = query.iter().zip(u.iter()).map(|(&q, &x)| q as f64 * x).sum();
+        basis_coefficients[*i] = coeff.abs();
+        for (p, &x) in projection.iter_mut().zip(u.iter()) {
+            *p += coeff * x;
+        }
     }
+    let residual = query.iter().zip(projection.iter()).map(|(&q, &p)| q as f64 - p).collect();
 
-    /// 高性能握手分析
-    pub fn compute_handshakes(&self, query: Vec<u8>, flattened_tags: Vec<u8>, n_tags: u32) -> PyResult<HandshakeResult> {
-        let dim = self.dimensions as usize;
-        let n = n_tags as usize;
+    OrthogonalProjectionResult {
+        projection,
+        residual,
+        basis_coefficients,
+        skipped_rows: Vec::new(),
+    }
+}
+
+/// 扁平矩阵中全零行的下标
+fn zero_rows(rows: &[f32], dim: usize) -> Vec<u32> {
+    rows.chunks_exact(dim.max(1))
+        .enumerate()
+        .filter(|(_, row)| row.iter().all(|&x| x == 0.0))
+        .map(|(i, _)| i as u32)
+        .collect()
+}
+
+/// 处理一批行中的全零行 (如嵌入调用失败时存下的零向量): strict 时报 InvalidArgumentError；
+/// skip 时返回去掉这些行的副本与被去掉的下标；否则原样返回、不报告
+fn drop_zero_rows<'a>(rows: &'a [f32], dim: usize, name: &str, skip: bool, strict: bool) -> PyResult<(Cow<'a, [f32]>, Vec<u32>)> {
+    if !skip && !strict {
+        return Ok((Cow::Borrowed(rows), Vec::new()));
+    }
+    let zeros = zero_rows(rows, dim);
+    if zeros.is_empty() {
+        return Ok((Cow::Borrowed(rows), Vec::new()));
+    }
+    if strict {
+        return Err(PyErr::new::<InvalidArgumentError, _>(format!("{} has all-zero rows at {:?}", name, zeros)));
+    }
+    let kept = rows
+        .chunks_exact(dim.max(1))
+        .enumerate()
+        .filter(|(i, _)| zeros.binary_search(&(*i as u32)).is_err())
+        .flat_map(|(_, row)| row.iter().copied())
+        .collect();
+    Ok((Cow::Owned(kept), zeros))
+}
+
+/// strict 时拒绝全零的单个向量
+fn check_nonzero_vector(vector: &[f32], name: &str, strict: bool) -> PyResult<()> {
+    if strict && vector.iter().all(|&x| x == 0.0) {
+        return Err(PyErr::new::<InvalidArgumentError, _>(format!("{} is an all-zero vector", name)));
+    }
+    Ok(())
+}
+
+/// tag 的 L2 模长
+fn tag_norm(tag: &[f32]) -> f64 {
+    tag.iter().map(|&x| (x as f64) * (x as f64)).sum::<f64>().sqrt()
+}
+
+/// 握手分析: query 到每个 tag 的差向量的模长与单位方向
+/// tag_norms 为预先算好的各 tag 模长 (TagSet 缓存)，None 时现算；只有 extended=true 时才用到
+fn handshakes(q: &[f32], tags: &[f32], dim: usize, extended: bool, tag_norms: Option<&[f64]>) -> HandshakeResult {
+    let n = tags.len() / dim.max(1);
+    let mut magnitudes = Vec::with_capacity(n);
+    let mut directions = Vec::with_capacity(n * dim);
+
+    for tag in tags.chunks_exact(dim.max(1)) {
+        let delta: Vec<f64> = q.iter().zip(tag.iter()).map(|(&a, &b)| (a - b) as f64).collect();
+        let mag = delta.iter().map(|&d| d * d).sum::<f64>().sqrt();
+        magnitudes.push(mag);
+        if mag > 1e-9 {
+            directions.extend(delta.iter().map(|&d| d / mag));
+        } else {
+            directions.extend(std::iter::repeat_n(0.0, dim));
+        }
+    }
 
-        let q: &[f32] = unsafe {
-            std::slice::from_raw_parts(query.as_ptr() as *const f32, query.len() / 4)
+    let (relative_magnitudes, query_norm, tag_norms) = if extended {
+        let tag_norms: Vec<f64> = match tag_norms {
+            Some(norms) => norms.to_vec(),
+            None => tags.chunks_exact(dim.max(1)).map(tag_norm).collect(),
         };
-        let tags: &[f32] = unsafe {
-            std::slice::from_raw_parts(flattened_tags.as_ptr() as *const f32, flattened_tags.len() / 4)
+        let relative = magnitudes
+            .iter()
+            .zip(tag_norms.iter())
+            .map(|(&m, &t)| if t > 0.0 { m / t } else { f64::INFINITY })
+            .collect();
+        (Some(relative), Some(tag_norm(q)), Some(tag_norms))
+    } else {
+        (None, None, None)
+    };
+
+    HandshakeResult {
+        magnitudes,
+        directions,
+        relative_magnitudes,
+        query_norm,
+        tag_norms,
+        skipped_rows: Vec::new(),
+    }
+}
+
+/// 传入 f32 字节的字节序 (见模块文档)
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ByteOrder {
+    Le,
+    Be,
+}
+
+impl ByteOrder {
+    fn parse(order: &str) -> PyResult<Self> {
+        match order {
+            "le" => Ok(Self::Le),
+            "be" => Ok(Self::Be),
+            other => Err(PyErr::new::<InvalidArgumentError, _>(format!(
+                "Invalid byte_order '{}': expected 'le' or 'be'",
+                other
+            ))),
+        }
+    }
+
+    /// 按该字节序解释 f32 字节 (长度校验同 f32_view)；与本机字节序一致时尽量零拷贝，否则逐个交换字节
+    fn decode<'a>(self, bytes: &'a [u8], name: &str, expected: Option<usize>) -> PyResult<Cow<'a, [f32]>> {
+        let values = f32_view(bytes, name, expected)?;
+        if (self == Self::Le) == cfg!(target_endian = "little") {
+            return Ok(values);
+        }
+        Ok(Cow::Owned(values.iter().map(|x| f32::from_bits(x.to_bits().swap_bytes())).collect()))
+    }
+}
+
+/// 搜索结果排序方式
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ResultOrder {
+    BestFirst,
+    WorstFirst,
+}
+
+impl ResultOrder {
+    fn parse(order: &str) -> PyResult<Self> {
+        match order {
+            "best_first" => Ok(Self::BestFirst),
+            "worst_first" => Ok(Self::WorstFirst),
+            other => Err(PyErr::new::<InvalidArgumentError, _>(format!(
+                "Invalid order '{}': expected 'best_first' or 'worst_first'",
+                other
+            ))),
+        }
+    }
+
+    /// 输入为 best_first 顺序，worst_first 时原地倒序
+    fn apply<T>(self, results: &mut [T]) {
+        if self == Self::WorstFirst {
+            results.reverse();
+        }
+    }
+}
+
+/// 距离度量，约定与 usearch 一致: score = 1 - distance
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum DistanceMetric {
+    /// 平方欧氏距离 (索引使用的度量)
+    L2sq,
+    /// 1 - 余弦相似度
+    Cos,
+    /// 1 - 内积
+    Ip,
+}
+
+impl DistanceMetric {
+    fn parse(metric: &str) -> PyResult<Self> {
+        match metric {
+            "l2sq" => Ok(Self::L2sq),
+            "cos" => Ok(Self::Cos),
+            "ip" => Ok(Self::Ip),
+            other => Err(PyErr::new::<InvalidArgumentError, _>(format!(
+                "Invalid metric '{}': expected 'l2sq', 'cos' or 'ip'",
+                other
+            ))),
+        }
+    }
+
+    fn distance(self, a: &[f32], b: &[f32]) -> f64 {
+        match self {
+            Self::L2sq => l2sq(a, b),
+            Self::Cos => 1.0 - cosine(a, b),
+            Self::Ip => 1.0 - dot(a, b),
+        }
+    }
+}
+
+/// 在独立线程中执行搜索，超过 timeout 返回 TimeoutError (工作线程自行结束，结果被丢弃)
+fn search_with_timeout(
+    index: &Arc<RwLock<Index>>,
+    query: Vec<f32>,
+    k: usize,
+    timeout: std::time::Duration,
+) -> PyResult<usearch::ffi::Matches> {
+    let index = Arc::clone(index);
+    let (tx, rx) = std::sync::mpsc::channel();
+
+    std::thread::spawn(move || {
+        let result = match index.read() {
+            Ok(index) => index.search(&query, k).map_err(|e| format!("Search failed: {:?}", e)),
+            Err(e) => Err(format!("Lock failed: {}", e)),
         };
+        let _ = tx.send(result);
+    });
 
-        let mut magnitudes = Vec::with_capacity(n);
-        let mut directions = Vec::with_capacity(n * dim);
+    match rx.recv_timeout(timeout) {
+        Ok(Ok(matches)) => Ok(matches),
+        Ok(Err(msg)) => Err(PyErr::new::<VexusError, _>(msg)),
+        Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => Err(PyErr::new::<VexusError, _>(
+            "Search worker terminated unexpectedly".to_string(),
+        )),
+        Err(std::sync::mpsc::RecvTimeoutError::Timeout) => Err(PyErr::new::<VexusTimeoutError, _>(format!(
+            "Search exceeded timeout of {} ms",
+            timeout.as_millis()
+        ))),
+    }
+}
 
-        for i in 0..n {
-            let start = i * dim;
-            let tag_vec = &tags[start..start + dim];
-            let mut mag_sq = 0.0;
-            let mut delta = vec![0.0; dim];
+/// 计算单个 ID 的逐维度贡献，ID 不存在时抛 KeyError
+fn explain_one(index: &Index, query: &[f32], id: u64, top_dims: usize) -> PyResult<ExplainResult> {
+    let vector = get_vector(index, id, query.len())
+        .ok_or_else(|| PyErr::new::<NotFoundError, _>(format!("ID {} not found in index", id)))?;
 
-            for d in 0..dim {
-                let diff = (q[d] - tag_vec[d]) as f64;
-                delta[d] = diff;
-                mag_sq += diff * diff;
-            }
+    let contributions: Vec<f64> = query
+        .iter()
+        .zip(vector.iter())
+        .map(|(&q, &v)| {
+            let d = (q - v) as f64;
+            -(d * d)
+        })
+        .collect();
+    let total: f64 = contributions.iter().sum();
 
-            let mag = mag_sq.sqrt();
-            magnitudes.push(mag);
+    let mut order: Vec<usize> = (0..contributions.len()).collect();
+    order.sort_by(|&a, &b| contributions[b].abs().total_cmp(&contributions[a].abs()));
+    order.truncate(top_dims);
 
-            if mag > 1e-9 {
-                for d in 0..dim {
-                    directions.push(delta[d] / mag);
+    Ok(ExplainResult {
+        id,
+        dims: order.iter().map(|&d| d as u32).collect(),
+        contributions: order.iter().map(|&d| contributions[d]).collect(),
+        total,
+        score: 1.0 + total,
+    })
+}
+
+/// 并行流式计算一组 ID 对应向量的均值，没有任何可取回的向量时返回 None
+fn compute_centroid(index: &Index, ids: &[u64], dim: usize) -> Option<Vec<f64>> {
+    let (sum, count) = ids
+        .par_iter()
+        .fold(
+            || (vec![0.0f64; dim], 0usize),
+            |(mut sum, count), &id| match get_vector(index, id, dim) {
+                Some(v) => {
+                    for (s, &x) in sum.iter_mut().zip(v.iter()) {
+                        *s += x as f64;
+                    }
+                    (sum, count + 1)
                 }
-            } else {
-                for _ in 0..dim {
-                    directions.push(0.0);
+                None => (sum, count),
+            },
+        )
+        .reduce(
+            || (vec![0.0f64; dim], 0usize),
+            |(mut a, ca), (b, cb)| {
+                for (x, y) in a.iter_mut().zip(b.iter()) {
+                    *x += y;
                 }
-            }
-        }
+                (a, ca + cb)
+            },
+        );
 
-        Ok(HandshakeResult {
-            magnitudes,
-            directions,
-        })
+    if count == 0 {
+        return None;
     }
+    Some(sum.into_iter().map(|x| x / count as f64).collect())
+}
 
-    /// 高性能 EPA 投影
-    pub fn project(
-        &self,
-        vector: Vec<u8>,
-        flattened_basis: Vec<u8>,
-        mean_vector: Vec<u8>,
-        k: u32,
-    ) -> PyResult<ProjectResult> {
-        let dim = self.dimensions as usize;
-        let k = k as usize;
+/// f32 切片 -> 本机字节序字节 (与输入端的指针重解释保持一致)
+fn f32_to_bytes(values: &[f32]) -> Vec<u8> {
+    values.iter().flat_map(|x| x.to_ne_bytes()).collect()
+}
 
-        let vec: &[f32] = unsafe {
-            std::slice::from_raw_parts(vector.as_ptr() as *const f32, vector.len() / 4)
-        };
-        let basis_slice: &[f32] = unsafe {
-            std::slice::from_raw_parts(flattened_basis.as_ptr() as *const f32, flattened_basis.len() / 4)
-        };
-        let mean: &[f32] = unsafe {
-            std::slice::from_raw_parts(mean_vector.as_ptr() as *const f32, mean_vector.len() / 4)
+/// 向量内容哈希: f32 小端字节 (与 SQLite 中的 BLOB 相同) 的 xxh3-64
+/// 固定按小端计算，.meta 中持久化的哈希在不同字节序的机器之间可比
+fn vector_hash(values: &[f32]) -> u64 {
+    if cfg!(target_endian = "little") {
+        // SAFETY: 小端机器上 f32 的内存表示即其小端字节，u8 没有对齐要求
+        let bytes = unsafe { std::slice::from_raw_parts(values.as_ptr() as *const u8, std::mem::size_of_val(values)) };
+        xxhash_rust::xxh3::xxh3_64(bytes)
+    } else {
+        let bytes: Vec<u8> = values.iter().flat_map(|x| x.to_le_bytes()).collect();
+        xxhash_rust::xxh3::xxh3_64(&bytes)
+    }
+}
+
+/// compute_review_priority 的时间单位换算
+const SECONDS_PER_DAY: f64 = 86_400.0;
+/// sm2_interval_days 最多模拟的复习次数
+const SM2_MAX_REVIEWS: u32 = 64;
+
+/// SM-2: 以质量分 quality (0..=5) 连续复习 n_reviews 次后的下一次间隔 (天)
+/// 间隔为 1 天、6 天、之后每次乘易度因子 EF (初始 2.5，每次按 quality 调整，下限 1.3)；
+/// quality < 3 视为没记住，重新从 1 天开始
+fn sm2_interval_days(quality: f64, n_reviews: u32) -> f64 {
+    if quality < 3.0 {
+        return 1.0;
+    }
+    let miss = 5.0 - quality;
+    let delta = 0.1 - miss * (0.08 + miss * 0.02);
+    let mut ease = 2.5f64;
+    let mut interval = 1.0;
+    // EF >= 1.3，复习 SM2_MAX_REVIEWS 次后间隔已远超任何实际时间跨度
+    for n in 1..=n_reviews.min(SM2_MAX_REVIEWS) {
+        ease = (ease + delta).max(1.3);
+        interval = match n {
+            1 => 1.0,
+            2 => 6.0,
+            _ => interval * ease,
         };
+    }
+    interval
+}
 
-        if vec.len() != dim || basis_slice.len() != k * dim || mean.len() != dim {
-            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("Dimension mismatch".to_string()));
+/// 登记的共享视图句柄数减一，归零时移除 (之后允许 save 覆盖该文件)
+fn release_shared_view(path: Option<&Path>) {
+    let Some(path) = path else {
+        return;
+    };
+    if let Ok(mut views) = SHARED_VIEWS.lock()
+        && let Some(count) = views.get_mut(path)
+    {
+        *count -= 1;
+        if *count == 0 {
+            views.remove(path);
         }
+    }
+}
 
-        let mut centered = vec![0.0; dim];
-        for d in 0..dim {
-            centered[d] = (vec[d] - mean[d]) as f64;
-        }
+/// 余弦相似度，任一向量范数为 0 时返回 0
+fn cosine(a: &[f32], b: &[f32]) -> f64 {
+    let mut dot = 0.0;
+    let mut na = 0.0;
+    let mut nb = 0.0;
+    for (&x, &y) in a.iter().zip(b.iter()) {
+        let (x, y) = (x as f64, y as f64);
+        dot += x * y;
+        na += x * x;
+        nb += y * y;
+    }
 
-        let mut projections = vec![0.0; k];
-        let mut total_energy = 0.0;
+    if na > 0.0 && nb > 0.0 { dot / (na.sqrt() * nb.sqrt()) } else { 0.0 }
+}
 
-        for i in 0..k {
-            let start = i * dim;
-            let b = &basis_slice[start..start + dim];
-            let mut dot = 0.0;
-            for d in 0..dim {
-                dot += centered[d] * (b[d] as f64);
-            }
-            projections[i] = dot;
-            total_energy += dot * dot;
+/// 注意力温度必须为正的有限数
+fn check_temperature(temperature: f64) -> PyResult<()> {
+    if temperature.is_finite() && temperature > 0.0 {
+        return Ok(());
+    }
+    Err(PyErr::new::<InvalidArgumentError, _>(format!(
+        "temperature must be a positive finite number, got {}",
+        temperature
+    )))
+}
+
+/// 按 ID 顺序取向量，任一 ID 不存在报 KeyError
+fn fetch_vectors(index: &Index, ids: &[u32], dim: usize) -> PyResult<Vec<Vec<f32>>> {
+    ids.iter()
+        .map(|&id| {
+            get_vector(index, id as u64, dim)
+                .ok_or_else(|| PyErr::new::<NotFoundError, _>(format!("ID {} not found in index", id)))
+        })
+        .collect()
+}
+
+/// softmax(q · k_i / sqrt(dim) / temperature)
+fn attention_weights(q: &[f32], keys: &[Vec<f32>], temperature: f64) -> Vec<f64> {
+    let scale = (q.len() as f64).sqrt() * temperature;
+    let logits: Vec<f64> = keys.iter().map(|k| dot(q, k) / scale).collect();
+    softmax(&logits)
+}
+
+/// 把 v 投影到 neg → pos 轴: 返回 (位置, 垂直分量模长)，位置以两端中点为 0、pos 为 +1、neg 为 -1
+/// 两端点重合 (轴退化) 时返回 None
+fn axis_projection(v: &[f32], pos: &[f32], neg: &[f32]) -> Option<(f64, f64)> {
+    let axis: Vec<f64> = pos.iter().zip(neg.iter()).map(|(&p, &n)| p as f64 - n as f64).collect();
+    let axis_sq: f64 = axis.iter().map(|x| x * x).sum();
+    if axis_sq < 1e-12 {
+        return None;
+    }
+
+    let centered: Vec<f64> = (0..v.len()).map(|d| v[d] as f64 - (pos[d] as f64 + neg[d] as f64) / 2.0).collect();
+    let t = centered.iter().zip(axis.iter()).map(|(c, a)| c * a).sum::<f64>() / axis_sq;
+    let perpendicular = centered
+        .iter()
+        .zip(axis.iter())
+        .map(|(c, a)| (c - t * a).powi(2))
+        .sum::<f64>()
+        .sqrt();
+
+    Some((2.0 * t, perpendicular))
+}
+
+fn degenerate_axis_err(axis: &str, positive_id: u32, negative_id: u32) -> PyErr {
+    PyErr::new::<InvalidArgumentError, _>(format!(
+        "{} axis is degenerate: IDs {} and {} have the same vector",
+        axis, positive_id, negative_id
+    ))
+}
+
+/// 数值稳定的 log(Σ exp(x))
+fn logsumexp(values: impl Iterator<Item = f64> + Clone) -> f64 {
+    let max = values.clone().fold(f64::NEG_INFINITY, f64::max);
+    if max == f64::NEG_INFINITY {
+        return max;
+    }
+    max + values.map(|x| (x - max).exp()).sum::<f64>().ln()
+}
+
+/// 数值稳定的 softmax (先减去最大值)
+fn softmax(logits: &[f64]) -> Vec<f64> {
+    let max = logits.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let exps: Vec<f64> = logits.iter().map(|&x| (x - max).exp()).collect();
+    let sum: f64 = exps.iter().sum();
+    exps.into_iter().map(|e| e / sum).collect()
+}
+
+/// 当前 Unix 时间 (秒)
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// 可复现的伪随机数发生器 (SplitMix64)，用于所有带 seed 的采样
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// [0, 1) 均匀分布
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// 标准正态分布 (Box-Muller)
+    fn next_gaussian(&mut self) -> f64 {
+        let u1 = self.next_f64().max(f64::MIN_POSITIVE);
+        let u2 = self.next_f64();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+
+    /// 标准 Gumbel 分布 (-ln(-ln U))
+    fn next_gumbel(&mut self) -> f64 {
+        let u = self.next_f64().max(f64::MIN_POSITIVE);
+        -(-u.ln()).ln()
+    }
+
+    /// [0, n) 均匀整数
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_f64() * n as f64) as usize % n.max(1)
+    }
+}
+
+/// 从 ID 集合中无放回采样 n 个 (排序后再抽，保证同一 seed 结果稳定)
+fn sample_keys(keys: &HashSet<u64>, n: usize, seed: u64) -> Vec<u64> {
+    let mut ids: Vec<u64> = keys.iter().copied().collect();
+    ids.sort_unstable();
+
+    let n = n.min(ids.len());
+    let mut rng = SplitMix64::new(seed);
+    for i in 0..n {
+        let j = i + rng.below(ids.len() - i);
+        ids.swap(i, j);
+    }
+    ids.truncate(n);
+    ids
+}
+
+/// 校验字节缓冲区并按 f32 数组解释 (所有接收向量字节的入口都经过这里)
+/// 空缓冲区 (除非期望 0 个元素)、字节数不是 4 的倍数、元素个数与 expected 不符时报 ValueError，
+/// 错误信息带参数名以及期望/实际元素个数；校验通过之后，缓冲区按 f32 对齐时直接借用，否则逐元素拷贝
+fn f32_view<'a>(bytes: &'a [u8], name: &str, expected: Option<usize>) -> PyResult<Cow<'a, [f32]>> {
+    let expected_desc = expected.map(|n| format!(", expected {} f32 elements", n)).unwrap_or_default();
+    if bytes.is_empty() && expected != Some(0) {
+        return Err(PyErr::new::<DimensionMismatchError, _>(format!(
+            "{} is empty{}",
+            name, expected_desc
+        )));
+    }
+    if !bytes.len().is_multiple_of(4) {
+        return Err(PyErr::new::<DimensionMismatchError, _>(format!(
+            "{} byte length {} is not a multiple of 4{}",
+            name,
+            bytes.len(),
+            expected_desc
+        )));
+    }
+    let len = bytes.len() / 4;
+    if let Some(n) = expected
+        && len != n
+    {
+        return Err(PyErr::new::<DimensionMismatchError, _>(format!(
+            "{} has {} f32 elements, expected {}",
+            name, len, n
+        )));
+    }
+    // SAFETY: 任意 4 字节都是合法的 f32 位模式，align_to 只在对齐的中段返回 f32
+    match unsafe { bytes.align_to::<f32>() } {
+        ([], floats, []) => Ok(Cow::Borrowed(floats)),
+        _ => Ok(Cow::Owned(
+            bytes.chunks_exact(4).map(|c| f32::from_ne_bytes([c[0], c[1], c[2], c[3]])).collect(),
+        )),
+    }
+}
+
+/// 将字节解释为 IEEE 半精度数组并逐个转为 f32 (转换无损)
+fn f16_lanes(bytes: &[u8], name: &str) -> PyResult<Vec<f32>> {
+    if bytes.is_empty() {
+        return Err(PyErr::new::<DimensionMismatchError, _>(format!("{} is empty", name)));
+    }
+    if !bytes.len().is_multiple_of(2) {
+        return Err(PyErr::new::<DimensionMismatchError, _>(format!(
+            "{} has odd byte length {}, not a f16 buffer",
+            name,
+            bytes.len()
+        )));
+    }
+    Ok(bytes
+        .chunks_exact(2)
+        .map(|b| half::f16::from_ne_bytes([b[0], b[1]]).to_f32())
+        .collect())
+}
+
+/// 解码两个等长的 f16 向量
+fn f16_pair(a: &[u8], b: &[u8]) -> PyResult<(Vec<f32>, Vec<f32>)> {
+    let (a, b) = (f16_lanes(a, "a")?, f16_lanes(b, "b")?);
+    if a.len() != b.len() {
+        return Err(PyErr::new::<DimensionMismatchError, _>(format!(
+            "Length mismatch: {} vs {} f16 lanes",
+            a.len(),
+            b.len()
+        )));
+    }
+    Ok((a, b))
+}
+
+/// 解码 n_rows 行 f16 矩阵与一个 f32 查询向量，行宽取查询维度
+fn f16_rows(matrix: &[u8], n_rows: u32, query: &[u8]) -> PyResult<(Vec<f32>, Vec<f32>)> {
+    let q = &*f32_view(query, "query", None)?;
+    let m = f16_lanes(matrix, "matrix")?;
+    if m.len() != n_rows as usize * q.len() {
+        return Err(PyErr::new::<DimensionMismatchError, _>(format!(
+            "Matrix size mismatch: expected {} rows of {} f16 lanes, got {} lanes",
+            n_rows,
+            q.len(),
+            m.len()
+        )));
+    }
+    Ok((m, q.to_vec()))
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f64 {
+    a.iter().zip(b.iter()).map(|(&x, &y)| x as f64 * y as f64).sum()
+}
+
+/// f16 向量点积 (f64 累加)
+#[pyfunction]
+pub fn dot_f16(a: Vec<u8>, b: Vec<u8>) -> PyResult<f64> {
+    let (a, b) = f16_pair(&a, &b)?;
+    Ok(dot(&a, &b))
+}
+
+/// f16 向量欧氏距离 (非平方)
+#[pyfunction]
+pub fn l2_f16(a: Vec<u8>, b: Vec<u8>) -> PyResult<f64> {
+    let (a, b) = f16_pair(&a, &b)?;
+    Ok(l2sq(&a, &b).sqrt())
+}
+
+/// f16 向量余弦相似度，任一向量模长为 0 时为 0
+#[pyfunction]
+pub fn cosine_f16(a: Vec<u8>, b: Vec<u8>) -> PyResult<f64> {
+    let (a, b) = f16_pair(&a, &b)?;
+    Ok(cosine(&a, &b))
+}
+
+/// f16 矩阵每行与 f32 查询的点积 (用于压缩候选重排)
+#[pyfunction]
+pub fn dot_f16_batch(matrix: Vec<u8>, n_rows: u32, query: Vec<u8>) -> PyResult<Vec<f64>> {
+    let (m, q) = f16_rows(&matrix, n_rows, &query)?;
+    Ok(m.chunks_exact(q.len()).map(|row| dot(row, &q)).collect())
+}
+
+/// f16 矩阵每行与 f32 查询的欧氏距离
+#[pyfunction]
+pub fn l2_f16_batch(matrix: Vec<u8>, n_rows: u32, query: Vec<u8>) -> PyResult<Vec<f64>> {
+    let (m, q) = f16_rows(&matrix, n_rows, &query)?;
+    Ok(m.chunks_exact(q.len()).map(|row| l2sq(row, &q).sqrt()).collect())
+}
+
+/// f16 矩阵每行与 f32 查询的余弦相似度
+#[pyfunction]
+pub fn cosine_f16_batch(matrix: Vec<u8>, n_rows: u32, query: Vec<u8>) -> PyResult<Vec<f64>> {
+    let (m, q) = f16_rows(&matrix, n_rows, &query)?;
+    Ok(m.chunks_exact(q.len()).map(|row| cosine(row, &q)).collect())
+}
+
+/// 合并多个分片的搜索结果为全局 top-k
+/// 按 score 降序，score 相同按 id 升序；要求各分片的 score 语义一致
+#[pyfunction]
+pub fn merge_results(results: Vec<Vec<PyRef<SearchResult>>>, k: u32) -> PyResult<Vec<SearchResult>> {
+    let mut merged: Vec<SearchResult> = results
+        .iter()
+        .flatten()
+        .map(|r| SearchResult { id: r.id, namespace: r.namespace, score: r.score, hop: r.hop, strategy: r.strategy.clone(), distance: r.distance })
+        .collect();
+
+    merged.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.id.cmp(&b.id)));
+    merged.truncate(k as usize);
+    Ok(merged)
+}
+
+/// 在临时候选集上做精确 top-k，不写入任何索引
+/// candidates 为 n 行 f32 向量，结果 id 为候选的行号；score 与索引搜索一致 (1 - distance)
+/// metric: "l2sq" (与索引相同)、"cos" 或 "ip"；计算时释放 GIL 并按候选并行
+#[pyfunction]
+#[pyo3(signature = (query, candidates, n, k, metric="l2sq".to_string()))]
+pub fn rank_candidates(
+    py: Python<'_>,
+    query: Vec<u8>,
+    candidates: Vec<u8>,
+    n: u32,
+    k: u32,
+    metric: String,
+) -> PyResult<Vec<SearchResult>> {
+    let metric = DistanceMetric::parse(&metric)?;
+    let q = &*f32_view(&query, "query", None)?;
+    let dim = q.len();
+    let c = &*f32_view(&candidates, "candidates", Some(n as usize * dim))?;
+
+    let ranked = py.allow_threads(|| {
+        let mut scored: Vec<(u32, f64)> = c
+            .par_chunks(dim)
+            .enumerate()
+            .map(|(row, v)| (row as u32, 1.0 - metric.distance(q, v)))
+            .collect();
+        let by_score = |a: &(u32, f64), b: &(u32, f64)| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0));
+        let k = (k as usize).min(scored.len());
+        if k > 0 && k < scored.len() {
+            scored.select_nth_unstable_by(k - 1, by_score);
         }
+        scored.truncate(k);
+        scored.sort_by(by_score);
+        scored
+    });
 
-        let mut probabilities = vec![0.0; k];
-        let mut entropy = 0.0;
+    Ok(ranked
+        .into_iter()
+        .map(|(id, score)| SearchResult { id, namespace: 0, score, hop: 0, strategy: None, distance: None })
+        .collect())
+}
 
-        if total_energy > 1e-12 {
-            for i in 0..k {
-                let p = (projections[i] * projections[i]) / total_energy;
-                probabilities[i] = p;
-                if p > 1e-9 {
-                    entropy -= p * p.log2();
-                }
+/// 按 seed 把 ID 确定性地划分为 (train, test)，test 约占 test_fraction (四舍五入)
+/// 先排序再抽样，结果与输入顺序无关；两部分都按升序返回
+#[pyfunction]
+pub fn split_ids(ids: Vec<u32>, test_fraction: f64, seed: u64) -> PyResult<(Vec<u32>, Vec<u32>)> {
+    if !(0.0..=1.0).contains(&test_fraction) {
+        return Err(PyErr::new::<InvalidArgumentError, _>(format!(
+            "test_fraction must be in [0, 1], got {}",
+            test_fraction
+        )));
+    }
+
+    let mut ids = ids;
+    ids.sort_unstable();
+    let n_test = (ids.len() as f64 * test_fraction).round() as usize;
+
+    let mut rng = SplitMix64::new(seed);
+    for i in 0..n_test {
+        let j = i + rng.below(ids.len() - i);
+        ids.swap(i, j);
+    }
+    let mut train = ids.split_off(n_test);
+    let mut test = ids;
+    train.sort_unstable();
+    test.sort_unstable();
+    Ok((train, test))
+}
+
+/// 顺序读取索引文件，把页面带入系统页缓存 (适用于 mmap 方式打开的大索引)，不依赖任何已打开的索引
+/// 返回 (读取字节数, 耗时毫秒)
+#[pyfunction]
+pub fn prefetch(py: Python<'_>, index_path: String) -> PyResult<(u64, f64)> {
+    py.allow_threads(|| {
+        use std::io::Read;
+
+        let start = std::time::Instant::now();
+        let mut file = std::fs::File::open(&index_path)
+            .map_err(|e| PyErr::new::<StorageError, _>(format!("Failed to open index file: {}", e)))?;
+
+        let mut buffer = vec![0u8; 1 << 20];
+        let mut total = 0u64;
+        loop {
+            let read = file.read(&mut buffer)
+                .map_err(|e| PyErr::new::<StorageError, _>(format!("Failed to read index file: {}", e)))?;
+            if read == 0 {
+                break;
             }
+            total += read as u64;
         }
 
-        Ok(ProjectResult {
-            projections,
-            probabilities,
-            entropy,
-            total_energy,
-        })
+        Ok((total, start.elapsed().as_secs_f64() * 1000.0))
+    })
+}
+
+/// 把 (命名空间, 表内 ID) 打包成索引 key: (ns << 56) | id
+/// 表内 ID 须在 u32 范围内 (与 SearchResult.id 及各方法的 id 参数一致)，否则报错
+#[pyfunction]
+pub fn pack_id(ns: u8, id: i64) -> PyResult<u64> {
+    namespaced_key(Some(ns), id)
+}
+
+/// pack_id 的逆操作，返回 (命名空间, 表内 ID)
+#[pyfunction]
+pub fn unpack_id(key: u64) -> (u8, u64) {
+    ((key >> NAMESPACE_SHIFT) as u8, key & NAMESPACE_ID_MASK)
+}
+
+/// 未指定命名空间时 key 就是原 ID (保持旧行为)
+fn namespaced_key(ns: Option<u8>, id: i64) -> PyResult<u64> {
+    let Some(ns) = ns else {
+        return Ok(id as u64);
+    };
+    if u32::try_from(id).is_err() {
+        return Err(PyErr::new::<InvalidArgumentError, _>(format!(
+            "ID {} does not fit in u32, cannot pack into namespace {}",
+            id, ns
+        )));
     }
+    Ok(((ns as u64) << NAMESPACE_SHIFT) | id as u64)
+}
+
+/// namespaced_key 的批量版本
+fn namespaced_keys(ns: Option<u8>, ids: &[u32]) -> PyResult<Vec<u64>> {
+    ids.iter().map(|&id| namespaced_key(ns, id as i64)).collect()
 }
 
 /// Python 模块定义
@@ -600,5 +6690,61 @@ fn vector_db(_py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<HandshakeResult>()?;
     m.add_class::<ProjectResult>()?;
     m.add_class::<VexusStats>()?;
+    m.add_class::<CalibrationInfo>()?;
+    m.add_class::<RecoverReport>()?;
+    m.add_class::<VerifyReport>()?;
+    m.add_class::<AdaptiveCutoff>()?;
+    m.add_class::<ExplainResult>()?;
+    m.add_class::<LifetimeStats>()?;
+    m.add_class::<RobustSearchResult>()?;
+    m.add_class::<WarmupReport>()?;
+    m.add_class::<GrowthEvent>()?;
+    m.add_class::<GraphStats>()?;
+    m.add_class::<VersionedIndex>()?;
+    m.add_class::<TieredIndex>()?;
+    m.add_class::<TagSet>()?;
+    m.add_class::<QueryLogEntry>()?;
+    m.add_class::<TrajectoryResult>()?;
+    m.add_class::<NamespaceStats>()?;
+    m.add_class::<DetailedStats>()?;
+    m.add_class::<VexusIterator>()?;
+    m.add_class::<IndexInfo>()?;
+    m.add_class::<CorpusHealth>()?;
+    m.add_class::<IntegrityReport>()?;
+    m.add_class::<RecommendedOptions>()?;
+    #[cfg(any(test, feature = "testing"))]
+    testing::register(m)?;
+    m.add_function(wrap_pyfunction!(dot_f16, m)?)?;
+    m.add_function(wrap_pyfunction!(l2_f16, m)?)?;
+    m.add_function(wrap_pyfunction!(cosine_f16, m)?)?;
+    m.add_function(wrap_pyfunction!(dot_f16_batch, m)?)?;
+    m.add_function(wrap_pyfunction!(l2_f16_batch, m)?)?;
+    m.add_function(wrap_pyfunction!(cosine_f16_batch, m)?)?;
+    m.add_function(wrap_pyfunction!(merge_results, m)?)?;
+    m.add_function(wrap_pyfunction!(rank_candidates, m)?)?;
+    m.add_function(wrap_pyfunction!(split_ids, m)?)?;
+    m.add_function(wrap_pyfunction!(pack_id, m)?)?;
+    m.add_function(wrap_pyfunction!(unpack_id, m)?)?;
+    m.add_function(wrap_pyfunction!(prefetch, m)?)?;
+    m.add("VexusError", m.py().get_type_bound::<VexusError>())?;
+    m.add("RemoveError", m.py().get_type_bound::<RemoveError>())?;
+    m.add("IndexClosedError", m.py().get_type_bound::<IndexClosedError>())?;
+    m.add("SqliteError", m.py().get_type_bound::<SqliteError>())?;
+    m.add("CorruptFileError", m.py().get_type_bound::<CorruptFileError>())?;
+    m.add("StorageError", m.py().get_type_bound::<StorageError>())?;
+    m.add("DimensionMismatchError", m.py().get_type_bound::<DimensionMismatchError>())?;
+    m.add("InvalidArgumentError", m.py().get_type_bound::<InvalidArgumentError>())?;
+    m.add("NotFoundError", m.py().get_type_bound::<NotFoundError>())?;
+    m.add("CapacityError", m.py().get_type_bound::<CapacityError>())?;
+    m.add("VexusTimeoutError", m.py().get_type_bound::<VexusTimeoutError>())?;
+    m.add("MissingFileError", m.py().get_type_bound::<MissingFileError>())?;
+    m.add("CursorExpiredError", m.py().get_type_bound::<CursorExpiredError>())?;
+    m.add("ReadOnlyIndexError", m.py().get_type_bound::<ReadOnlyIndexError>())?;
+    let codes = PyDict::new_bound(m.py());
+    for (code, ty) in error_codes(m.py()) {
+        ty.setattr("code", code)?;
+        codes.set_item(code, ty)?;
+    }
+    m.add("ERROR_CODES", codes)?;
     Ok(())
 }
@@ -0,0 +1,114 @@
+//! 测试夹具生成 (feature = "testing")
+//! Python 测试 (test.py) 通过 vector_db.testing 调用，表结构与确定性随机向量统一由这里生成
+
+use pyo3::prelude::*;
+use rusqlite::{params, Connection};
+
+use crate::{f32_to_bytes, SplitMix64, SqliteError, VexusError, VexusIndex};
+
+/// make_test_db 生成的 chunks 全部挂在这个日记名下
+pub const FIXTURE_DIARY: &str = "fixture";
+
+/// [-1, 1) 均匀分布的确定性向量
+fn fixture_vector(rng: &mut SplitMix64, dim: usize) -> Vec<f32> {
+    (0..dim).map(|_| (rng.next_f64() * 2.0 - 1.0) as f32).collect()
+}
+
+/// 按 recover_from_sqlite 读取的表结构 (与后端 ORM 模型一致) 生成 SQLite 测试库
+/// tags: ID 1..=n_tags; chunks: ID 1..=n_chunks，全部属于日记 FIXTURE_DIARY；同一 seed 生成的向量相同
+#[pyfunction]
+pub fn make_test_db(path: String, n_tags: u32, n_chunks: u32, dim: u32, seed: u64) -> PyResult<()> {
+    let sqlite_err = |e: rusqlite::Error| PyErr::new::<SqliteError, _>(format!("Failed to build test DB: {}", e));
+
+    let mut conn = Connection::open(&path).map_err(sqlite_err)?;
+    conn.execute_batch(
+        "CREATE TABLE diary_files (
+             id INTEGER PRIMARY KEY AUTOINCREMENT,
+             path TEXT UNIQUE NOT NULL,
+             diary_name TEXT NOT NULL,
+             checksum TEXT NOT NULL,
+             mtime INTEGER NOT NULL,
+             size INTEGER NOT NULL,
+             updated_at INTEGER NOT NULL
+         );
+         CREATE TABLE chunks (
+             id INTEGER PRIMARY KEY AUTOINCREMENT,
+             file_id INTEGER NOT NULL REFERENCES diary_files(id) ON DELETE CASCADE,
+             chunk_index INTEGER NOT NULL,
+             content TEXT NOT NULL,
+             vector BLOB,
+             UNIQUE (file_id, chunk_index)
+         );
+         CREATE TABLE tags (
+             id INTEGER PRIMARY KEY AUTOINCREMENT,
+             name TEXT UNIQUE NOT NULL,
+             vector BLOB
+         );",
+    )
+    .map_err(sqlite_err)?;
+
+    let mut rng = SplitMix64::new(seed);
+    let tx = conn.transaction().map_err(sqlite_err)?;
+    tx.execute(
+        "INSERT INTO diary_files (id, path, diary_name, checksum, mtime, size, updated_at) VALUES (1, ?1, ?2, '', 0, 0, 0)",
+        params![format!("{}/diary.txt", FIXTURE_DIARY), FIXTURE_DIARY],
+    )
+    .map_err(sqlite_err)?;
+    for i in 1..=n_tags {
+        let vector = f32_to_bytes(&fixture_vector(&mut rng, dim as usize));
+        tx.execute("INSERT INTO tags (id, name, vector) VALUES (?1, ?2, ?3)", params![i, format!("tag_{}", i), vector])
+            .map_err(sqlite_err)?;
+    }
+    for i in 1..=n_chunks {
+        let vector = f32_to_bytes(&fixture_vector(&mut rng, dim as usize));
+        tx.execute(
+            "INSERT INTO chunks (id, file_id, chunk_index, content, vector) VALUES (?1, 1, ?2, ?3, ?4)",
+            params![i, i - 1, format!("chunk {}", i), vector],
+        )
+        .map_err(sqlite_err)?;
+    }
+    tx.commit().map_err(sqlite_err)
+}
+
+/// 生成并保存一个含 n 个确定性随机向量 (ID 1..=n) 的索引 (带 .meta 侧车)
+#[pyfunction]
+pub fn make_test_index(py: Python<'_>, path: String, n: u32, dim: u32, seed: u64) -> PyResult<()> {
+    let index = VexusIndex::new(dim, n.max(1), None, None, None, None, None, crate::DEFAULT_MAX_RESULTS)?;
+    let mut rng = SplitMix64::new(seed);
+    let ids: Vec<u32> = (1..=n).collect();
+    let vectors: Vec<f32> = ids.iter().flat_map(|_| fixture_vector(&mut rng, dim as usize)).collect();
+    index.add_batch(py, ids, f32_to_bytes(&vectors), None, "le")?;
+    index.save(py, path)
+}
+
+/// 把 raw 放在缓冲区中偏移 offset 字节处再经 f32_view 解释，用于覆盖未按 f32 对齐的输入
+#[pyfunction]
+pub fn f32_view_at_offset(raw: Vec<u8>, offset: usize) -> PyResult<Vec<f32>> {
+    let mut buffer = vec![0u8; offset + raw.len()];
+    buffer[offset..].copy_from_slice(&raw);
+    Ok(crate::f32_view(&buffer[offset..], "raw", None)?.into_owned())
+}
+
+/// 持有索引写锁 hold_ms 毫秒，拿到锁后调用 acquired.set() (传入 threading.Event)；
+/// 在另一个线程里运行，用来让带 timeout_ms 的搜索确定地等锁超时
+#[pyfunction]
+pub fn hold_write_lock(py: Python<'_>, index: PyRef<'_, VexusIndex>, hold_ms: u64, acquired: PyObject) -> PyResult<()> {
+    let _guard = index
+        .index
+        .write()
+        .map_err(|e| PyErr::new::<VexusError, _>(format!("Lock failed: {}", e)))?;
+    acquired.call_method0(py, "set")?;
+    py.allow_threads(|| std::thread::sleep(std::time::Duration::from_millis(hold_ms)));
+    Ok(())
+}
+
+/// 注册 vector_db.testing 子模块
+pub fn register(parent: &Bound<'_, PyModule>) -> PyResult<()> {
+    let m = PyModule::new_bound(parent.py(), "testing")?;
+    m.add_function(wrap_pyfunction!(make_test_db, &m)?)?;
+    m.add_function(wrap_pyfunction!(make_test_index, &m)?)?;
+    m.add_function(wrap_pyfunction!(f32_view_at_offset, &m)?)?;
+    m.add_function(wrap_pyfunction!(hold_write_lock, &m)?)?;
+    m.add("FIXTURE_DIARY", FIXTURE_DIARY)?;
+    parent.add_submodule(&m)
+}
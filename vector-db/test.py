@@ -5,7 +5,7 @@ Vexus-Lite Python 测试文件
 
 import struct
 import os
-from vector_db import VexusIndex
+from vector_db import VexusIndex, VersionedIndex, TieredIndex
 
 print('🧪 Testing Vexus-Lite (PyO3)...\n')
 
@@ -78,6 +78,2045 @@ try:
     vexus.remove(1)
     print('✅ Vector removed\n')
 
+    # 测试9: 分数校准
+    print('Test 9: Fitting score calibration...')
+    info = vexus.fit_score_calibration(100, 42)
+    assert len(info.quantiles) == 101
+    assert all(a <= b for a, b in zip(info.quantiles, info.quantiles[1:]))
+    calibrated = vexus.search(query_bytes, 3, calibrated=True)
+    assert all(0.0 <= r.score <= 1.0 for r in calibrated)
+    assert all(a.score >= b.score for a, b in zip(calibrated, calibrated[1:]))
+    # 校准随 .meta 持久化: 重新加载后 calibrated 分数不变
+    cal = VexusIndex(dim=128, capacity=50)
+    cal.add_batch(list(range(50)), struct.pack('6400f', *[random.random() for _ in range(6400)]))
+    cal_info = cal.fit_score_calibration(50, 7)
+    cal.save('./test_calibrated.usearch')
+    reloaded = VexusIndex.load(dim=128, capacity=50, index_path='./test_calibrated.usearch')
+    assert reloaded.calibration_info().quantiles == cal_info.quantiles
+    assert [(r.id, r.score) for r in reloaded.search(query_bytes, 5, calibrated=True)] == \
+        [(r.id, r.score) for r in cal.search(query_bytes, 5, calibrated=True)]
+    os.remove('./test_calibrated.usearch')
+    os.remove('./test_calibrated.usearch.meta')
+    print(f'✅ Calibration fitted on {info.sample_size} vectors ({info.n_pairs} pairs)\n')
+
+    # 测试10: 多文件恢复
+    print('Test 10: Recovering from multiple SQLite files...')
+    import sqlite3
+    db_paths = []
+    for shard in range(2):
+        path = f'./test_shard_{shard}.db'
+        conn = sqlite3.connect(path)
+        conn.execute('CREATE TABLE IF NOT EXISTS tags (id INTEGER PRIMARY KEY, vector BLOB)')
+        for i in range(5):
+            vec = struct.pack('128f', *[random.random() for _ in range(128)])
+            conn.execute('INSERT OR REPLACE INTO tags VALUES (?, ?)', (shard * 100 + i, vec))
+        conn.commit()
+        conn.close()
+        db_paths.append(path)
+    multi = VexusIndex(dim=128, capacity=100)
+    report = multi.recover_from_sqlite_multi(db_paths + ['./missing_shard.db'], 'tags')
+    assert report.inserted == 10 and report.files_processed == 2
+    assert report.failed_files[0][0] == './missing_shard.db'
+    for path in db_paths:
+        os.remove(path)
+    print(f'✅ Recovered {report.inserted} vectors, {len(report.failed_files)} file(s) failed\n')
+
+    # 测试11: 搜索超时
+    print('Test 11: Search with timeout...')
+    assert len(vexus.search(query_bytes, 2, timeout_ms=5000)) == 2
+
+    def search_while_locked(timeout_ms):
+        """另一个线程持有写锁期间搜索，搜索必然等锁超时"""
+        import threading
+        import vector_db
+        acquired = threading.Event()
+        holder = threading.Thread(target=vector_db.testing.hold_write_lock, args=(vexus, 500, acquired))
+        holder.start()
+        try:
+            assert acquired.wait(5)
+            vexus.search(query_bytes, 2, timeout_ms=timeout_ms)
+        finally:
+            holder.join()
+
+    for tiny in (0, 50):
+        try:
+            search_while_locked(tiny)
+            assert False, f'timeout_ms={tiny} should raise while the index is locked'
+        except TimeoutError:
+            pass
+    assert len(vexus.search(query_bytes, 2, timeout_ms=5000)) == 2
+    print('✅ Tiny timeout raised TimeoutError\n')
+
+    # 测试12: 生命周期统计
+    print('Test 12: Lifetime stats round-trip...')
+    life = vexus2.lifetime_stats()
+    assert life.total_adds == 3 and life.save_count == 1 and not life.initialized_fresh
+    os.rename('./test_index.usearch.meta', './test_index.usearch.meta.bak')
+    legacy = VexusIndex.load(dim=128, capacity=1000, index_path='./test_index.usearch')
+    assert legacy.lifetime_stats().initialized_fresh
+    os.rename('./test_index.usearch.meta.bak', './test_index.usearch.meta')
+    print(f'✅ Lifetime stats: {life.total_adds} adds, {life.save_count} saves\n')
+
+    # 测试13: 分量范围校验
+    print('Test 13: Value bounds validation...')
+    bounded = VexusIndex(dim=128, capacity=10, value_bounds=(-1.0, 1.0))
+    bounded.add(1, single_bytes)
+    bad = list(single_vector)
+    bad[7] = 5.0
+    try:
+        bounded.add(2, struct.pack('128f', *bad))
+        raise AssertionError('out-of-range vector was accepted')
+    except ValueError as e:
+        assert 'id 2' in str(e) and 'dimension 7' in str(e)
+    print('✅ Out-of-range component rejected\n')
+
+    # 测试14: 质心
+    print('Test 14: Computing centroid...')
+    small = VexusIndex(dim=4, capacity=10)
+    rows = [[1.0, 2.0, 3.0, 4.0], [3.0, 2.0, 1.0, 0.0], [2.0, 2.0, 2.0, 2.0]]
+    small.add_batch([1, 2, 3], struct.pack('12f', *[x for r in rows for x in r]))
+    centroid = struct.unpack('4f', small.centroid())
+    expected = [sum(col) / len(rows) for col in zip(*rows)]
+    assert all(abs(a - b) < 1e-6 for a, b in zip(centroid, expected))
+    print(f'✅ Centroid: {centroid}\n')
+
+    # 测试15: 重新居中
+    print('Test 15: Recentering index...')
+    removed = struct.unpack('4f', small.recenter())
+    assert all(abs(a - b) < 1e-6 for a, b in zip(removed, expected))
+    assert all(abs(x) < 1e-5 for x in struct.unpack('4f', small.centroid()))
+    assert small.lifetime_stats().last_compaction is not None
+    # 逐个 remove + add 为每个向量留下一个墓碑
+    assert small.tombstone_ratio == 1.0 and small.should_compact(0.5)
+    print('✅ Centroid is ~zero after recenter\n')
+
+    # 测试16: 并发保存与插入
+    print('Test 16: Concurrent save while inserting...')
+    import threading
+    stress = VexusIndex(dim=16, capacity=8)
+    stop = threading.Event()
+
+    def inserter():
+        i = 0
+        while not stop.is_set() and i < 2000:
+            stress.add(i, struct.pack('16f', *[random.random() for _ in range(16)]))
+            i += 1
+
+    worker = threading.Thread(target=inserter)
+    worker.start()
+    snapshots = []
+    for n in range(10):
+        path = f'./test_stress_{n}.usearch'
+        stress.save(path)
+        snapshots.append(path)
+    stop.set()
+    worker.join()
+    for path in snapshots:
+        snap = VexusIndex.load(dim=16, capacity=8, index_path=path)
+        size = snap.stats().total_vectors
+        assert sorted(snap.sample_random(size + 1, 0)) == list(range(size))
+        os.remove(path)
+        os.remove(path + '.meta')
+    print(f'✅ {len(snapshots)} snapshots loaded with prefix-consistent ids\n')
+
+    # 测试17: JSON 统计导出
+    print('Test 17: Exporting stats as JSON...')
+    import json
+    stats_doc = json.loads(vexus.stats_json())
+    for key in ('total_vectors', 'dimensions', 'capacity', 'memory_usage', 'lifetime'):
+        assert key in stats_doc, key
+    assert stats_doc['lifetime']['save_count'] == 1
+    print(f'✅ JSON stats keys: {sorted(stats_doc)}\n')
+
+    # 测试18: 恢复时的 ID 过滤
+    print('Test 18: Recovery with include/exclude filters...')
+    filter_db = './test_filter.db'
+    conn = sqlite3.connect(filter_db)
+    conn.execute('CREATE TABLE tags (id INTEGER PRIMARY KEY, vector BLOB)')
+    for i in range(20):
+        conn.execute('INSERT INTO tags VALUES (?, ?)', (i, struct.pack('128f', *[random.random() for _ in range(128)])))
+    conn.commit()
+    conn.close()
+    small_filter = VexusIndex(dim=128, capacity=100)
+    report = small_filter.recover_from_sqlite_multi([filter_db], 'tags', exclude_ids=[0, 1, 2])
+    assert report.inserted == 17 and report.excluded == 3
+    large_filter = VexusIndex(dim=128, capacity=100)
+    allow = list(range(10, 20)) + list(range(1000, 11000))
+    report = large_filter.recover_from_sqlite_multi([filter_db], 'tags', include_ids=allow, exclude_ids=[19])
+    assert report.inserted == 9 and report.excluded == 11
+    # 单文件恢复同样返回报告；类型不对的行计入 skipped_row_errors 而不是被静默丢弃
+    conn = sqlite3.connect(filter_db)
+    conn.execute('INSERT INTO tags VALUES (?, ?)', (20, 'not a blob'))
+    conn.commit()
+    conn.close()
+    report = VexusIndex(dim=128, capacity=100).recover_from_sqlite(filter_db, 'tags', exclude_ids=[0, 1])
+    assert (report.inserted, report.excluded, report.skipped_row_errors) == (18, 2, 1)
+    os.remove(filter_db)
+    print('✅ Small (in-loop) and large (temp table) filters applied\n')
+
+    # 测试19: 展平的批量搜索
+    print('Test 19: Flat batch search...')
+    batch_queries = query_bytes + single_bytes
+    qidx, rids, rscores = vexus.search_batch_flat(batch_queries, 2, 2)
+    assert len(qidx) == len(rids) == len(rscores) == 4
+    assert qidx == sorted(qidx) and set(qidx) == {0, 1}
+    assert [r.id for r in vexus.search(query_bytes, 2)] == [rid for q, rid in zip(qidx, rids) if q == 0]
+    print('✅ Flat arrays are aligned and grouped by query\n')
+
+    # 测试20: 扩容事件
+    print('Test 20: Capacity growth events...')
+    import logging
+
+    class CapturedLogs(logging.Handler):
+        def __init__(self):
+            super().__init__()
+            self.records = []
+
+        def emit(self, record):
+            self.records.append(record)
+
+    vdb_logger = logging.getLogger('vector_db')
+    vdb_logger.setLevel(logging.DEBUG)
+    captured = CapturedLogs()
+    growing = VexusIndex(dim=8, capacity=10)
+    growing.add_batch(list(range(10)), struct.pack('80f', *[random.random() for _ in range(80)]))
+    vdb_logger.addHandler(captured)
+    events = growing.add_batch(list(range(10, 30)), struct.pack('160f', *[random.random() for _ in range(160)]))
+    vdb_logger.removeHandler(captured)
+    assert len(events) == 1 and events[0].new_capacity >= 30
+    # 扩容事件同时以 debug 级别写入 vector_db 日志器
+    assert [r.levelno for r in captured.records] == [logging.DEBUG]
+    assert captured.records[0].getMessage().startswith(f'add_batch: capacity grew {events[0].old_capacity} -> {events[0].new_capacity}')
+    print(f'✅ Single growth event: {events[0].old_capacity} -> {events[0].new_capacity} in {events[0].elapsed_ms:.2f} ms\n')
+
+    # 测试21: 结果排序方式
+    print('Test 21: Result ordering...')
+    best = [r.id for r in vexus.search(query_bytes, 3)]
+    worst = [r.id for r in vexus.search(query_bytes, 3, order='worst_first')]
+    assert worst == best[::-1]
+    print('✅ worst_first is the exact reverse of best_first\n')
+
+    # 测试22: 并发删除 + 搜索
+    print('Test 22: Concurrent remove + search stress...')
+    import time
+    from vector_db import VexusError
+    stress = VexusIndex(dim=16, capacity=2000)
+    stress.add_batch(list(range(2000)), struct.pack('32000f', *[random.random() for _ in range(32000)]))
+    removed_total = [0]
+    unexpected = []
+    deadline = time.time() + 3.0
+
+    def remover(start):
+        ids = list(range(start, 2000, 4))
+        while ids and time.time() < deadline:
+            batch, ids = ids[:10], ids[10:]
+            try:
+                removed_total[0] += stress.remove_batch(batch)
+            except VexusError:
+                pass
+            except Exception as e:
+                unexpected.append(e)
+
+    def searcher():
+        q = struct.pack('16f', *[random.random() for _ in range(16)])
+        while time.time() < deadline:
+            try:
+                stress.search(q, 5)
+            except VexusError:
+                pass
+            except Exception as e:
+                unexpected.append(e)
+
+    workers = [threading.Thread(target=remover, args=(i,)) for i in range(2)]
+    workers += [threading.Thread(target=searcher) for _ in range(2)]
+    for w in workers:
+        w.start()
+    for w in workers:
+        w.join()
+    assert not unexpected, unexpected
+    assert stress.stats().total_vectors == 2000 - removed_total[0]
+    print(f'✅ {removed_total[0]} removes interleaved with searches, index consistent\n')
+
+    # 测试23: close 后操作报错
+    print('Test 23: Operations after close...')
+    from vector_db import IndexClosedError
+    closing = VexusIndex(dim=4, capacity=10)
+    closing.add(1, struct.pack('4f', 0.1, 0.2, 0.3, 0.4))
+    closing.close()
+    closing.close()
+    for op in (lambda: closing.search(struct.pack('4f', 0, 0, 0, 0), 1),
+               lambda: closing.add(2, struct.pack('4f', 0, 0, 0, 0)),
+               lambda: closing.remove(1),
+               lambda: closing.stats(),
+               # 只读 meta 或不碰索引的方法同样报错
+               lambda: closing.sample_random(1, 0),
+               lambda: closing.sample_stratified({1: 0}, 1, 0),
+               lambda: closing.lifetime_stats(),
+               lambda: closing.calibration_info(),
+               lambda: closing.get_side_data(1),
+               lambda: closing.set_side_data(1, 'x'),
+               lambda: closing.get_labels(1),
+               lambda: closing.bloom_stats(),
+               lambda: closing.tombstone_ratio,
+               lambda: closing.info(),
+               lambda: closing.estimate_memory(10),
+               lambda: closing.query_log(),
+               lambda: closing.contains(1),
+               lambda: closing.compute_attention(struct.pack('4f', 0, 0, 0, 0), [], 1.0),
+               lambda: closing.filter_by_predicate(lambda key: True),
+               lambda: closing.compute_handshake_magnitudes(struct.pack('4f', 1, 0, 0, 0), struct.pack('4f', 0, 1, 0, 0), 1)):
+        try:
+            op()
+            raise AssertionError('operation after close should fail')
+        except IndexClosedError:
+            pass
+    print('✅ Closed index raises IndexClosedError\n')
+
+    # 测试24: 线性变换
+    print('Test 24: Linear transform of stored vectors...')
+    algebra = VexusIndex(dim=2, capacity=10)
+    algebra.add(1, struct.pack('2f', 1.0, 0.0))
+    algebra.add(2, struct.pack('2f', 0.0, 1.0))
+    rotate = struct.pack('4f', 0.0, -1.0, 1.0, 0.0)
+    out = algebra.apply_transform([1, 2], rotate, 2, False)
+    assert out == [[0.0, 1.0], [-1.0, 0.0]]
+    assert algebra.search(struct.pack('2f', 1.0, 0.0), 1)[0].id == 1
+    assert algebra.lifetime_stats().last_compaction is None
+    algebra.apply_transform([1, 2], rotate, 2, True)
+    assert algebra.lifetime_stats().last_compaction is not None
+    assert algebra.search(struct.pack('2f', 0.0, 1.0), 1)[0].id == 1
+    print('✅ Rotation applied both as a preview and in place\n')
+
+    # 测试25: 已正交基的直接投影
+    print('Test 25: Projection onto an orthonormal basis...')
+    vecs = [random.random() for _ in range(128 * 32)]
+    svd = vexus.compute_svd(struct.pack(f'{len(vecs)}f', *vecs), 32, 16)
+    basis = struct.pack(f'{len(svd.u)}f', *svd.u)
+    target = struct.pack('128f', *[random.random() for _ in range(128)])
+    t0 = time.perf_counter()
+    for _ in range(200):
+        slow = vexus.compute_orthogonal_projection(target, basis, svd.k)
+    t1 = time.perf_counter()
+    for _ in range(200):
+        fast = vexus.compute_orthogonal_projection(target, basis, svd.k, assume_orthonormal=True, verify=True)
+    t2 = time.perf_counter()
+    assert all(abs(a - b) < 1e-4 for a, b in zip(slow.projection, fast.projection))
+    assert all(abs(a - b) < 1e-4 for a, b in zip(slow.basis_coefficients, fast.basis_coefficients))
+    try:
+        skewed = struct.pack('256f', *([1.0] + [0.0] * 127 + [1.0] * 128))
+        vexus.compute_orthogonal_projection(target, skewed, 2, assume_orthonormal=True, verify=True)
+        raise AssertionError('non-orthonormal basis should fail verification')
+    except ValueError:
+        pass
+    print(f'✅ Same projection, Gram-Schmidt {t1 - t0:.4f}s vs direct {t2 - t1:.4f}s\n')
+
+    # 测试26: 图连通性
+    print('Test 26: Graph connectivity stats...')
+    graph = VexusIndex(dim=8, capacity=500)
+    graph.add_batch(list(range(500)), struct.pack('4000f', *[random.random() for _ in range(4000)]))
+    gs = graph.graph_stats(sample=100, seed=1)
+    assert gs.connectivity == 16
+    assert 0 < gs.avg_degree <= 32 and gs.avg_degree != min(32, 499)
+    assert gs.n_levels >= 1 and gs.max_level == gs.n_levels - 1
+    assert gs.self_recall > 0.9
+    # 三个节点时第 0 层每个节点恰好连着另外两个
+    tiny_graph = VexusIndex(dim=2, capacity=3)
+    tiny_graph.add_batch([1, 2, 3], struct.pack('6f', 0.0, 0.0, 1.0, 0.0, 0.0, 1.0))
+    tiny_gs = tiny_graph.graph_stats()
+    assert (tiny_gs.avg_degree, tiny_gs.sample_size) == (2.0, 3)
+    empty_gs = VexusIndex(dim=2, capacity=3).graph_stats()
+    assert (empty_gs.avg_degree, empty_gs.n_levels) == (0.0, 0)
+    print(f'✅ avg degree {gs.avg_degree}, levels {gs.n_levels}, self recall {gs.self_recall:.2f}\n')
+
+    # 测试27: 多版本索引
+    print('Test 27: Versioned index...')
+    old_model = VexusIndex(dim=2, capacity=10)
+    old_model.add(1, struct.pack('2f', 1.0, 0.0))
+    new_model = VexusIndex(dim=2, capacity=10)
+    new_model.add(2, struct.pack('2f', 1.0, 0.0))
+    versioned = VersionedIndex()
+    versioned.add_version('v1', old_model)
+    versioned.add_version('v2', new_model)
+    probe = struct.pack('2f', 1.0, 0.0)
+    assert versioned.active_version == 'v1'
+    r1, r2 = versioned.compare_versions('v1', 'v2', probe, 1)
+    assert r1[0].id == 1 and r2[0].id == 2
+    versioned.promote('v2')
+    assert versioned.search(probe, 1)[0].id == 2
+    try:
+        versioned.promote('v3')
+        raise AssertionError('unknown version should fail')
+    except KeyError:
+        pass
+    print('✅ Versions compared and promoted\n')
+
+    # 测试28: 扩展握手输出
+    print('Test 28: Extended handshakes...')
+    import math
+    hs_index = VexusIndex(dim=2, capacity=10)
+    hs_tags = struct.pack('6f', 1.0, 0.0, 0.0, 2.0, 0.0, 0.0)
+    plain = hs_index.compute_handshakes(struct.pack('2f', 1.0, 0.0), hs_tags, 3)
+    assert plain.relative_magnitudes is None and plain.tag_norms is None
+    hs = hs_index.compute_handshakes(struct.pack('2f', 1.0, 0.0), hs_tags, 3, extended=True)
+    assert hs.query_norm == 1.0
+    assert hs.tag_norms == [1.0, 2.0, 0.0]
+    assert hs.relative_magnitudes[0] == 0.0
+    assert abs(hs.relative_magnitudes[1] - math.sqrt(5) / 2) < 1e-9
+    assert hs.relative_magnitudes[2] == float('inf')
+    print('✅ Relative magnitudes match hand-computed values\n')
+
+    # 测试29: Bloom 过滤器快速否定
+    print('Test 29: Bloom filter fast negatives...')
+    bloomed = VexusIndex(dim=4, capacity=100, bloom_bits=1 << 16)
+    bloomed.add_batch(list(range(50)), struct.pack('200f', *[random.random() for _ in range(200)]))
+    assert all(bloomed.contains_batch(list(range(50))))
+    negatives, fallthroughs = bloomed.bloom_stats()
+    assert (negatives, fallthroughs) == (0, 50)
+    probes = list(range(1000, 11000))
+    assert not any(bloomed.contains_batch(probes))
+    negatives, after = bloomed.bloom_stats()
+    # 判否的查询不计入 fallthroughs，落到真实检查的只有假阳性
+    assert negatives + (after - fallthroughs) == len(probes)
+    assert after - fallthroughs < len(probes) * 0.01
+    bloomed.remove(0)
+    assert bloomed.contains(0) is False
+    assert VexusIndex(dim=4, capacity=10).bloom_stats() is None
+    print(f'✅ {negatives} definite negatives, {after - fallthroughs} false positives fell through\n')
+
+    # 测试30: 损坏文件的部分恢复
+    print('Test 30: Partial load of a corrupted index...')
+    from vector_db import CorruptFileError
+    partial_src = VexusIndex(dim=4, capacity=100)
+    partial_src.add_batch(list(range(100)), struct.pack('400f', *[random.random() for _ in range(400)]))
+    partial_src.save('./test_partial.usearch')
+    intact, recovered, total = VexusIndex.load_partial(4, 100, './test_partial.usearch')
+    assert intact.stats().total_vectors == 100 and (recovered, total) == (100, 100)
+    assert intact.verify_integrity() is True
+    os.remove('./test_partial.usearch.meta')
+    # 没有 .meta 的旧索引无法校验
+    legacy = VexusIndex.load(4, 100, './test_partial.usearch')
+    assert legacy.verify_integrity() is None
+    with open('./test_partial.usearch', 'rb') as f:
+        partial_bytes = f.read()
+    with open('./test_partial.usearch', 'r+b') as f:
+        f.truncate(len(partial_bytes) * 3 // 4)
+    damaged, recovered, total = VexusIndex.load_partial(4, 100, './test_partial.usearch')
+    assert damaged.stats().total_vectors == recovered < 100 and total == 100
+    assert damaged.verify_integrity() is True
+    capped_partial, _, _ = VexusIndex.load_partial(4, 100, './test_partial.usearch', max_results=3)
+    try:
+        capped_partial.search(struct.pack('4f', 0, 0, 0, 0), 4)
+        assert False, 'k above max_results should raise'
+    except ValueError:
+        pass
+    # 文件头版本不认识时拒绝按 v2 布局解析
+    head_at = 8 + 100 * 16
+    assert partial_bytes[head_at:head_at + 7] == b'usearch'
+    with open('./test_partial.usearch', 'wb') as f:
+        f.write(partial_bytes[:head_at + 7] + struct.pack('<H', 9) + partial_bytes[head_at + 9:len(partial_bytes) * 3 // 4])
+    try:
+        VexusIndex.load_partial(4, 100, './test_partial.usearch')
+        assert False, 'unknown usearch format version should be rejected'
+    except CorruptFileError:
+        pass
+    os.remove('./test_partial.usearch')
+    print(f'✅ Recovered {recovered} of {total} vectors from a truncated file\n')
+
+    # 测试31: f16 距离核
+    print('Test 31: f16 kernels...')
+    from vector_db import dot_f16, l2_f16, cosine_f16, dot_f16_batch, l2_f16_batch, cosine_f16_batch
+    fa = [random.uniform(-1, 1) for _ in range(64)]
+    fb = [random.uniform(-1, 1) for _ in range(64)]
+    ha, hb = struct.pack('64e', *fa), struct.pack('64e', *fb)
+    ref_dot = sum(x * y for x, y in zip(fa, fb))
+    ref_l2 = math.sqrt(sum((x - y) ** 2 for x, y in zip(fa, fb)))
+    ref_cos = ref_dot / (math.sqrt(sum(x * x for x in fa)) * math.sqrt(sum(y * y for y in fb)))
+    assert abs(dot_f16(ha, hb) - ref_dot) < 0.05
+    assert abs(l2_f16(ha, hb) - ref_l2) < 0.01
+    assert abs(cosine_f16(ha, hb) - ref_cos) < 0.005
+    rows = ha + hb
+    query_f32 = struct.pack('64f', *fa)
+    assert all(abs(x - y) < 0.05 for x, y in zip(dot_f16_batch(rows, 2, query_f32), [sum(x * x for x in fa), ref_dot]))
+    assert abs(l2_f16_batch(rows, 2, query_f32)[1] - ref_l2) < 0.01
+    assert abs(cosine_f16_batch(rows, 2, query_f32)[0] - 1.0) < 0.005
+    try:
+        dot_f16(ha, hb[:-1])
+        raise AssertionError('odd-length buffer should fail')
+    except ValueError:
+        pass
+    print('✅ f16 kernels match f32 reference within half-precision tolerance\n')
+
+    # 测试32: 分片结果合并
+    print('Test 32: Federated result merge...')
+    from vector_db import merge_results
+    shards = []
+    for shard_no in range(3):
+        shard = VexusIndex(dim=2, capacity=10)
+        for j in range(3):
+            shard.add(shard_no * 10 + j, struct.pack('2f', shard_no * 0.1 + j * 0.3, 0.0))
+        shards.append(shard.search(struct.pack('2f', 0.0, 0.0), 3))
+    merged = merge_results(shards, 4)
+    expected = sorted((r for rs in shards for r in rs), key=lambda r: (-r.score, r.id))[:4]
+    assert [r.id for r in merged] == [r.id for r in expected]
+    assert [r.id for r in merged][:3] == [0, 10, 20]
+    print('✅ Three shards merged into a global top-4\n')
+
+    # 测试33: 死 ID 审计
+    print('Test 33: Dead id audit...')
+    audited = VexusIndex(dim=4, capacity=20)
+    audited.add_batch(list(range(10)), struct.pack('40f', *[random.random() for _ in range(40)]))
+    audited.remove(3)
+    live, dead = audited.audit_live_ids()
+    assert live == [0, 1, 2, 4, 5, 6, 7, 8, 9] and dead == []
+    assert audited.cleanup_dead() == 0
+    print('✅ All tracked ids are live after remove\n')
+
+    # 测试34: 临时候选集精确排序
+    print('Test 34: Ranking an external candidate block...')
+    from vector_db import rank_candidates
+    cand = [random.random() for _ in range(128 * 50)]
+    cand_bytes = struct.pack(f'{len(cand)}f', *cand)
+    ranked = rank_candidates(query_bytes, cand_bytes, 50, 5)
+    q_vals = struct.unpack('128f', query_bytes)
+    exact = sorted(range(50), key=lambda r: sum((q_vals[d] - cand[r * 128 + d]) ** 2 for d in range(128)))[:5]
+    assert [r.id for r in ranked] == exact
+    assert all(a.score >= b.score for a, b in zip(ranked, ranked[1:]))
+    cos_ranked = rank_candidates(query_bytes, cand_bytes, 50, 3, 'cos')
+    assert len(cos_ranked) == 3 and cos_ranked[0].score <= 1.0 + 1e-9
+    print('✅ Exact top-k over candidates matches brute force\n')
+
+    # 测试35: 向量迭代器
+    print('Test 35: Iterating stored vectors...')
+    iterated = VexusIndex(dim=2, capacity=10)
+    iterated.add_batch([5, 1, 3], struct.pack('6f', 5.0, 0.0, 1.0, 0.0, 3.0, 0.0))
+    pairs = list(iterated.iter_vectors())
+    assert [p[0] for p in pairs] == [1, 3, 5]
+    assert pairs[2][1] == [5.0, 0.0]
+    it = iter(iterated)
+    next(it)
+    iterated.remove(3)
+    assert [p[0] for p in it] == [5]
+    print('✅ Iterator yields (id, vector) pairs in id order and skips removed ids\n')
+
+    # 测试36: 附加数据持久化
+    print('Test 36: Side data round-trip...')
+    sided = VexusIndex(dim=2, capacity=10)
+    sided.add_batch([1, 2], struct.pack('4f', 1.0, 0.0, 0.0, 1.0))
+    sided.set_side_data(1, '{"diary": "a"}', 1700000000)
+    sided.set_side_data(2, None, 1700000500)
+    sided.remove(2)
+    sided.save('./test_side.usearch')
+    restored = VexusIndex.load(2, 10, './test_side.usearch')
+    assert restored.get_side_data(1) == ('{"diary": "a"}', 1700000000, False)
+    assert restored.get_side_data(2) == (None, 1700000500, True)
+    assert restored.get_side_data(3) is None
+    os.remove('./test_side.usearch')
+    os.remove('./test_side.usearch.meta')
+    print('✅ Side data and tombstones survive save/load\n')
+
+    # 测试37: 配置随 save/load 恢复
+    print('Test 37: Index configuration round-trip...')
+    configured = VexusIndex(dim=3, capacity=10, value_bounds=(-1.0, 1.0))
+    configured.add(1, struct.pack('3f', 0.5, 0.5, 0.5))
+    configured.save('./test_config.usearch')
+    reloaded = VexusIndex.load(3, 10, './test_config.usearch')
+    saved_info, loaded_info = configured.info(), reloaded.info()
+    for field in ('dimensions', 'metric', 'quantization', 'connectivity', 'expansion_add', 'expansion_search', 'value_bounds'):
+        assert getattr(saved_info, field) == getattr(loaded_info, field), field
+    assert loaded_info.value_bounds == (-1.0, 1.0)
+    assert json.loads(reloaded.stats_json())['config']['metric'] == 'l2sq'
+    try:
+        reloaded.add(2, struct.pack('3f', 5.0, 0.0, 0.0))
+        raise AssertionError('restored value_bounds should reject out-of-range vectors')
+    except ValueError:
+        pass
+    for bad_args in ((4, 10, './test_config.usearch'), (3, 10, './test_config.usearch', None, (0.0, 2.0))):
+        try:
+            VexusIndex.load(*bad_args)
+            raise AssertionError('mismatched override should fail')
+        except ValueError:
+            pass
+    os.remove('./test_config.usearch')
+    os.remove('./test_config.usearch.meta')
+    print('✅ Configuration restored from the sidecar, overrides validated\n')
+
+    # 测试38: 逐向量回调
+    print('Test 38: map_vectors callback...')
+    seen = {}
+
+    def collect(id, raw):
+        if id == 1:
+            raise RuntimeError('callback failure is counted, not raised')
+        seen[id] = struct.unpack('2f', raw)
+
+    assert iterated.map_vectors(collect) == 1
+    assert seen == {5: (5.0, 0.0)}
+    # KeyboardInterrupt / SystemExit 不能被吞掉
+    for interrupt in (KeyboardInterrupt, SystemExit):
+        def interrupted(id, raw, interrupt=interrupt):
+            raise interrupt()
+        try:
+            iterated.map_vectors(interrupted)
+            assert False, f'{interrupt.__name__} should propagate out of map_vectors'
+        except interrupt:
+            pass
+    print('✅ Callback ran per vector, failures only reduce the count\n')
+
+    # 测试39: EPA 批量分类
+    print('Test 39: classify_epa matches project argmax...')
+    epa_basis = struct.pack(f'{len(svd.u)}f', *svd.u)
+    epa_mean = struct.pack('128f', *[0.5] * 128)
+    epa_rows = [[random.random() for _ in range(128)] for _ in range(20)]
+    labels = vexus.classify_epa(struct.pack(f'{20 * 128}f', *sum(epa_rows, [])), 20, epa_basis, epa_mean, svd.k)
+    for row, label in zip(epa_rows, labels):
+        probs = vexus.project(struct.pack('128f', *row), epa_basis, epa_mean, svd.k).probabilities
+        assert label == max(range(len(probs)), key=probs.__getitem__)
+    print('✅ Batch labels equal per-vector argmax\n')
+
+    # 测试40: 向量库健康指标
+    print('Test 40: Corpus health on synthetic data...')
+    healthy = VexusIndex(dim=4, capacity=20)
+    axes = [(2.0, 0, 0, 0), (0, 2.0, 0, 0), (0, 0, 2.0, 0), (0, 0, 0, 2.0)]
+    for i in range(8):
+        healthy.add(i, struct.pack('4f', *axes[i % 4]))
+    healthy.add(8, struct.pack('4f', 0, 0, 0, 0))
+    h = healthy.corpus_health(100, seed=3)
+    assert h.sample_size == 9
+    assert abs(h.near_zero_fraction - 1 / 9) < 1e-9
+    assert abs(h.mean_norm - 16 / 9) < 1e-9
+    assert abs(h.mean_pairwise_cosine - 4 / 36) < 1e-9
+    assert 3.0 < h.effective_dim <= 4.0 + 1e-9
+    assert healthy.corpus_health(5, seed=3).as_dict() == healthy.corpus_health(5, seed=3).as_dict()
+    assert h.as_dict()['sample_size'] == 9
+    # 样本数小于 dim 时走 Gram 矩阵，结果与协方差矩阵一致: 4 个去均值后两两正交的等长向量，有效维度为 2
+    for wide_dim in (2, 64):
+        wide = VexusIndex(dim=wide_dim, capacity=10)
+        for i, (a, sign) in enumerate([(0, 1), (1, 1), (0, -1), (1, -1)]):
+            row = [0.0] * wide_dim
+            row[a] = sign
+            wide.add(i, struct.pack(f'{wide_dim}f', *row))
+        assert abs(wide.corpus_health(10, seed=0).effective_dim - 2.0) < 1e-9
+    print(f'✅ {h.as_dict()}\n')
+
+    # 测试41: 参数建议
+    print('Test 41: Recommended HNSW options...')
+    small_opts = VexusIndex.recommended_options(1000, 128)
+    large_opts = VexusIndex.recommended_options(10_000_000, 128)
+    assert large_opts.expansion_add > small_opts.expansion_add
+    assert large_opts.expansion_search > small_opts.expansion_search
+    tuned = VexusIndex(128, 100, connectivity=small_opts.connectivity,
+                       expansion_add=small_opts.expansion_add, expansion_search=small_opts.expansion_search)
+    assert tuned.info().connectivity == small_opts.connectivity
+    print(f'✅ n=1e3 -> M={small_opts.connectivity}, n=1e7 -> M={large_opts.connectivity}\n')
+
+    # 测试42: 按谓词过滤
+    print('Test 42: filter_by_predicate...')
+    filtered = VexusIndex(dim=2, capacity=20)
+    filtered.add_batch(list(range(10)), struct.pack('20f', *[random.random() for _ in range(20)]))
+    assert filtered.filter_by_predicate(lambda i: i % 2 == 0) == 5
+    assert filtered.audit_live_ids()[0] == [0, 2, 4, 6, 8]
+    assert filtered.stats().total_vectors == 5
+    print('✅ Odd ids removed by predicate\n')
+
+    # 测试43: 输入缓冲区校验
+    print('Test 43: Buffer validation across entry points...')
+    from vector_db import rank_candidates, dot_f16_batch
+    checked = VexusIndex(dim=4, capacity=10)
+    good4 = struct.pack('4f', 0.1, 0.2, 0.3, 0.4)
+    checked.add(1, good4)
+    # 第三项: 是否有确定的期望元素个数 (没有时 short 输入的错误会落到配对的参数上)
+    entry_points = [
+        ('vector', lambda b: checked.add(2, b), True),
+        ('vectors', lambda b: checked.add_batch([2], b), True),
+        ('query', lambda b: checked.search(b, 1), True),
+        ('queries', lambda b: checked.search_batch_flat(b, 1, 1), True),
+        ('query', lambda b: checked.search_with_forced(b, 1, [1]), True),
+        ('query', lambda b: checked.search_robust(b, 1, 2, 0.01), True),
+        ('query', lambda b: checked.explain(b, 1, 2), True),
+        ('query', lambda b: checked.explain_results(b, []), True),
+        ('sample', lambda b: checked.warmup(1, sample=b), True),
+        ('matrix', lambda b: checked.apply_transform([1], b, 4, False), True),
+        ('query', lambda b: checked.classify_zero_shot(b, good4, 1, ['a']), True),
+        ('class_prototypes', lambda b: checked.classify_zero_shot(good4, b, 1, ['a']), True),
+        ('flattened_vectors', lambda b: checked.compute_svd(b, 1, 1), True),
+        ('vector', lambda b: checked.compute_orthogonal_projection(b, good4, 1), True),
+        ('flattened_tags', lambda b: checked.compute_orthogonal_projection(good4, b, 1), True),
+        ('query', lambda b: checked.compute_handshakes(b, good4, 1), True),
+        ('flattened_tags', lambda b: checked.compute_handshakes(good4, b, 1), True),
+        ('vector', lambda b: checked.project(b, good4, good4, 1), True),
+        ('flattened_basis', lambda b: checked.project(good4, b, good4, 1), True),
+        ('mean_vector', lambda b: checked.project(good4, good4, b, 1), True),
+        ('flattened', lambda b: checked.classify_epa(b, 1, good4, good4, 1), True),
+        ('mean_vector', lambda b: checked.classify_epa(good4, 1, good4, b, 1), True),
+        ('candidates', lambda b: rank_candidates(good4, b, 1, 1), True),
+        ('query', lambda b: rank_candidates(b, good4, 1, 1), False),
+        ('query', lambda b: dot_f16_batch(struct.pack('4e', 1, 2, 3, 4), 1, b), False),
+    ]
+    bad_inputs = [('empty', b''), ('misaligned', b'\x00' * 3), ('short', struct.pack('2f', 0.1, 0.2))]
+    n_cases = 0
+    for param, call, counted in entry_points:
+        for label, buf in bad_inputs:
+            if label == 'short' and not counted:
+                continue
+            n_cases += 1
+            try:
+                call(buf)
+                raise AssertionError(f'{param}/{label} should fail')
+            except ValueError as e:
+                assert param in str(e), (param, label, str(e))
+    print(f'✅ {n_cases} bad-buffer cases rejected with named parameters\n')
+
+    # 测试44: 索引近似相等
+    print('Test 44: approx_equals after save/load...')
+    original = VexusIndex(dim=4, capacity=20)
+    original.add_batch(list(range(10)), struct.pack('40f', *[random.random() for _ in range(40)]))
+    original.save('./test_equal.usearch')
+    copy = VexusIndex.load(4, 20, './test_equal.usearch')
+    assert original.approx_equals(copy, 1e-6) and copy.approx_equals(original, 1e-6)
+    copy.remove(0)
+    copy.add(0, struct.pack('4f', 9.0, 9.0, 9.0, 9.0))
+    assert not original.approx_equals(copy, 1e-6)
+    copy.remove(0)
+    assert not original.approx_equals(copy, 1e-6)
+    os.remove('./test_equal.usearch')
+    os.remove('./test_equal.usearch.meta')
+    print('✅ Reloaded index equals original, mutated copy does not\n')
+
+    # 测试45: 缩放点积注意力
+    print('Test 45: compute_attention...')
+    import math
+    attn_index = VexusIndex(dim=4, capacity=10)
+    attn_index.add_batch([1, 2, 3], struct.pack('12f', 1, 0, 0, 0, 0, 1, 0, 0, -1, 0, 0, 0))
+    q = struct.pack('4f', 2, 0, 0, 0)
+    weights = attn_index.compute_attention(q, [1, 2, 3], 1.0)
+    logits = [2 / 2, 0, -2 / 2]
+    z = sum(math.exp(l) for l in logits)
+    assert all(abs(w - math.exp(l) / z) < 1e-9 for w, l in zip(weights, logits))
+    assert abs(sum(weights) - 1.0) < 1e-9
+    sharp = attn_index.compute_attention(q, [1, 2, 3], 0.1)
+    assert sharp[0] > weights[0]
+    try:
+        attn_index.compute_attention(q, [1, 99], 1.0)
+        assert False, 'missing key id should raise'
+    except KeyError:
+        pass
+    try:
+        attn_index.compute_attention(q, [1], 0.0)
+        assert False, 'zero temperature should raise'
+    except ValueError:
+        pass
+    print(f'✅ Attention weights: {[round(w, 4) for w in weights]}\n')
+
+    # 测试46: 多表恢复时的 ID 命名空间
+    print('Test 46: Namespaced recovery of tags and chunks...')
+    from vector_db import pack_id, unpack_id
+    assert unpack_id(pack_id(2, 17)) == (2, 17)
+    try:
+        pack_id(1, 1 << 32)
+        assert False, 'id wider than u32 should raise'
+    except ValueError:
+        pass
+    ns_db = './test_namespace.db'
+    conn = sqlite3.connect(ns_db)
+    conn.execute('CREATE TABLE tags (id INTEGER PRIMARY KEY, vector BLOB)')
+    conn.execute('CREATE TABLE diary_files (id INTEGER PRIMARY KEY, diary_name TEXT)')
+    conn.execute('CREATE TABLE chunks (id INTEGER PRIMARY KEY, file_id INTEGER, vector BLOB)')
+    conn.execute("INSERT INTO diary_files VALUES (1, 'diary')")
+    ns_vectors = {}
+    for table in ('tags', 'chunks'):
+        for i in range(1, 4):
+            vec = struct.pack('8f', *[random.random() for _ in range(8)])
+            ns_vectors[(table, i)] = vec
+            if table == 'tags':
+                conn.execute('INSERT INTO tags VALUES (?, ?)', (i, vec))
+            else:
+                conn.execute('INSERT INTO chunks VALUES (?, 1, ?)', (i, vec))
+    conn.commit()
+    conn.close()
+    ns_index = VexusIndex(dim=8, capacity=20)
+    assert ns_index.recover_from_sqlite(ns_db, 'tags', id_namespace=1).inserted == 3
+    assert ns_index.recover_from_sqlite(ns_db, 'chunks', 'diary', id_namespace=2).inserted == 3
+    assert ns_index.stats().total_vectors == 6
+    for (table, i), vec in ns_vectors.items():
+        top = ns_index.search(vec, 1)[0]
+        assert (top.namespace, top.id) == (1 if table == 'tags' else 2, i)
+    conn = sqlite3.connect(ns_db)
+    conn.execute('INSERT INTO tags VALUES (?, ?)', (1 << 56, struct.pack('8f', *[0.0] * 8)))
+    conn.commit()
+    conn.close()
+    try:
+        ns_index.recover_from_sqlite(ns_db, 'tags', id_namespace=1)
+        assert False, 'rowid wider than u32 should raise'
+    except ValueError:
+        pass
+    assert ns_index.stats().total_vectors == 6
+    # 审计与过滤按完整 key 工作，不会误删命名空间 0 里低位相同的向量
+    ns_index.add(1, struct.pack('8f', *[0.5] * 8))
+    live, dead = ns_index.audit_live_ids()
+    assert pack_id(2, 1) in live and 1 in live and dead == []
+    assert ns_index.filter_by_predicate(lambda key: key != pack_id(2, 1)) == 1
+    assert ns_index.contains(1) and ns_index.stats().total_vectors == 6
+    assert pack_id(2, 1) not in ns_index.audit_live_ids()[0]
+    # 按 ID 寻址的方法都接受 id_namespace
+    assert ns_index.contains(2, id_namespace=2) and not ns_index.contains(2)
+    assert ns_index.contains_batch([1, 2], id_namespace=1) == [True, True]
+    ns_index.set_side_data(2, 'chunk two', 7, id_namespace=2)
+    assert ns_index.get_side_data(2, id_namespace=2) == ('chunk two', 7, False)
+    assert ns_index.get_side_data(2) is None
+    ns_index.set_labels(3, ['calm'], id_namespace=2)
+    assert ns_index.get_labels(3, id_namespace=2) == ['calm'] and ns_index.get_labels(3) == []
+    assert set(ns_index.sample_random(100, 0)) == set(ns_index.audit_live_ids()[0])
+    ns_index.remove(2, id_namespace=2)
+    assert not ns_index.contains(2, id_namespace=2) and ns_index.contains(2, id_namespace=1)
+    assert ns_index.remove_batch([2, 3], id_namespace=1) == 2
+    assert ns_index.contains(1) and ns_index.stats().total_vectors == 3
+    # 返回 ID 列表的方法给出完整 key，返回结果对象的方法带 namespace，tag 17 与 chunk 17 不会混淆
+    nsq = VexusIndex(2, 8)
+    nsq.add_batch([17, 18], struct.pack('4f', 1.0, 0.0, 1.1, 0.0), id_namespace=1)
+    nsq.add_batch([17, 18], struct.pack('4f', 0.0, 1.0, 0.0, 1.1), id_namespace=2)
+    tag17, tag18, chunk17, chunk18 = pack_id(1, 17), pack_id(1, 18), pack_id(2, 17), pack_id(2, 18)
+    along_x, along_y = struct.pack('2f', 1.0, 0.0), struct.pack('2f', 0.0, 1.0)
+    assert nsq.search_batch_flat(along_x + along_y, 2, 1)[1] == [tag17, chunk17]
+    assert [(r.namespace, r.id) for r in nsq.search_robust(along_y, 1, 4, 0.01, 1)] == [(2, 17)]
+    assert sorted(unpack_id(key)[0] for key in nsq.sample_diverse(4, 0.5, 0)) == [1, 2]
+    assert nsq.propagate_labels({tag17: 5, chunk17: 7}, 1, 1) == {tag17: 5, tag18: 5, chunk17: 7, chunk18: 7}
+    # timestamps 按 key 下标，带命名空间的 key 只取侧车时间戳: timestamps[17] = 0 不会作用到 tag 17 / chunk 17
+    nsq.set_side_data(17, timestamp=800, id_namespace=1)
+    ns_decayed = {(r.namespace, r.id): r.score for r in nsq.search_with_decay(along_x, 4, [0] * 20, 1000, 100)}
+    assert abs(ns_decayed[(1, 17)] - 0.25) < 1e-6 and abs(ns_decayed[(2, 17)] - 1 / 3) < 1e-6
+    assert abs(ns_decayed[(1, 18)] - 1 / 1.01) < 1e-6
+    os.remove(ns_db)
+    print('✅ Overlapping rowids from two tables stay separate\n')
+
+    # 测试47: 注意力加权软检索
+    print('Test 47: retrieve_weighted...')
+    blended = attn_index.retrieve_weighted(q, [1, 2, 3], 1.0)
+    expected = [weights[0] - weights[2], weights[1], 0.0, 0.0]
+    assert len(blended) == 4 and all(abs(b - e) < 1e-6 for b, e in zip(blended, expected))
+    assert attn_index.retrieve_weighted(q, [], 1.0) == [0.0] * 4
+    print(f'✅ Attended vector: {[round(x, 4) for x in blended]}\n')
+
+    # 测试48: 以 numpy 二维数组取回向量
+    print('Test 48: get_vectors_array...')
+    import numpy as np
+    found_ids, matrix = attn_index.get_vectors_array([3, 99, 1])
+    assert list(found_ids) == [3, 1]
+    assert matrix.shape == (len(found_ids), 4) and matrix.dtype == np.float32
+    assert np.allclose(matrix[0], [-1, 0, 0, 0]) and np.allclose(matrix[1], [1, 0, 0, 0])
+    empty_ids, empty = attn_index.get_vectors_array([])
+    assert len(empty_ids) == 0 and empty.shape == (0, 4)
+    print(f'✅ Array shape {matrix.shape}\n')
+
+    # 测试49: 恢复前的内存预估
+    print('Test 49: Memory forecast before recovery...')
+    mem_db = './test_memory.db'
+    conn = sqlite3.connect(mem_db)
+    conn.execute('CREATE TABLE diary_files (id INTEGER PRIMARY KEY, diary_name TEXT)')
+    conn.execute('CREATE TABLE chunks (id INTEGER PRIMARY KEY, file_id INTEGER, vector BLOB)')
+    conn.execute("INSERT INTO diary_files VALUES (1, 'big'), (2, 'small')")
+    for i in range(200):
+        conn.execute('INSERT INTO chunks VALUES (?, ?, ?)', (i, 1 if i < 190 else 2, struct.pack('8f', *[random.random() for _ in range(8)])))
+    conn.commit()
+    conn.close()
+    mem_index = VexusIndex(dim=8, capacity=300)
+    assert mem_index.estimate_memory(0) == 0
+    assert mem_index.estimate_memory(1000) > mem_index.estimate_memory(100) > 8 * 4 * 100
+    mem_index.set_memory_limit(mem_index.stats().memory_usage + mem_index.estimate_memory(50))
+    # COUNT 使用同样的日记过滤: small 只有 10 行，可以恢复
+    assert mem_index.recover_from_sqlite(mem_db, 'chunks', 'small').inserted == 10
+    try:
+        mem_index.recover_from_sqlite(mem_db, 'chunks', 'big')
+        assert False, 'forecast over the limit should refuse'
+    except MemoryError:
+        pass
+    assert mem_index.stats().total_vectors == 10
+    assert mem_index.recover_from_sqlite(mem_db, 'chunks', 'big', force=True).inserted == 190
+    mem_index.set_memory_limit(None)
+    os.remove(mem_db)
+    print(f'✅ Forecast for 1000 vectors: {mem_index.estimate_memory(1000)} bytes\n')
+
+    # 测试50: 样本不足时的就绪检查
+    print('Test 50: Readiness and minimum-sample guards...')
+    assert attn_index.is_ready(3) and not attn_index.is_ready(4)
+    assert not VexusIndex(dim=4, capacity=10).is_ready(1)
+    two = struct.pack('8f', 1, 0, 0, 0, 0, 1, 0, 0)
+    assert attn_index.compute_svd(two, 2, 2).k == 2
+    try:
+        attn_index.compute_svd(two, 2, 3)
+        assert False, 'requesting more components than vectors should raise'
+    except ValueError as e:
+        assert 'at least 3 vectors' in str(e)
+    try:
+        attn_index.project(struct.pack('4f', 1, 0, 0, 0), b'', struct.pack('4f', 0, 0, 0, 0), 0)
+        assert False, 'empty basis should raise'
+    except ValueError:
+        pass
+    print('✅ Too few samples raise a clear ValueError\n')
+
+    # 测试51: 交叉注意力
+    print('Test 51: compute_cross_attention...')
+    cross = attn_index.compute_cross_attention([1, 2], [1, 2, 3], 1.0)
+    assert len(cross) == 2 * 4
+    row_weights = attn_index.compute_attention(struct.pack('4f', 1, 0, 0, 0), [1, 2, 3], 1.0)
+    expected_row = attn_index.retrieve_weighted(struct.pack('4f', 1, 0, 0, 0), [1, 2, 3], 1.0)
+    assert all(abs(a - b) < 1e-6 for a, b in zip(cross[:4], expected_row))
+    assert abs(sum(row_weights) - 1.0) < 1e-9
+    assert attn_index.compute_cross_attention([], [1, 2], 1.0) == []
+    try:
+        attn_index.compute_cross_attention([1, 42], [1], 1.0)
+        assert False, 'missing query id should raise'
+    except KeyError:
+        pass
+    print('✅ Cross attention rows match single-query retrieval\n')
+
+    # 测试52: 冷热分层索引
+    print('Test 52: TieredIndex merge, dedupe and demotion...')
+    tiered = TieredIndex(VexusIndex(dim=4, capacity=10), VexusIndex(dim=4, capacity=10))
+    tiered.cold.add_batch([1, 2, 3], struct.pack('12f', 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0))
+    tiered.add(1, struct.pack('4f', 0.9, 0.1, 0, 0))
+    tiered.add(10, struct.pack('4f', 0, 0.9, 0, 0))
+    probe = struct.pack('4f', 1, 0, 0, 0)
+    merged = tiered.search(probe, 3)
+    assert [r.id for r in merged].count(1) == 1
+    assert [r.score for r in merged] == sorted((r.score for r in merged), reverse=True)
+    hot_one = tiered.hot.search(probe, 1)[0]
+    assert next(r for r in merged if r.id == 1).score == hot_one.score
+    boosted = tiered.search(struct.pack('4f', 0, 1, 0, 0), 2, hot_boost=0.5)
+    assert boosted[0].id == 10
+    assert tiered.search(probe, 4, cold_k=1)[-1].id != 3
+    assert tiered.demote([10, 99]) == 1
+    assert tiered.tier_of(10) == 'cold' and tiered.tier_of(1) == 'hot' and tiered.tier_of(99) is None
+    tiered.save('./test_tiered')
+    reloaded = TieredIndex.load(4, 10, './test_tiered')
+    assert reloaded.tier_of(10) == 'cold' and reloaded.tier_of(1) == 'hot'
+    assert [r.id for r in reloaded.search(probe, 3)] == [r.id for r in tiered.search(probe, 3)]
+    capped_tiers = TieredIndex.load(4, 10, './test_tiered', max_results=2)
+    for tier in (capped_tiers.hot, capped_tiers.cold):
+        try:
+            tier.search(probe, 3)
+            assert False, 'k above max_results should raise'
+        except ValueError:
+            pass
+    for suffix in ('.hot', '.hot.meta', '.cold', '.cold.meta'):
+        os.remove('./test_tiered' + suffix)
+    print('✅ Hot copy wins on dedupe, demotion survives save/load\n')
+
+    # 测试53: 情绪强度 (效价轴投影)
+    print('Test 53: compute_emotion_intensity...')
+    emo = VexusIndex(dim=4, capacity=10)
+    emo.add_batch([1, 2, 3, 4], struct.pack('16f', 1, 0, 0, 0, -1, 0, 0, 0, 0.5, 2, 0, 0, -1, 0, 0, 0))
+    assert emo.compute_emotion_intensity(1, 1, 2) == (1.0, 0.0)
+    assert emo.compute_emotion_intensity(2, 1, 2) == (-1.0, 0.0)
+    valence, intensity = emo.compute_emotion_intensity(3, 1, 2)
+    assert abs(valence - 0.5) < 1e-9 and abs(intensity - 2.0) < 1e-9
+    try:
+        emo.compute_emotion_intensity(3, 2, 4)
+        assert False, 'degenerate axis should raise'
+    except ValueError:
+        pass
+    print(f'✅ valence={valence:.2f}, intensity={intensity:.2f}\n')
+
+    # 测试54: 搜索结果写入预分配缓冲区
+    print('Test 54: search_into preallocated buffers...')
+    out_ids = np.zeros(8, dtype=np.uint32)
+    out_scores = np.zeros(8, dtype=np.float32)
+    written = vexus.search_into(query_bytes, 5, out_ids, out_scores)
+    expected = vexus.search(query_bytes, 5)
+    assert written == len(expected)
+    assert list(out_ids[:written]) == [r.id for r in expected]
+    assert np.allclose(out_scores[:written], [r.score for r in expected], atol=1e-5)
+    # vexus 在测试9中已拟合校准: calibrated 分数与命名空间都与 search 一致
+    out_ns = np.full(8, 255, dtype=np.uint8)
+    written = vexus.search_into(query_bytes, 5, out_ids, out_scores, calibrated=True, out_namespaces=out_ns)
+    expected = vexus.search(query_bytes, 5, calibrated=True)
+    assert np.allclose(out_scores[:written], [r.score for r in expected], atol=1e-5)
+    assert list(out_ns[:written]) == [r.namespace for r in expected]
+    try:
+        vexus.search_into(query_bytes, 5, np.zeros(4, dtype=np.uint32), out_scores)
+        assert False, 'short output buffer should raise'
+    except ValueError:
+        pass
+    print(f'✅ Wrote {written} results into reused buffers\n')
+
+    # 测试55: 异常上的稳定错误码
+    print('Test 55: Error codes on raised exceptions...')
+    from vector_db import ERROR_CODES
+
+    def expect_code(code, fn):
+        try:
+            fn()
+        except Exception as e:
+            assert getattr(e, 'code', None) == code, f'expected {code}, got {e!r}'
+            assert isinstance(e, ERROR_CODES[code])
+            return
+        assert False, f'{code} was not raised'
+
+    err_dir = './test_errors'
+    os.makedirs(err_dir, exist_ok=True)
+    err_index = VexusIndex(dim=4, capacity=10)
+    err_index.add(1, struct.pack('4f', 1, 0, 0, 0))
+    err_index.save(f'{err_dir}/ok.usearch')
+    with open(f'{err_dir}/ok.usearch.meta', 'wb') as f:
+        f.write(b'garbage')
+    with open(f'{err_dir}/garbage.db', 'wb') as f:
+        f.write(b'this is not a sqlite database' * 100)
+    tiny_db = f'{err_dir}/tiny.db'
+    conn = sqlite3.connect(tiny_db)
+    conn.execute('CREATE TABLE tags (id INTEGER PRIMARY KEY, vector BLOB)')
+    conn.execute('INSERT INTO tags VALUES (1, ?)', (struct.pack('4f', 1, 2, 3, 4),))
+    conn.commit()
+    conn.close()
+    limited = VexusIndex(dim=4, capacity=10)
+    limited.set_memory_limit(1)
+    closed = VexusIndex(dim=4, capacity=10)
+    closed.close()
+
+    expect_code('E_DIM_MISMATCH', lambda: err_index.search(b'\0' * 8, 1))
+    expect_code('E_INVALID_ARGUMENT', lambda: err_index.search(struct.pack('4f', 1, 0, 0, 0), 1, order='sideways'))
+    expect_code('E_NOT_FOUND', lambda: err_index.compute_attention(struct.pack('4f', 1, 0, 0, 0), [42], 1.0))
+    expect_code('E_CAPACITY', lambda: limited.recover_from_sqlite(tiny_db, 'tags'))
+    expect_code('E_SQLITE', lambda: err_index.recover_from_sqlite(f'{err_dir}/garbage.db', 'tags'))
+    expect_code('E_CORRUPT_FILE', lambda: VexusIndex.load(4, 10, f'{err_dir}/ok.usearch'))
+    expect_code('E_IO', lambda: err_index.save(f'{err_dir}/missing_dir/x.usearch'))
+    expect_code('E_FILE_NOT_FOUND', lambda: err_index.recover_from_sqlite_multi([f'{err_dir}/missing.db'], 'tags', strict=True))
+    expect_code('E_INDEX_CLOSED', lambda: closed.search(struct.pack('4f', 1, 0, 0, 0), 1))
+    expect_code('E_LOCK_TIMEOUT', lambda: search_while_locked(0))
+    # E_INTERNAL (锁中毒 / usearch 内部失败) 与 E_REMOVE (重试耗尽) 无法从 Python 稳定触发，只检查登记
+    assert ERROR_CODES['E_INTERNAL'].code == 'E_INTERNAL' and ERROR_CODES['E_REMOVE'].code == 'E_REMOVE'
+    assert all(cls.code == code for code, cls in ERROR_CODES.items())
+    import shutil
+    shutil.rmtree(err_dir)
+    print(f'✅ {len(ERROR_CODES)} error codes registered and attached\n')
+
+    # 测试56: 只算握手模长
+    print('Test 56: compute_handshake_magnitudes...')
+    hs_tags = struct.pack('128f', *[random.random() for _ in range(128)]) + single_bytes + query_bytes
+    full = vexus.compute_handshakes(query_bytes, hs_tags, 3)
+    mags = vexus.compute_handshake_magnitudes(query_bytes, hs_tags, 3)
+    assert mags == full.magnitudes and mags[2] == 0.0
+    print(f'✅ Magnitudes match full handshake: {[round(m, 3) for m in mags]}\n')
+
+    # 测试57: 唤醒-效价二维坐标
+    print('Test 57: compute_arousal_valence...')
+    emo.add_batch([5, 6, 7], struct.pack('12f', 0, 1, 0, 0, 0, -1, 0, 0, 0.5, 0.5, 0, 0))
+    assert emo.compute_arousal_valence(7, (5, 6), (1, 2)) == (0.5, 0.5)
+    assert emo.compute_arousal_valence(1, (5, 6), (1, 2)) == (0.0, 1.0)
+    try:
+        emo.compute_arousal_valence(7, (6, 6), (1, 2))
+        assert False, 'degenerate arousal axis should raise'
+    except ValueError as e:
+        assert 'Arousal' in str(e)
+    print('✅ Circumplex coordinates computed\n')
+
+    # 测试58: 查询日志环形缓冲区
+    print('Test 58: Query log ring buffer...')
+    logged = VexusIndex(dim=4, capacity=10)
+    logged.add_batch([1, 2, 3], struct.pack('12f', 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0))
+    probe = struct.pack('4f', 1, 0, 0, 0)
+    logged.search(probe, 1)
+    assert logged.query_log() == []
+    logged.enable_query_log(3, store_vectors=True)
+    for k in range(1, 6):
+        logged.search(probe, k, order='worst_first' if k == 5 else 'best_first')
+    entries = logged.query_log()
+    assert [e.k for e in entries] == [3, 4, 5]
+    assert entries[-1].method == 'search' and 'worst_first' in entries[-1].options
+    assert entries[-1].ids == [r.id for r in logged.search(probe, 5, order='worst_first')]
+    assert entries[0].query == [1.0, 0.0, 0.0, 0.0]
+    logged.search_with_forced(probe, 2, [3])
+    assert logged.query_log()[-1].method == 'search_with_forced' and logged.query_log()[-1].ids[0] == 3
+    assert logged.dump_query_log('./test_query_log.jsonl') == 3
+    with open('./test_query_log.jsonl') as f:
+        lines = [json.loads(line) for line in f]
+    assert len(lines) == 3 and lines[-1]['method'] == 'search_with_forced'
+    os.remove('./test_query_log.jsonl')
+    logged.clear_query_log()
+    assert logged.query_log() == []
+    logged.save('./test_logged.usearch')
+    assert VexusIndex.load(4, 10, './test_logged.usearch').query_log() == []
+    os.remove('./test_logged.usearch')
+    os.remove('./test_logged.usearch.meta')
+    print('✅ Oldest entries evicted, log excluded from save/load\n')
+
+    # 测试59: 可复现的 train/test 划分
+    print('Test 59: split_ids...')
+    from vector_db import split_ids
+    all_ids = list(range(1000))
+    train, test = split_ids(all_ids, 0.2, 7)
+    assert (train, test) == split_ids(list(reversed(all_ids)), 0.2, 7)
+    assert split_ids(all_ids, 0.2, 8) != (train, test)
+    assert len(test) == 200 and sorted(train + test) == all_ids
+    assert split_ids(all_ids, 0.0, 7) == (all_ids, [])
+    try:
+        split_ids(all_ids, 1.5, 7)
+        assert False, 'fraction outside [0, 1] should raise'
+    except ValueError:
+        pass
+    print(f'✅ {len(train)} train / {len(test)} test, deterministic per seed\n')
+
+    # 测试60: 语义轨迹
+    print('Test 60: compute_trajectory...')
+    path_index = VexusIndex(dim=4, capacity=10)
+    path_index.add_batch([1, 2, 3, 4], struct.pack('16f', 0, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 2, 2, 0, 0))
+    traj = path_index.compute_trajectory([1, 2, 3, 4])
+    assert traj.velocities == [1.0, 1.0, 2.0] and traj.accelerations == [0.0, 1.0]
+    assert traj.total_path_length == 4.0 and abs(traj.net_displacement - math.sqrt(8)) < 1e-9
+    assert abs(traj.smoothness - (1 - 0.5 / (4 / 3))) < 1e-9
+    assert path_index.compute_trajectory([1, 2, 3]).smoothness == 1.0
+    try:
+        path_index.compute_trajectory([1])
+        assert False, 'a single point should raise'
+    except ValueError:
+        pass
+    print(f'✅ Path length {traj.total_path_length}, smoothness {traj.smoothness:.3f}\n')
+
+    # 测试61: 按命名空间细分的统计
+    print('Test 61: stats_detailed per namespace...')
+    ns_stats = VexusIndex(dim=4, capacity=20)
+    ns_stats.add_batch([1, 2, 3], struct.pack('12f', *[random.random() for _ in range(12)]), id_namespace=1)
+    ns_stats.add_batch([1, 2], struct.pack('8f', *[random.random() for _ in range(8)]), id_namespace=2)
+    ns_stats.add(7, struct.pack('4f', 1, 1, 1, 1))
+    ns_stats.set_side_data(7, timestamp=1700000000)
+    detailed = ns_stats.stats_detailed()
+    assert detailed.total_vectors == 6 and detailed.timestamped == 1
+    assert {ns: st.vectors for ns, st in detailed.namespaces.items()} == {0: 1, 1: 3, 2: 2}
+    assert detailed.namespaces[0].timestamped == 1 and detailed.namespaces[1].timestamped == 0
+    doc = detailed.as_dict()
+    assert doc['namespaces'][1]['vectors'] == 3
+    assert abs(sum(n['memory_bytes'] for n in doc['namespaces'].values()) - doc['memory_usage']) <= 3
+    print(f'✅ Namespaces: { {ns: n["vectors"] for ns, n in doc["namespaces"].items()} }\n')
+
+    # 测试62: 批量 top-1 最近 tag
+    print('Test 62: best_tag...')
+    bt_tags = struct.pack('12f', 1, 0, 0, 0, 0, 1, 0, 0, 0.7, 0.7, 0, 0)
+    bt_queries = struct.pack('12f', 2, 0.1, 0, 0, 0.1, 0.1, 0, 0, 0, 0, 0, 0)
+    tag_idx, sims = attn_index.best_tag(bt_queries, 3, bt_tags, 3)
+    assert tag_idx == [0, 2, 0] and sims[2] == 0.0
+    assert abs(sims[0] - 2 / math.sqrt(4.01)) < 1e-6
+    try:
+        attn_index.best_tag(bt_queries, 3, b'', 0)
+        assert False, 'no tags should raise'
+    except ValueError:
+        pass
+    print(f'✅ Best tags {tag_idx}\n')
+
+    # 测试63: 轨迹平滑
+    print('Test 63: smooth_trajectory...')
+    smoothed = path_index.smooth_trajectory([1, 2, 3, 4], 1)
+    assert smoothed[0] == [0.5, 0.0, 0.0, 0.0] and smoothed[1] == [1.0, 0.0, 0.0, 0.0]
+    assert smoothed[3] == [2.0, 1.0, 0.0, 0.0]
+    assert path_index.smooth_trajectory([1, 2], 0) == [[0.0] * 4, [1.0, 0.0, 0.0, 0.0]]
+    assert path_index.smooth_trajectory([], 2) == []
+    print('✅ Window average computed\n')
+
+    # 测试64: 测试夹具生成 (需以 --features testing 构建，见 make test-vector-db)
+    print('Test 64: testing fixtures...')
+    import vector_db
+    fixtures = getattr(vector_db, 'testing', None)
+    assert fixtures is not None, 'vector_db.testing missing: build with `maturin develop --features testing`'
+    fx_db = './test_fixture.db'
+    fx_index = './test_fixture.usearch'
+    for f in (fx_db, fx_index, fx_index + '.meta'):
+        if os.path.exists(f):
+            os.remove(f)
+    fixtures.make_test_db(fx_db, 5, 12, 8, 42)
+    fx = VexusIndex(8, 32)
+    recovered = fx.recover_from_sqlite(fx_db, 'tags', id_namespace=1).inserted
+    recovered += fx.recover_from_sqlite(fx_db, 'chunks', fixtures.FIXTURE_DIARY, id_namespace=2).inserted
+    assert recovered == 5 + 12 and fx.stats().total_vectors == 17
+    # 维度不符被跳过的行以 warning 级别写入 vector_db 日志器，不再打印到 stdout
+    captured.records.clear()
+    vdb_logger.addHandler(captured)
+    assert VexusIndex(4, 8).recover_from_sqlite(fx_db, 'tags').skipped_dim_mismatch == 5
+    vdb_logger.removeHandler(captured)
+    warnings = [r.getMessage() for r in captured.records if r.levelno == logging.WARNING]
+    assert warnings == ['recover_from_sqlite: skipped 5 vectors due to dimension mismatch (expected 16 bytes)']
+    fixtures.make_test_index(fx_index, 20, 8, 42)
+    fx_loaded = VexusIndex.load(dim=8, capacity=20, index_path=fx_index)
+    assert fx_loaded.stats().total_vectors == 20 and fx_loaded.verify_integrity()
+    for f in (fx_db, fx_index, fx_index + '.meta'):
+        os.remove(f)
+    # 未按 4 字节对齐的缓冲区走拷贝路径，结果与对齐时相同
+    raw = struct.pack('3f', 1.5, -2.0, 3.25)
+    for offset in range(4):
+        assert fixtures.f32_view_at_offset(raw, offset) == [1.5, -2.0, 3.25]
+    print('✅ Fixture DB and index round-trip\n')
+
+    # 测试65: Minkowski 重排搜索
+    print('Test 65: search_minkowski...')
+    mk = VexusIndex(2, 8)
+    mk.add(1, struct.pack('2f', 0.9, 0.9))
+    mk.add(2, struct.pack('2f', 1.5, 0.0))
+    origin = struct.pack('2f', 0.0, 0.0)
+    # L1: id2 (1.5) 比 id1 (1.8) 近；L2: id1 (~1.27) 比 id2 (1.5) 近
+    assert [r.id for r in mk.search_minkowski(origin, 2, 1.0, 8)] == [2, 1]
+    assert [r.id for r in mk.search_minkowski(origin, 2, 2.0, 8)] == [1, 2]
+    assert abs(mk.search_minkowski(origin, 1, 1.0, 8)[0].score - (1.0 - 1.5)) < 1e-6
+    try:
+        mk.search_minkowski(origin, 2, 0.5, 8)
+        assert False, 'p < 1 should raise'
+    except ValueError:
+        pass
+    print('✅ L1 and L2 orderings differ as expected\n')
+
+    # 测试66: 轨迹突变点
+    print('Test 66: detect_change_points...')
+    # 速度 1, 1, 2，平均 4/3
+    assert path_index.detect_change_points([1, 2, 3, 4], 1.2) == [3]
+    assert path_index.detect_change_points([1, 2, 3, 4], 0.5) == [1, 2, 3]
+    assert path_index.detect_change_points([1, 1, 1], 1.0) == []
+    assert path_index.detect_change_points([1], 1.0) == []
+    print('✅ Change points found\n')
+
+    # 测试67: Python 回调重排
+    print('Test 67: search_rerank...')
+    rr = VexusIndex(2, 8)
+    rr.add_batch([1, 2, 3], struct.pack('6f', 0.1, 0.0, 0.5, 0.0, 1.0, 0.0))
+    rr_query = struct.pack('2f', 0.0, 0.0)
+    assert [r.id for r in rr.search(rr_query, 3)] == [1, 2, 3]
+    # 分数 = 距离，最远的排最前；回调内部查询本索引不会死锁
+    inverted = rr.search_rerank(rr_query, 3, 3, lambda i, d: d + 0 * rr.stats().total_vectors)
+    assert [r.id for r in inverted] == [3, 2, 1]
+    assert [r.id for r in rr.search_rerank(rr_query, 2, 3, lambda i, d: -d)] == [1, 2]
+    def bad_scorer(i, d):
+        raise RuntimeError('scorer failed')
+    try:
+        rr.search_rerank(rr_query, 3, 3, bad_scorer)
+        assert False, 'scorer exception should propagate'
+    except RuntimeError as e:
+        assert 'scorer failed' in str(e)
+    print('✅ Rerank honored callback scores\n')
+
+    # 测试68: 墓碑比例与压缩
+    print('Test 68: tombstone_ratio / should_compact / compact...')
+    tb = VexusIndex(2, 16)
+    tb.add_batch(list(range(1, 11)), struct.pack('20f', *[float(x) for x in range(20)]))
+    assert tb.tombstone_ratio == 0.0 and not tb.should_compact(0.5)
+    tb.remove_batch([1, 2, 3])
+    assert abs(tb.tombstone_ratio - 3 / 7) < 1e-9 and not tb.should_compact(0.5)
+    tb.remove_batch([4, 5])
+    assert tb.tombstone_ratio == 1.0 and tb.should_compact(0.5)
+    assert tb.compact() == 5
+    assert tb.tombstone_ratio == 0.0 and not tb.should_compact(0.5)
+    assert tb.stats().total_vectors == 5 and tb.lifetime_stats().last_compaction is not None
+    assert tb.search(struct.pack('2f', 12.0, 13.0), 1)[0].id == 7
+    print('✅ Compaction threshold crossed and reset\n')
+
+    # 测试69: 轨迹自相关
+    print('Test 69: compute_trajectory_autocorrelation...')
+    cyc = VexusIndex(2, 8)
+    cyc.add_batch([1, 2, 3, 4], struct.pack('8f', 0, 0, 1, 0, 1, 1, 0, 1))
+    # 绕正方形两圈: 位移 e1, e2, -e1, -e2 循环，周期为 4
+    acf = cyc.compute_trajectory_autocorrelation([1, 2, 3, 4, 1, 2, 3, 4, 1], 8)
+    assert len(acf) == 8
+    assert abs(acf[0]) < 1e-9 and abs(acf[1] + 1.0) < 1e-9 and abs(acf[3] - 1.0) < 1e-9
+    assert acf[7] == 0.0
+    print('✅ Period-4 cycle detected\n')
+
+    # 测试70: 标签倒排索引过滤搜索
+    print('Test 70: require_labels...')
+    lb = VexusIndex(4, 1000)
+    lb_rng = random.Random(7)
+    lb_ids = list(range(1, 1001))
+    lb_vecs = [lb_rng.uniform(-0.1, 0.1) for _ in range(4 * 1000)]
+    # 稀有标签的两个向量离查询很远
+    lb_vecs[4 * 998:4 * 1000] = [5.0, 5.0, 5.0, 5.0, -5.0, -5.0, -5.0, -5.0]
+    lb.add_batch(lb_ids, struct.pack(f'{len(lb_vecs)}f', *lb_vecs))
+    lb.set_labels(999, ['rare', 'night'])
+    lb.set_labels(1000, ['rare'])
+    lb.set_labels(1, ['common'])
+    lb_query = struct.pack('4f', 0.0, 0.0, 0.0, 0.0)
+    # 过采样再过滤的做法找不到稀有标签
+    assert not {r.id for r in lb.search(lb_query, 50)} & {999, 1000}
+    rare = lb.search(lb_query, 5, require_labels=['rare'])
+    assert sorted(r.id for r in rare) == [999, 1000] and all(r.strategy == 'exact' for r in rare)
+    assert [r.id for r in lb.search(lb_query, 5, require_labels=['rare', 'night'])] == [999]
+    assert lb.search(lb_query, 5, require_labels=['missing']) == []
+    assert lb.search(lb_query, 1)[0].strategy is None
+    assert lb.get_labels(999) == ['night', 'rare']
+    lb.save('./test_labels.usearch')
+    lb2 = VexusIndex.load(dim=4, capacity=1000, index_path='./test_labels.usearch')
+    assert lb2.get_labels(1000) == ['rare']
+    lb2.remove(1000)
+    assert [r.id for r in lb2.search(lb_query, 5, require_labels=['rare'])] == [999]
+    os.remove('./test_labels.usearch')
+    os.remove('./test_labels.usearch.meta')
+    print('✅ Rare label fully recalled\n')
+
+    # 测试71: 原始距离
+    print('Test 71: search(raw=True)...')
+    rawi = VexusIndex(2, 4)
+    rawi.add_batch([1, 2], struct.pack('4f', 3.0, 4.0, 1.0, 0.0))
+    raw_hits = rawi.search(struct.pack('2f', 0.0, 0.0), 2, raw=True)
+    # l2sq: 原始距离为平方欧氏距离，score = 1 - distance
+    assert [(r.id, r.distance) for r in raw_hits] == [(2, 1.0), (1, 25.0)]
+    assert all(abs(r.score - (1.0 - r.distance)) < 1e-9 for r in raw_hits)
+    assert rawi.search(struct.pack('2f', 0.0, 0.0), 1)[0].distance is None
+    print('✅ Raw distances match score transform\n')
+
+    # 测试72: 滑动窗口波动
+    print('Test 72: compute_window_variance...')
+    # path_index: 1=(0,0,0,0) 2=(1,0,0,0) 3=(2,0,0,0) 4=(2,2,0,0)
+    wv = path_index.compute_window_variance([1, 2, 3, 4], 2)
+    assert len(wv) == 3
+    assert abs(wv[0] - 0.5) < 1e-9 and abs(wv[1] - 0.5) < 1e-9 and abs(wv[2] - 1.0) < 1e-9
+    assert path_index.compute_window_variance([1, 1, 1], 3) == [0.0]
+    assert path_index.compute_window_variance([1, 2], 3) == []
+    print('✅ Window variance computed\n')
+
+    # 测试73: 向量哈希登记与 SQLite 分叉检测
+    print('Test 73: vector hashes / verify_against_sqlite...')
+    hv_db = './test_hashes.db'
+    if os.path.exists(hv_db):
+        os.remove(hv_db)
+    hv_conn = sqlite3.connect(hv_db)
+    hv_conn.execute('CREATE TABLE tags (id INTEGER PRIMARY KEY, name TEXT, vector BLOB)')
+    for i in range(1, 5):
+        hv_conn.execute('INSERT INTO tags VALUES (?, ?, ?)', (i, f't{i}', struct.pack('4f', i, 0, 0, 0)))
+    hv_conn.commit()
+    hv = VexusIndex(4, 16)
+    hv.add(99, struct.pack('4f', 1, 1, 1, 1))
+    assert hv.vector_hash(99) is None
+    hv.enable_vector_hashes()
+    # 哈希固定按小端字节计算，与机器字节序及输入的 byte_order 无关
+    assert hv.vector_hash(99) == 0xbbc02cc3f509c9c2
+    hv.add(98, struct.pack('>4f', 1, 1, 1, 1), byte_order='be')
+    assert hv.vector_hash(98) == hv.vector_hash(99)
+    hv.remove(98)
+    assert hv.recover_from_sqlite(hv_db, 'tags').inserted == 4
+    report = hv.verify_against_sqlite(hv_db, 'tags')
+    assert report.checked == 4 and report.mismatched == [] and report.unhashed == 0
+    # SQLite 单边更新 (部分更新 bug)
+    hv_conn.execute('UPDATE tags SET vector = ? WHERE id = 2', (struct.pack('4f', 2, 0.5, 0, 0),))
+    hv_conn.commit()
+    hv_conn.close()
+    assert hv.verify_against_sqlite(hv_db, 'tags').mismatched == [2]
+    assert hv.verify_against_sqlite(hv_db, 'tags', sample=2).checked == 2
+    hv.save('./test_hashes.usearch')
+    hv2 = VexusIndex.load(dim=4, capacity=16, index_path='./test_hashes.usearch')
+    assert hv2.vector_hash(3) == hv.vector_hash(3)
+    assert hv2.verify_against_sqlite(hv_db, 'tags').mismatched == [2]
+    for f in (hv_db, './test_hashes.usearch', './test_hashes.usearch.meta'):
+        os.remove(f)
+    # 插入开销粗测
+    bench_vecs = np.random.rand(20000, 64).astype(np.float32).tobytes()
+    bench_times = []
+    for hashed in (False, True):
+        bench = VexusIndex(64, 20000)
+        if hashed:
+            bench.enable_vector_hashes()
+        t0 = time.time()
+        bench.add_batch(list(range(20000)), bench_vecs)
+        bench_times.append(time.time() - t0)
+    print(f'   add_batch 20000x64: {bench_times[0]:.3f}s plain, {bench_times[1]:.3f}s hashed')
+    print('✅ Divergence detected\n')
+
+    # 测试74: TagSet 缓存
+    print('Test 74: TagSet...')
+    from vector_db import TagSet
+    ts_rng = random.Random(3)
+    ts_tags = [ts_rng.uniform(-1, 1) for _ in range(5 * 4)]
+    ts_tags[8:12] = [2 * x for x in ts_tags[0:4]]  # 第 2 个 tag 与第 0 个线性相关
+    ts_bytes = struct.pack('20f', *ts_tags)
+    ts_query = struct.pack('4f', 0.3, -0.2, 0.9, 0.1)
+    tag_set = TagSet(4, ts_bytes, 5)
+    assert tag_set.n_tags == 5 and tag_set.dim == 4
+    hs_cached = tag_set.handshakes(ts_query, extended=True)
+    hs_direct = attn_index.compute_handshakes(ts_query, ts_bytes, 5, extended=True)
+    assert hs_cached.magnitudes == hs_direct.magnitudes and hs_cached.directions == hs_direct.directions
+    assert hs_cached.tag_norms == hs_direct.tag_norms and hs_cached.relative_magnitudes == hs_direct.relative_magnitudes
+    pr_cached = tag_set.project(ts_query)
+    pr_direct = attn_index.compute_orthogonal_projection(ts_query, ts_bytes, 5)
+    assert pr_cached.projection == pr_direct.projection and pr_cached.basis_coefficients == pr_direct.basis_coefficients
+    assert pr_cached.basis_coefficients[2] == 0.0
+    best_ids, best_sims = attn_index.best_tag(ts_query, 1, ts_bytes, 5)
+    assert tag_set.best(ts_query) == (best_ids[0], best_sims[0])
+    print('✅ TagSet matches uncached methods\n')
+
+    # 测试75: 遗忘曲线加权
+    print('Test 75: search_with_decay...')
+    dc = VexusIndex(2, 4)
+    dc.add_batch([1, 2], struct.pack('4f', 0.1, 0.0, 0.3, 0.0))
+    dc_query = struct.pack('2f', 0.0, 0.0)
+    # 相关度 1 / (1 + dist): id1 更近但已过两个半衰期 (0.25 / 1.01)，id2 刚写入 (1 / 1.09)
+    decayed = dc.search_with_decay(dc_query, 2, [0, 800, 1000], 1000, 100)
+    assert [r.id for r in decayed] == [2, 1]
+    assert abs(decayed[1].score - 0.25 / 1.01) < 1e-6
+    assert [r.id for r in dc.search_with_decay(dc_query, 2, [], 1000, 100)] == [1, 2]
+    dc.set_side_data(1, timestamp=800)
+    assert [r.id for r in dc.search_with_decay(dc_query, 2, [], 1000, 100)] == [2, 1]
+    # 距离大于 1 时 1 - dist 为负，衰减不能让旧记忆反超: id1 (dist 4, 旧) 应排在 id2 (dist 4.41, 新) 之后
+    far = VexusIndex(2, 4)
+    far.add_batch([1, 2], struct.pack('4f', 2.0, 0.0, 2.1, 0.0))
+    far_decayed = far.search_with_decay(dc_query, 2, [0, 0, 1000], 1000, 100)
+    assert [r.id for r in far_decayed] == [2, 1]
+    assert all(0.0 < r.score <= 1.0 for r in far_decayed)
+    print('✅ Old memories decayed\n')
+
+    # 测试76: 流式 SVD
+    print('Test 76: compute_svd_streaming...')
+    sv = VexusIndex(8, 4)
+    sv_rng = np.random.default_rng(11)
+    sv_data = (sv_rng.normal(size=(300, 2)) * [5.0, 3.0]) @ sv_rng.normal(size=(2, 8))
+    sv_data = (sv_data + 0.05 * sv_rng.normal(size=(300, 8))).astype(np.float32)
+    exact = sv.compute_svd(sv_data.tobytes(), 300, 2)
+    approx = sv.compute_svd_streaming(iter([sv_data[i:i + 32].tobytes() for i in range(0, 300, 32)]), 8, 2)
+    assert exact.error_bound is None and approx.k == 2 and approx.error_bound >= 0.0
+    for se, sa in zip(exact.s, approx.s):
+        # compute_svd 用 f32 分解，留 1e-4 的相对容差
+        assert (se ** 2 - approx.error_bound) * (1 - 1e-4) <= sa ** 2 <= se ** 2 * (1 + 1e-4)
+    for i in range(2):
+        ue = np.array(exact.u[i * 8:(i + 1) * 8])
+        ua = np.array(approx.u[i * 8:(i + 1) * 8])
+        assert abs(ue @ ua) > 0.99
+    def broken_chunks():
+        yield sv_data[:10].tobytes()
+        raise RuntimeError('source closed')
+    try:
+        sv.compute_svd_streaming(broken_chunks(), 8, 2)
+        assert False, 'iterator error should propagate'
+    except RuntimeError as e:
+        assert 'source closed' in str(e)
+    print(f'   σ exact {exact.s[0]:.3f}/{exact.s[1]:.3f}, streaming {approx.s[0]:.3f}/{approx.s[1]:.3f}, Δ={approx.error_bound:.4f}')
+    print('✅ Streaming SVD within bound\n')
+
+    # 测试77: 大端字节输入
+    print('Test 77: byte_order="be"...')
+    be = VexusIndex(3, 8)
+    be.add(1, struct.pack('>3f', 1.0, 2.0, 3.0), byte_order='be')
+    be.add_batch([2, 3], struct.pack('>6f', -1.0, 0.5, 0.0, 4.0, 4.0, 4.0), byte_order='be')
+    be_ids, be_matrix = be.get_vectors_array([1, 2, 3])
+    assert be_matrix.tolist() == [[1.0, 2.0, 3.0], [-1.0, 0.5, 0.0], [4.0, 4.0, 4.0]]
+    be_db = './test_be.db'
+    if os.path.exists(be_db):
+        os.remove(be_db)
+    be_conn = sqlite3.connect(be_db)
+    be_conn.execute('CREATE TABLE tags (id INTEGER PRIMARY KEY, name TEXT, vector BLOB)')
+    be_conn.execute('INSERT INTO tags VALUES (10, ?, ?)', ('be', struct.pack('>3f', 0.25, -8.0, 1.5)))
+    be_conn.commit()
+    be_conn.close()
+    assert be.recover_from_sqlite(be_db, 'tags', byte_order='be').inserted == 1
+    assert be.get_vectors_array([10])[1].tolist() == [[0.25, -8.0, 1.5]]
+    os.remove(be_db)
+    try:
+        be.add(4, struct.pack('3f', 1, 1, 1), byte_order='middle')
+        assert False, 'invalid byte_order should raise'
+    except ValueError:
+        pass
+    print('✅ Big-endian input decoded\n')
+
+    # 测试78: 语义 + 时间接近度混合
+    print('Test 78: search_recency_biased...')
+    # dc: id1 相关度 1 / 1.01 (很久以前), id2 相关度 1 / 1.09 (刚写入)
+    rb_times = [(1, 0), (2, 1000)]
+    assert [r.id for r in dc.search_recency_biased(dc_query, 2, rb_times, 1000, 1.0)] == [1, 2]
+    blended = dc.search_recency_biased(dc_query, 2, rb_times, 1000, 0.5)
+    assert [r.id for r in blended] == [2, 1]
+    assert abs(blended[0].score - (0.5 / 1.09 + 0.5 * 1.0)) < 1e-6
+    # 语义项与时间项同在 [0, 1]，远处的向量也不会得到负分
+    far_blended = far.search_recency_biased(dc_query, 2, [(1, 0), (2, 1000)], 1000, 0.5)
+    assert [r.id for r in far_blended] == [2, 1] and all(0.0 <= r.score <= 1.0 for r in far_blended)
+    try:
+        dc.search_recency_biased(dc_query, 2, rb_times, 1000, 1.5)
+        assert False, 'alpha out of range should raise'
+    except ValueError:
+        pass
+    print('✅ Recency blended into ranking\n')
+
+    # 测试79: 分页搜索
+    print('Test 79: search_paged...')
+    from vector_db import CursorExpiredError
+    pg = VexusIndex(2, 32)
+    pg.add_batch(list(range(1, 26)), struct.pack('50f', *[v for i in range(1, 26) for v in (i * 0.1, 0.0)]))
+    pg_query = struct.pack('2f', 0.0, 0.0)
+    pages, cursor = [], None
+    while True:
+        page, cursor = pg.search_paged(pg_query, 4, cursor)
+        pages.extend(page)
+        if cursor is None:
+            break
+    assert [r.id for r in pages] == [r.id for r in pg.search(pg_query, 25)] == list(range(1, 26))
+    # 同距离按 ID 升序
+    tie = VexusIndex(2, 4)
+    tie.add_batch([7, 3, 5], struct.pack('6f', 1.0, 0.0, 0.0, 1.0, -1.0, 0.0))
+    first, tie_cursor = tie.search_paged(pg_query, 2)
+    second, last_cursor = tie.search_paged(pg_query, 2, tie_cursor)
+    assert [r.id for r in first + second] == [3, 5, 7] and last_cursor is None
+    _, pg_cursor = pg.search_paged(pg_query, 4)
+    for bad_query, bad_cursor in ((struct.pack('2f', 1.0, 1.0), pg_cursor), (pg_query, b'garbage')):
+        try:
+            pg.search_paged(bad_query, 4, bad_cursor)
+            assert False, 'bad cursor should raise'
+        except CursorExpiredError as e:
+            assert e.code == 'E_CURSOR_EXPIRED' and isinstance(e, ValueError)
+    # close 之后已发出的游标不能继续翻页
+    _, open_cursor = tie.search_paged(pg_query, 1)
+    tie.close()
+    for closed_cursor in (open_cursor, None):
+        try:
+            tie.search_paged(pg_query, 1, closed_cursor)
+            assert False, 'paging a closed index should raise'
+        except IndexClosedError:
+            pass
+    print('✅ Pages concatenate to the full ranking\n')
+
+    # 测试80: 自适应 k
+    print('Test 80: search_adaptive...')
+    ad_rng = random.Random(80)
+    ad_center = [1.0, 1.0, 1.0, 1.0]
+    ad_vectors = [[c + ad_rng.uniform(-0.01, 0.01) for c in ad_center] for _ in range(5)]
+    ad_vectors += [[ad_rng.uniform(-5.0, -2.0) for _ in range(4)] for _ in range(45)]
+    ad = VexusIndex(4, 64)
+    ad.add_batch(list(range(1, 51)), struct.pack('200f', *[v for vec in ad_vectors for v in vec]))
+    ad_query = struct.pack('4f', *ad_center)
+    for ad_method in ('elbow', 'threshold_knee'):
+        ad_results, ad_cutoff = ad.search_adaptive(ad_query, 20, ad_method)
+        assert ad_cutoff.suggested_k == 5 and sorted(r.id for r in ad_results) == [1, 2, 3, 4, 5]
+        assert len(ad_cutoff.gaps) == 19 and ad_cutoff.method == ad_method
+    # 不足 3 个结果时全部返回
+    assert ad.search_adaptive(ad_query, 2)[1].suggested_k == 2
+    try:
+        ad.search_adaptive(ad_query, 20, 'bogus')
+        assert False, 'unknown method should raise'
+    except ValueError:
+        pass
+    print('✅ Cutoff lands after the relevant cluster\n')
+
+    # 测试81: 搜索时连带取回 SQLite 文本
+    print('Test 81: search_with_text...')
+    txt_db = './test_text.db'
+    if os.path.exists(txt_db):
+        os.remove(txt_db)
+    txt_conn = sqlite3.connect(txt_db)
+    txt_conn.execute('CREATE TABLE chunks (id INTEGER PRIMARY KEY, content TEXT)')
+    txt_conn.executemany('INSERT INTO chunks VALUES (?, ?)', [(1, 'first'), (2, 'second'), (9, 'far')])
+    txt_conn.commit()
+    txt_conn.close()
+    enriched = pg.search_with_text(pg_query, 3, txt_db, 'chunks', 'id', 'content')
+    # ID 3 在库中没有对应行，被跳过
+    assert [(i, text) for i, _, text in enriched] == [(1, 'first'), (2, 'second')]
+    assert [score for _, score, _ in enriched] == [r.score for r in pg.search(pg_query, 2)]
+    try:
+        pg.search_with_text(pg_query, 3, txt_db, 'chunks; DROP TABLE chunks', 'id', 'content')
+        assert False, 'unsafe identifier should raise'
+    except ValueError:
+        pass
+    os.remove(txt_db)
+    print('✅ Text joined in the same call\n')
+
+    # 测试82: 间隔重复复习优先级
+    print('Test 82: compute_review_priority...')
+    day = 86400
+    # ad 中噪声 (ID 6..50) 占多数，簇 (ID 1..5) 位于质心反方向，更难
+    assert ad.compute_review_priority(1, 0, 3, 0.0, 10 * day) > ad.compute_review_priority(6, 0, 3, 0.0, 10 * day)
+    assert ad.compute_review_priority(6, 0, 3, 0.0, 10 * day) > ad.compute_review_priority(6, 0, 3, 0.0, 2 * day)
+    assert ad.compute_review_priority(6, 0, 1, 0.0, 10 * day) > ad.compute_review_priority(6, 0, 4, 0.0, 10 * day)
+    assert ad.compute_review_priority(6, 0, 4, 0.8, 10 * day) > ad.compute_review_priority(6, 0, 4, 0.0, 10 * day)
+    assert ad.compute_review_priority(6, 5 * day, 4, 0.0, 0) == 0.0
+    for bad_args in ((6, 0, 1, 1.5, day), (999, 0, 1, 0.5, day)):
+        try:
+            ad.compute_review_priority(*bad_args)
+            assert False, 'invalid review args should raise'
+        except (ValueError, KeyError):
+            pass
+    print('✅ Harder, older, less-reviewed memories come first\n')
+
+    # 测试83: 单次搜索结果数上限
+    print('Test 83: max_results...')
+    capped = VexusIndex(2, 16, max_results=3)
+    capped.add_batch(list(range(1, 9)), struct.pack('16f', *[v for i in range(1, 9) for v in (i * 0.1, 0.0)]))
+    assert len(capped.search(pg_query, 3)) == 3
+    for call in (lambda: capped.search(pg_query, 4),
+                 lambda: capped.search_paged(pg_query, 4),
+                 lambda: capped.search_minkowski(pg_query, 2, 2.0, 10),
+                 # 批量的总结果数 n*k 同样受限
+                 lambda: capped.search_batch_flat(pg_query * 2, 2, 2),
+                 lambda: capped.search_with_forced(pg_query, 1, [1, 2, 3, 4]),
+                 lambda: capped.search_robust(pg_query, 2, 4, 0.01)):
+        try:
+            call()
+            assert False, 'request above max_results should raise'
+        except ValueError as e:
+            assert 'max_results' in str(e)
+    assert len(capped.search_batch_flat(pg_query * 3, 3, 1)[0]) == 3
+    assert len(capped.search_with_forced(pg_query, 1, [1, 2, 3])) == 3
+    # 多跳扩展到上限即停止
+    assert len(capped.search_multi_hop(1, 5, 3)) <= 3
+    print('✅ Oversized requests rejected\n')
+
+    # 测试84: 标签共现矩阵
+    print('Test 84: compute_cooccurrence_matrix...')
+    co = capped.compute_cooccurrence_matrix([[0, 1], [1, 2, 1], [0, 1, 2], []], 3)
+    assert co == [2.0, 2.0, 1.0,
+                  2.0, 3.0, 2.0,
+                  1.0, 2.0, 2.0]
+    try:
+        capped.compute_cooccurrence_matrix([[0, 3]], 3)
+        assert False, 'out-of-range tag should raise'
+    except ValueError:
+        pass
+    print('✅ Co-occurrence counts symmetric\n')
+
+    # 测试85: 只读共享视图
+    print('Test 85: open_shared...')
+    from vector_db import ReadOnlyIndexError
+    sh_path = './test_shared.usearch'
+    sh_rng = random.Random(85)
+    sh_src = VexusIndex(16, 512)
+    sh_src.add_batch(list(range(1, 501)), struct.pack('8000f', *[sh_rng.uniform(-1, 1) for _ in range(8000)]))
+    sh_src.save(sh_path)
+    sh_loaded = VexusIndex.load(dim=16, capacity=512, index_path=sh_path)
+    sh_a = VexusIndex.open_shared(sh_path)
+    sh_b = VexusIndex.open_shared(sh_path)
+    assert sh_a.is_shared and not sh_loaded.is_shared
+    sh_capped = VexusIndex.open_shared(sh_path, max_results=5)
+    assert len(sh_capped.search(struct.pack('16f', *[0.0] * 16), 5)) == 5
+    try:
+        sh_capped.search(struct.pack('16f', *[0.0] * 16), 6)
+        assert False, 'k above max_results should raise'
+    except ValueError:
+        pass
+    sh_capped.close()
+    sh_query = struct.pack('16f', *[sh_rng.uniform(-1, 1) for _ in range(16)])
+    expected = [r.id for r in sh_loaded.search(sh_query, 10)]
+    assert [r.id for r in sh_a.search(sh_query, 10)] == [r.id for r in sh_b.search(sh_query, 10)] == expected
+    # 视图的向量与图在 mmap 中，堆内存远小于完整加载的一份
+    assert sh_a.stats().memory_usage + sh_b.stats().memory_usage < sh_loaded.stats().memory_usage
+    sh_identity = struct.pack('256f', *[1.0 if r == c else 0.0 for r in range(16) for c in range(16)])
+    for mutate in (lambda: sh_a.add(999, struct.pack('16f', *[0.0] * 16)),
+                   lambda: sh_a.remove(1),
+                   lambda: sh_a.set_labels(1, ['x']),
+                   lambda: sh_a.apply_transform([1], sh_identity, 16, True)):
+        try:
+            mutate()
+            assert False, 'shared view should be read-only'
+        except ReadOnlyIndexError as e:
+            assert e.code == 'E_READ_ONLY'
+    # 不写回的变换只读向量，视图上可用
+    assert sh_a.apply_transform([1, 2], sh_identity, 16, False) == sh_loaded.apply_transform([1, 2], sh_identity, 16, False)
+    # 不能覆盖正被共享视图映射的文件，所有视图释放后可以
+    try:
+        sh_loaded.save(sh_path)
+        assert False, 'save onto a shared file should raise'
+    except ReadOnlyIndexError:
+        pass
+    sh_a.close()
+    import gc
+    del sh_b
+    gc.collect()
+    sh_loaded.save(sh_path)
+    os.remove(sh_path)
+    os.remove(sh_path + '.meta')
+    print('✅ Shared views search without duplicating memory\n')
+
+    # 测试86: 分数衰减曲线
+    print('Test 86: score_profile...')
+    profile = sh_loaded.score_profile(sh_query, 20)
+    assert len(profile) == 20 and all(a >= b for a, b in zip(profile, profile[1:]))
+    assert profile == [r.score for r in sh_loaded.search(sh_query, 20)]
+    # 拟合校准后 calibrated=True 与 search 使用同一映射
+    vexus_profile = vexus.score_profile(query_bytes, 5, calibrated=True)
+    assert vexus_profile == [r.score for r in vexus.search(query_bytes, 5, calibrated=True)]
+    assert vexus_profile != vexus.score_profile(query_bytes, 5)
+    print('✅ Profile is monotonically non-increasing\n')
+
+    # 测试87: PMI 标签嵌入
+    print('Test 87: compute_pmi_embedding...')
+    pmi_entries = [[0, 1]] * 6 + [[2, 3]] * 6 + [[0, 2], [1, 3]]
+    pmi_co = capped.compute_cooccurrence_matrix(pmi_entries, 4)
+    emb = capped.compute_pmi_embedding(pmi_co, 4, 2)
+    assert len(emb) == 8 and all(math.isfinite(x) for x in emb)
+    rows = [emb[i * 2:(i + 1) * 2] for i in range(4)]
+    def emb_cos(a, b):
+        return sum(x * y for x, y in zip(a, b)) / (math.hypot(*a) * math.hypot(*b))
+    assert emb_cos(rows[0], rows[1]) > emb_cos(rows[0], rows[2])
+    assert emb_cos(rows[2], rows[3]) > emb_cos(rows[1], rows[2])
+    try:
+        capped.compute_pmi_embedding(pmi_co, 4, 5)
+        assert False, 'k_pmi > n_tags should raise'
+    except ValueError:
+        pass
+    print('✅ Tags used together embed together\n')
+
+    # 测试88: 全零向量在数学方法中的行为
+    print('Test 88: zero vectors in math routines...')
+    zm = VexusIndex(4, 4)
+    zero = struct.pack('4f', 0, 0, 0, 0)
+    unit = struct.pack('4f', 1, 0, 0, 0)
+    zm_tags = struct.pack('12f', 1, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0)
+    zm_basis = struct.pack('8f', 1, 0, 0, 0, 0, 1, 0, 0)
+
+    def no_nan(*values):
+        for v in values:
+            if v is None:
+                continue
+            for x in (v if isinstance(v, (list, tuple)) else [v]):
+                assert not math.isnan(x), values
+
+    for q in (zero, unit):
+        r = zm.compute_orthogonal_projection(q, zm_tags, 3)
+        no_nan(r.projection, r.residual, r.basis_coefficients)
+        h = zm.compute_handshakes(q, zm_tags, 3, extended=True)
+        no_nan(h.magnitudes, h.directions, h.relative_magnitudes, h.query_norm, h.tag_norms)
+        p = zm.project(q, zm_basis, zero, 2)
+        no_nan(p.projections, p.probabilities, p.entropy, p.total_energy)
+        no_nan(zm.best_tag(q, 1, zm_tags, 3)[1])
+        no_nan(zm.compute_handshake_magnitudes(q, zm_tags, 3))
+        no_nan(zm.classify_epa(q, 1, zm_basis, zero, 2))
+        ts = TagSet(4, zm_tags, 3)
+        th = ts.handshakes(q, True)
+        no_nan(th.magnitudes, th.directions, th.relative_magnitudes, th.query_norm, th.tag_norms)
+        tp = ts.project(q)
+        no_nan(tp.projection, tp.residual, tp.basis_coefficients, ts.best(q))
+    # 索引里存了全零向量时，基于 ID 的数学方法同样不产生 NaN
+    for zid, v in ((1, zero), (2, zero), (3, unit)):
+        zm.add(zid, v)
+    for q in (zero, unit):
+        no_nan(zm.compute_attention(q, [1, 2, 3], 1.0), zm.retrieve_weighted(q, [1, 2, 3], 1.0))
+        no_nan(zm.in_span(q, 1, zm_tags, 3, 1e-6))
+    no_nan(zm.compute_cross_attention([1, 3], [1, 2, 3], 1.0))
+    traj = zm.compute_trajectory([1, 2, 3])
+    no_nan(traj.velocities, traj.accelerations, traj.total_path_length, traj.net_displacement, traj.smoothness)
+    no_nan(zm.compute_trajectory_autocorrelation([1, 2, 3, 1], 2))
+    for rows, n_rows in ((zero * 3, 3), (zero + zero + unit, 3)):
+        svd0 = zm.compute_svd(rows, n_rows, 2)
+        no_nan(svd0.u, svd0.s, svd0.error_bound)
+        no_nan([x for row in zm.component_loadings(struct.pack(f'{len(svd0.u)}f', *svd0.u), svd0.k, 2) for _, x in row])
+    no_nan(list(zm.corpus_health(10, 0).as_dict().values()))
+    assert zm.project(zero, zm_basis, zero, 2).entropy == 0.0
+    assert zm.best_tag(zero, 1, zm_tags, 3) == ([0], [0.0])
+    assert zm.classify_epa(zero + unit, 2, zm_basis, zero, 2) == [0, 0]
+    # 全零 tag / 基向量在 skip_zero_vectors 下不参与比较，返回原下标
+    zm_skip_tags = struct.pack('8f', 0, 0, 0, 0, 0, 1, 0, 0)
+    assert zm.best_tag(zero, 1, zm_skip_tags, 2, skip_zero_vectors=True) == ([1], [0.0])
+    assert zm.classify_epa(zero, 1, zm_skip_tags, zero, 2, skip_zero_vectors=True) == [1]
+    assert zm.compute_handshake_magnitudes(unit, zm_tags, 3, skip_zero_vectors=True) == \
+        zm.compute_handshakes(unit, zm_tags, 3, skip_zero_vectors=True).magnitudes
+    ts = TagSet(4, zm_tags, 3, skip_zero_vectors=True)
+    assert ts.n_tags == 2 and ts.skipped_rows == [1] and ts.handshakes(unit).skipped_rows == [1]
+    assert ts.best(struct.pack('4f', 0, 1, 0, 0)) == (2, 1.0)
+    # skip_zero_vectors 去掉全零 tag 并报告下标
+    r = zm.compute_orthogonal_projection(unit, zm_tags, 3, skip_zero_vectors=True)
+    assert r.skipped_rows == [1] and len(r.basis_coefficients) == 2
+    h = zm.compute_handshakes(unit, zm_tags, 3, skip_zero_vectors=True)
+    assert h.skipped_rows == [1] and len(h.magnitudes) == 2
+    assert zm.compute_handshakes(unit, zm_tags, 3).skipped_rows == []
+    # strict 直接报错
+    for strict_call in (lambda: zm.compute_orthogonal_projection(unit, zm_tags, 3, strict=True),
+                        lambda: zm.compute_handshakes(zero, zm_basis, 2, strict=True),
+                        lambda: zm.project(zero, zm_basis, zero, 2, strict=True),
+                        lambda: zm.best_tag(zero, 1, zm_basis, 2, strict=True),
+                        lambda: zm.classify_epa(zero, 1, zm_basis, zero, 2, strict=True),
+                        lambda: zm.compute_handshake_magnitudes(unit, zm_tags, 3, strict=True),
+                        lambda: zm.best_tag(unit, 1, zm_skip_tags, 2, skip_zero_vectors=True, strict=True),
+                        lambda: zm.best_tag(unit, 1, zero, 1, skip_zero_vectors=True),
+                        lambda: TagSet(4, zm_tags, 3, strict=True),
+                        lambda: TagSet(4, zm_basis, 2).best(zero, strict=True),
+                        lambda: TagSet(4, zm_basis, 2).project(zero, strict=True)):
+        try:
+            strict_call()
+            assert False, 'strict mode should reject zero vectors'
+        except ValueError:
+            pass
+    print('✅ Zero vectors never produce NaN\n')
+
+    # 测试89: Sinkhorn 最优传输距离
+    print('Test 89: compute_sinkhorn_distance...')
+    assert abs(path_index.compute_sinkhorn_distance([1], [4], 0.05, 50) - math.sqrt(8)) < 1e-6
+    assert path_index.compute_sinkhorn_distance([1, 2], [1, 2], 0.05, 100) < 1e-6
+    assert abs(path_index.compute_sinkhorn_distance([1, 2], [2, 3], 0.05, 100) - 1.0) < 1e-6
+    assert path_index.compute_sinkhorn_distance([1, 2], [3, 4], 0.05, 100) > 1.0
+    for bad in (([], [1], 0.1, 10), ([1], [2], 0.0, 10), ([1], [2], 0.1, 0)):
+        try:
+            path_index.compute_sinkhorn_distance(*bad)
+            assert False, 'invalid sinkhorn args should raise'
+        except ValueError:
+            pass
+    print('✅ Transport cost matches hand-computed EMD\n')
+
+    # 测试90: 写入归一化并记录原始模长
+    print('Test 90: enable_normalization / get_norm...')
+    nm = VexusIndex(3, 8)
+    nm.add(9, struct.pack('3f', 1, 1, 1))
+    nm.enable_normalization(record_norms=True)
+    nm.add(1, struct.pack('3f', 3, 4, 0))
+    nm.add_batch([2, 3], struct.pack('6f', 0, 0, 2, 0, 0, 0))
+    assert abs(nm.get_norm(1) - 5.0) < 1e-6 and abs(nm.get_norm(2) - 2.0) < 1e-6
+    assert nm.get_norm(3) == 0.0 and nm.get_norm(9) is None and nm.get_norm(42) is None
+    # 存入的是单位向量
+    top = nm.search(struct.pack('3f', 0.6, 0.8, 0), 1)[0]
+    assert top.id == 1 and abs(top.score - 1.0) < 1e-5
+    nm.save('./test_norms.usearch')
+    nm_loaded = VexusIndex.load(dim=3, capacity=8, index_path='./test_norms.usearch')
+    assert abs(nm_loaded.get_norm(1) - 5.0) < 1e-6
+    nm_loaded.add(4, struct.pack('3f', 0, 10, 0))
+    assert abs(nm_loaded.get_norm(4) - 10.0) < 1e-6
+    nm_loaded.remove(1)
+    assert nm_loaded.get_norm(1) is None
+    # 重写向量的操作会破坏单位长度，开启归一化时拒绝执行
+    identity3 = struct.pack('9f', 1, 0, 0, 0, 1, 0, 0, 0, 1)
+    for rewrite in (lambda: nm_loaded.recenter(), lambda: nm_loaded.apply_transform([4], identity3, 3, True)):
+        try:
+            rewrite()
+            assert False, 'rewriting stored vectors should be refused while normalization is on'
+        except ValueError:
+            pass
+    assert nm_loaded.apply_transform([4], identity3, 3, False) == [[0.0, 1.0, 0.0]]
+    os.remove('./test_norms.usearch')
+    os.remove('./test_norms.usearch.meta')
+    print('✅ Original norms survive normalization and reload\n')
+
+    # 测试91: 完整性自检
+    print('Test 91: self_check...')
+    ok_report = pg.self_check(10, seed=1)
+    assert ok_report.passed and ok_report.failures == [] and ok_report.sampled == 10 and ok_report.queries_run == 8
+    assert ok_report.index_size == ok_report.key_count == 25
+    # 模拟不干净的关机: 侧车登记的 ID 比索引里多
+    sc_small, sc_big = VexusIndex(2, 8), VexusIndex(2, 8)
+    sc_small.add_batch([1, 2, 3], struct.pack('6f', *[0.1 * i for i in range(6)]))
+    sc_big.add_batch([1, 2, 3, 4, 5], struct.pack('10f', *[0.1 * i for i in range(10)]))
+    sc_small.save('./test_selfcheck.usearch')
+    sc_big.save('./test_selfcheck_big.usearch')
+    os.replace('./test_selfcheck_big.usearch.meta', './test_selfcheck.usearch.meta')
+    broken = VexusIndex.load(dim=2, capacity=8, index_path='./test_selfcheck.usearch')
+    report = broken.self_check(10)
+    assert not report.passed and not report.size_ok and report.missing_keys == [4, 5]
+    assert report.dimensions_ok and report.queries_ok and len(report.failures) == 2
+    assert broken.stats().total_vectors == 3
+    for f in ('./test_selfcheck.usearch', './test_selfcheck.usearch.meta', './test_selfcheck_big.usearch'):
+        os.remove(f)
+    print('✅ Self-check pinpoints sidecar/index drift\n')
+
+    # 测试92: 批量子空间判定
+    print('Test 92: in_span...')
+    # 基: xy 平面 (第二个 tag 与第一个不正交，由 Gram-Schmidt 处理)
+    span_tags = struct.pack('8f', 1, 0, 0, 0, 1, 1, 0, 0)
+    span_queries = struct.pack('16f', 3, -2, 0, 0,  1, 1, 0.5, 0,  0, 0, 0, 0,  2, 2, 1e-4, 0)
+    assert zm.in_span(span_queries, 4, span_tags, 2, 1e-3) == [True, False, True, True]
+    assert zm.in_span(span_queries, 4, span_tags, 2, 1e-6)[3] is False
+    try:
+        zm.in_span(span_queries, 4, span_tags, 2, 0.0)
+        assert False, 'non-positive tol should raise'
+    except ValueError:
+        pass
+    print('✅ Span membership matches the known basis\n')
+
+    # 测试93: 离散 Fréchet 距离
+    print('Test 93: compute_frechet_distance...')
+    # path_index: 1=(0,0) 2=(1,0) 3=(2,0) 4=(2,2)
+    assert path_index.compute_frechet_distance([1, 2, 3], [1, 2, 3]) == 0.0
+    assert abs(path_index.compute_frechet_distance([1], [4]) - math.sqrt(8)) < 1e-9
+    # 终点必须对齐: 2 与 4 的距离 sqrt(5) 是瓶颈
+    assert abs(path_index.compute_frechet_distance([1, 2], [1, 3, 4]) - math.sqrt(5)) < 1e-9
+    assert path_index.compute_frechet_distance([1, 3], [1, 2, 3]) == 1.0
+    try:
+        path_index.compute_frechet_distance([], [1])
+        assert False, 'empty sequence should raise'
+    except ValueError:
+        pass
+    print('✅ Leash length matches hand-computed walks\n')
+
+    # 测试94: 按相似度加权抽样
+    print('Test 94: sample_weighted...')
+    top3 = [r.id for r in pg.search(pg_query, 3)]
+    for seed in range(20):
+        cold = pg.sample_weighted(pg_query, 3, 1e-6, 10, seed)
+        assert sorted(r.id for r in cold) == sorted(top3)
+    picks = pg.sample_weighted(pg_query, 5, 0.5, 10, 7)
+    assert len({r.id for r in picks}) == 5
+    assert [r.id for r in picks] == [r.id for r in pg.sample_weighted(pg_query, 5, 0.5, 10, 7)]
+    by_id = {r.id: r.score for r in pg.search(pg_query, 10)}
+    assert all(r.score == by_id[r.id] for r in picks)
+    # 高温: 候选池内近似均匀 (每个 ID 期望 200 次，标准差约 13)
+    hot_counts = {}
+    for seed in range(2000):
+        picked = pg.sample_weighted(pg_query, 1, 1e6, 10, seed)[0].id
+        hot_counts[picked] = hot_counts.get(picked, 0) + 1
+    assert sorted(hot_counts) == list(range(1, 11))
+    assert all(140 < c < 260 for c in hot_counts.values()), hot_counts
+    try:
+        pg.sample_weighted(pg_query, 11, 1.0, 10)
+        assert False, 'n > pool should raise'
+    except ValueError:
+        pass
+    print('✅ Sampling spans top-n to uniform\n')
+
+    # 测试95: 主成分载荷
+    print('Test 95: component_loadings...')
+    sparse_components = struct.pack('8f', 0, -0.8, 0, 0.6,  0, 0, 1, 0)
+    loadings = zm.component_loadings(sparse_components, 2, 2)
+    assert loadings[0] == [(1, struct.unpack('f', struct.pack('f', -0.8))[0]), (3, struct.unpack('f', struct.pack('f', 0.6))[0])]
+    assert loadings[1] == [(2, 1.0), (0, 0.0)]
+    assert len(zm.component_loadings(sparse_components, 2, 10)[0]) == 4
+    print('✅ Loadings pick the sparse component\'s dimensions\n')
+
+    # 测试96: 动态时间规整
+    print('Test 96: compute_dtw...')
+    # path_index: 1=(0,0) 2=(1,0) 3=(2,0) 4=(2,2)
+    assert path_index.compute_dtw([1, 2, 3], [1, 2, 3]) == (0.0, [(0, 0), (1, 1), (2, 2)])
+    # 代价相同时优先对角线: (0,0) -> (0,1) -> (1,2)，累计 |1-2| = 1
+    assert path_index.compute_dtw([1, 3], [1, 2, 3]) == (1.0, [(0, 0), (0, 1), (1, 2)])
+    dist, warp = path_index.compute_dtw([1], [3, 4])
+    assert abs(dist - (2.0 + math.sqrt(8))) < 1e-9 and warp == [(0, 0), (0, 1)]
+    try:
+        path_index.compute_dtw([1], [])
+        assert False, 'empty sequence should raise'
+    except ValueError:
+        pass
+    print('✅ Warping path and cost match hand-computed alignment\n')
+
+    # 测试97: 预读与预热
+    print('Test 97: prefetch and warmup...')
+    from vector_db import prefetch
+    warm = VexusIndex(dim=4, capacity=20)
+    warm.add_batch(list(range(10)), struct.pack('40f', *[random.random() for _ in range(40)]))
+    warm.save('./test_warm.usearch')
+    read_bytes, read_ms = prefetch('./test_warm.usearch')
+    assert read_bytes == os.path.getsize('./test_warm.usearch') and read_ms >= 0.0
+    try:
+        prefetch('./test_warm_missing.usearch')
+        assert False, 'prefetch of a missing file should raise'
+    except RuntimeError:
+        pass
+    report = warm.warmup(6, seed=1)
+    assert report.n_queries == 6 and len(report.latencies_ms) == 6
+    assert report.first_ms == report.latencies_ms[0] and report.improvement > 0.0
+    assert warm.warmup(3, sample=struct.pack('4f', 1, 0, 0, 0)).n_queries == 3
+    assert VexusIndex(dim=4, capacity=4).warmup(5).n_queries == 0
+    os.remove('./test_warm.usearch')
+    os.remove('./test_warm.usearch.meta')
+    print('✅ Prefetch read the whole file, warmup ran every query\n')
+
+    # 测试98: 多跳搜索沿近邻链扩展
+    print('Test 98: search_multi_hop on a chain...')
+    # 数轴上等距排列的 10 个点，ID i 在 (i, 0)
+    chain = VexusIndex(dim=2, capacity=20)
+    for i in range(10):
+        chain.add(i, struct.pack('2f', float(i), 0.0))
+    hops = chain.search_multi_hop(0, 2, 2)
+    assert [(r.id, r.hop) for r in hops] == [(1, 1), (2, 1), (3, 2)]
+    assert [r.score for r in hops] == [1.0 - 1.0, 1.0 - 4.0, 1.0 - 9.0]
+    assert [(r.id, r.hop) for r in chain.search_multi_hop(0, 3, 2)][-1] == (4, 3)
+    print('✅ Each hop reaches one step further along the chain\n')
+
+    # 测试99: 强制包含的 ID 排在最前
+    print('Test 99: search_with_forced pins anchors...')
+    origin2 = struct.pack('2f', 0.0, 0.0)
+    forced = chain.search_with_forced(origin2, 3, [9])
+    assert [r.id for r in forced] == [9, 0, 1]
+    assert forced[0].score == 1.0 - 81.0
+    assert [r.id for r in chain.search_with_forced(origin2, 3, [1, 9])] == [1, 9, 0]
+    print('✅ Forced IDs come first with their real scores, the rest is filled by nearest neighbours\n')
+
+    # 测试100: 逐维度解释
+    print('Test 100: explain per-dimension contributions...')
+    probe2 = struct.pack('2f', 0.0, 2.0)
+    ex = chain.explain(probe2, 3, 2)
+    # (0, 2) 与 (3, 0): 第 0 维贡献 -9，第 1 维贡献 -4
+    assert ex.id == 3 and ex.dims == [0, 1] and ex.contributions == [-9.0, -4.0]
+    assert ex.total == -13.0 and ex.score == 1.0 - 13.0
+    assert chain.explain(probe2, 3, 1).dims == [0]
+    hits = chain.search(probe2, 2)
+    assert [(e.id, e.total) for e in chain.explain_results(probe2, hits)] == [(r.id, r.score - 1.0) for r in hits]
+    try:
+        chain.explain(probe2, 42, 1)
+        assert False, 'missing id should raise KeyError'
+    except KeyError:
+        pass
+    print('✅ Contributions sum to the distance and are ranked by magnitude\n')
+
+    # 测试101: 多样性采样
+    print('Test 101: sample_diverse keeps points apart...')
+    for min_gap in (2.0, 3.0, 4.5):
+        spread = chain.sample_diverse(10, min_gap, 0)
+        assert spread and len(set(spread)) == len(spread)
+        assert all(abs(a - b) >= min_gap for a in spread for b in spread if a != b), (min_gap, spread)
+    assert len(chain.sample_diverse(2, 1.0, 0)) == 2
+    assert chain.sample_diverse(5, 1.0, 3) == chain.sample_diverse(5, 1.0, 3)
+    print('✅ Every sampled pair is at least min_distance apart\n')
+
+    # 测试102: 蓄水池采样
+    print('Test 102: sample_random is uniform and reproducible...')
+    picked = chain.sample_random(3, 5)
+    assert len(picked) == 3 and len(set(picked)) == 3 and set(picked) <= set(range(10))
+    assert chain.sample_random(3, 5) == picked
+    assert sorted(chain.sample_random(20, 5)) == list(range(10))
+    # 每个 ID 被抽中的频率接近 3/10
+    hits_by_id = [0] * 10
+    for trial in range(2000):
+        for key in chain.sample_random(3, trial):
+            hits_by_id[key] += 1
+    assert all(abs(h / 2000 - 0.3) < 0.05 for h in hits_by_id), hits_by_id
+    print('✅ Same seed, same sample; every ID equally likely\n')
+
+    # 测试103: 多探针鲁棒搜索
+    print('Test 103: search_robust stability...')
+    near_origin = struct.pack('2f', 0.1, 0.0)
+    robust = chain.search_robust(near_origin, 2, 8, 0.01, 1)
+    assert [r.id for r in robust] == [0, 1]
+    assert all(r.stability == 1.0 for r in robust) and [r.mean_rank for r in robust] == [0.0, 1.0]
+    # 查询落在两点正中间时，微小扰动就会改变第一名，两者的名次都不再稳定
+    midway = chain.search_robust(struct.pack('2f', 0.5, 0.0), 1, 32, 0.05, 1)
+    assert {r.id for r in midway} <= {0, 1} and all(r.stability < 1.0 for r in midway)
+    print('✅ Stable matches score stability 1, ambiguous ones less\n')
+
+    # 测试104: 分层采样
+    print('Test 104: sample_stratified is balanced...')
+    classes = {i: (0 if i < 6 else 1) for i in range(10)}
+    strat = chain.sample_stratified(classes, 2, 0)
+    assert len(strat) == 4 and len(set(strat)) == 4
+    assert all(classes[i] == 0 for i in strat[:2]) and all(classes[i] == 1 for i in strat[2:])
+    assert chain.sample_stratified(classes, 2, 0) == strat
+    try:
+        chain.sample_stratified(classes, 5, 0)
+        assert False, 'class 1 has only 4 members'
+    except ValueError:
+        pass
+    print('✅ n_per_class drawn from every class\n')
+
+    # 测试105: 标签传播
+    print('Test 105: propagate_labels from two seeds...')
+    spread_labels = chain.propagate_labels({0: 5, 9: 7}, 2, 5)
+    assert set(spread_labels) == set(range(10))
+    assert spread_labels[0] == 5 and spread_labels[9] == 7
+    assert all(spread_labels[i] == 5 for i in (1, 2, 3)) and all(spread_labels[i] == 7 for i in (6, 7, 8))
+    # 只传播一层时只覆盖种子的直接近邻
+    assert set(chain.propagate_labels({0: 5}, 2, 1)) == {0, 1, 2}
+    print('✅ Labels flow outward from the nearest seed\n')
+
+    # 测试106: 加权 k-NN 标签聚合
+    print('Test 106: aggregate_labels...')
+    votes = chain.aggregate_labels(4, [3, 5, 9], [1, 2, 2], False)
+    assert votes.keys() == {1, 2} and abs(votes[1] - 1 / 3) < 1e-12 and abs(votes[2] - 2 / 3) < 1e-12
+    # 按 1 / L2sq 加权: ID 3、5 距离 1，ID 9 距离 25，远处的 9 几乎不计票
+    weighted = chain.aggregate_labels(4, [3, 5, 9], [1, 2, 2], True)
+    assert abs(weighted[1] - 1 / (2 + 1 / 25)) < 1e-6 and abs(sum(weighted.values()) - 1.0) < 1e-12
+    print('✅ Label probabilities sum to 1, inverse-distance weighting discounts far neighbours\n')
+
+    # 测试107: 零样本分类
+    print('Test 107: classify_zero_shot...')
+    zs = chain.classify_zero_shot(struct.pack('2f', 1.0, 0.0), struct.pack('4f', 0, 1, 1, 0), 2, ['calm', 'joy'])
+    # 余弦相似度 (0, 1) 经 softmax: joy 的概率为 e / (e + 1)
+    assert [label for label, _ in zs] == ['joy', 'calm']
+    assert abs(zs[0][1] - math.e / (math.e + 1)) < 1e-12 and abs(zs[0][1] + zs[1][1] - 1.0) < 1e-12
+    try:
+        chain.classify_zero_shot(struct.pack('2f', 1.0, 0.0), struct.pack('4f', 0, 1, 1, 0), 2, ['only_one'])
+        assert False, 'label count must match n_classes'
+    except ValueError:
+        pass
+    print('✅ Softmax over prototype similarities, most likely label first\n')
+
+    # 测试108: 按标签求原型向量
+    print('Test 108: compute_prototypes...')
+    protos = chain.compute_prototypes([0, 1, 8, 9], [1, 1, 2, 2])
+    assert protos == {1: [0.5, 0.0], 2: [8.5, 0.0]}
+    try:
+        chain.compute_prototypes([0, 1], [1])
+        assert False, 'ids and labels must have the same length'
+    except ValueError:
+        pass
+    print('✅ Prototype is the mean of each label\'s vectors\n')
+
     # 最终统计
     print('Final stats:')
     final_stats = vexus.stats()
@@ -89,6 +2128,7 @@ try:
     # 清理测试文件
     try:
         os.remove('./test_index.usearch')
+        os.remove('./test_index.usearch.meta')
         print('🧹 Cleaned up test files')
     except:
         pass